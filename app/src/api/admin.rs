@@ -0,0 +1,329 @@
+use crate::api::guest;
+use crate::websocket::types::ConnectionManager;
+use axum::{
+    Router,
+    extract::{Path, State},
+    routing::{delete, get, post, put},
+};
+use common::request::admin::{
+    AnnounceRequest, BanRequest, BansQuery, HandleReportRequest, ReportsQuery,
+};
+use common::response::admin::{
+    AnnounceResponse, BanItem, BansResponse, ConnectionItem, ConnectionsResponse, ReportItem,
+    ReportsResponse,
+};
+use common::utils::response::ApiResponse;
+use common::validator::json::ValidatedJson;
+use common::validator::query::ValidatedQuery;
+use database::entity::sys_report::ReportStatus;
+use database::repository::sys_ban_repository::{self, NewBan};
+use database::repository::sys_report_repository;
+use middleware_fn::auth::CurrentUser;
+use middleware_fn::permission::require_permission;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 举报和封禁列表默认每页条数
+const DEFAULT_PAGE_SIZE: u64 = 20;
+
+/// 管理接口路由，每个接口按权限校验（需要挂载认证中间件）
+pub fn set_admin_api(connection_manager: Arc<ConnectionManager>) -> Router {
+    Router::new()
+        .route(
+            "/announce",
+            post(announce).route_layer(require_permission("announcements:send")),
+        )
+        .route(
+            "/connections",
+            get(connections).route_layer(require_permission("connections:read")),
+        )
+        .route(
+            "/connections/{client_id}",
+            delete(kick).route_layer(require_permission("connections:kick")),
+        )
+        .route(
+            "/reports",
+            get(reports).route_layer(require_permission("reports:read")),
+        )
+        .route(
+            "/reports/{id}",
+            put(handle_report).route_layer(require_permission("reports:handle")),
+        )
+        .route(
+            "/bans",
+            get(bans)
+                .route_layer(require_permission("bans:read"))
+                .merge(post(ban).route_layer(require_permission("bans:manage"))),
+        )
+        .route(
+            "/bans/{id}",
+            delete(lift_ban).route_layer(require_permission("bans:manage")),
+        )
+        .with_state(connection_manager)
+}
+
+/// 向所有在线连接发送系统公告
+pub async fn announce(
+    State(connection_manager): State<Arc<ConnectionManager>>,
+    user: CurrentUser,
+    ValidatedJson(payload): ValidatedJson<AnnounceRequest>,
+) -> ApiResponse<AnnounceResponse> {
+    let timestamp = now();
+
+    let delivered = connection_manager
+        .announce(&payload.message, timestamp)
+        .await;
+    tracing::info!("管理员 {} 的公告已发送给 {} 个连接", user.id, delivered);
+
+    ApiResponse::success(AnnounceResponse {
+        delivered,
+        timestamp,
+    })
+}
+
+/// 在线连接列表（集群模式下包含所有节点）
+pub async fn connections(
+    State(connection_manager): State<Arc<ConnectionManager>>,
+) -> ApiResponse<ConnectionsResponse> {
+    let clients = connection_manager
+        .list_clients()
+        .await
+        .into_iter()
+        .map(|client| ConnectionItem {
+            id: client.id,
+            connected_at: client.connected_at,
+        })
+        .collect::<Vec<_>>();
+
+    ApiResponse::success(ConnectionsResponse {
+        online_count: clients.len(),
+        clients,
+    })
+}
+
+/// 断开指定客户端的连接
+pub async fn kick(
+    State(connection_manager): State<Arc<ConnectionManager>>,
+    user: CurrentUser,
+    Path(client_id): Path<String>,
+) -> ApiResponse<()> {
+    connection_manager
+        .disconnect(&client_id, "你已被管理员断开连接")
+        .await;
+    tracing::info!("管理员 {} 断开了客户端 {}", user.id, client_id);
+
+    ApiResponse::success_with_message((), "已断开连接")
+}
+
+/// 举报列表，按ID倒序分页，可按处理状态过滤
+pub async fn reports(
+    ValidatedQuery(query): ValidatedQuery<ReportsQuery>,
+) -> ApiResponse<ReportsResponse> {
+    if database::get_db().is_none() {
+        return ApiResponse::error(503, "未配置用户数据库");
+    }
+    let status = match query.status.as_deref() {
+        None => None,
+        Some(status) => match ReportStatus::parse(status) {
+            Some(status) => Some(status),
+            None => return ApiResponse::error(400, "未知的举报状态"),
+        },
+    };
+
+    // 多取一条用于判断是否还有更多
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let mut records = match sys_report_repository::find_page(status, query.before, limit + 1).await
+    {
+        Ok(records) => records,
+        Err(e) => {
+            tracing::error!("查询举报失败: {}", e);
+            return ApiResponse::error(500, "查询举报失败");
+        }
+    };
+    let has_more = records.len() as u64 > limit;
+    records.truncate(limit as usize);
+
+    let reports = records
+        .into_iter()
+        .map(|report| ReportItem {
+            id: report.id,
+            reporter: report.reporter,
+            target: report.target,
+            session_id: report.session_id,
+            reason: report.reason,
+            detail: report.detail,
+            status: report.status.as_str().to_string(),
+            handled_by: report.handled_by,
+            handled_at: report.handled_at,
+            note: report.note,
+            created_at: report.created_at.timestamp(),
+        })
+        .collect();
+
+    ApiResponse::success(ReportsResponse { reports, has_more })
+}
+
+/// 处理待处理的举报（标记为已处理或驳回），需要封禁时另行调用封禁接口
+pub async fn handle_report(
+    user: CurrentUser,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<HandleReportRequest>,
+) -> ApiResponse<()> {
+    let admin_id = match admin_id(&user) {
+        Ok(admin_id) => admin_id,
+        Err((code, message)) => return ApiResponse::error(code, message),
+    };
+    let status = match ReportStatus::parse(&payload.status) {
+        Some(status @ (ReportStatus::Resolved | ReportStatus::Dismissed)) => status,
+        _ => return ApiResponse::error(400, "处理结果只能是 resolved 或 dismissed"),
+    };
+
+    match sys_report_repository::handle(id, status, admin_id, payload.note, now() as i64).await {
+        Ok(true) => {
+            tracing::info!(
+                "管理员 {} 将举报 {} 标记为 {}",
+                admin_id,
+                id,
+                status.as_str()
+            );
+            ApiResponse::success_with_message((), "举报已处理")
+        }
+        Ok(false) => ApiResponse::error(404, "举报不存在或已处理"),
+        Err(e) => {
+            tracing::error!("处理举报 {} 失败: {}", id, e);
+            ApiResponse::error(500, "处理举报失败")
+        }
+    }
+}
+
+/// 生效中的封禁列表，按ID倒序分页
+pub async fn bans(ValidatedQuery(query): ValidatedQuery<BansQuery>) -> ApiResponse<BansResponse> {
+    if database::get_db().is_none() {
+        return ApiResponse::error(503, "未配置用户数据库");
+    }
+
+    // 多取一条用于判断是否还有更多
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let mut records =
+        match sys_ban_repository::find_active_page(now() as i64, query.before, limit + 1).await {
+            Ok(records) => records,
+            Err(e) => {
+                tracing::error!("查询封禁失败: {}", e);
+                return ApiResponse::error(500, "查询封禁失败");
+            }
+        };
+    let has_more = records.len() as u64 > limit;
+    records.truncate(limit as usize);
+
+    let bans = records
+        .into_iter()
+        .map(|ban| BanItem {
+            id: ban.id,
+            target: ban.target,
+            ip: ban.ip,
+            reason: ban.reason,
+            created_by: ban.created_by,
+            expires_at: ban.expires_at,
+            created_at: ban.created_at.timestamp(),
+        })
+        .collect();
+
+    ApiResponse::success(BansResponse { bans, has_more })
+}
+
+/// 封禁客户端并断开其连接，封禁期间拒绝其令牌、登录、刷新令牌和 websocket 连接
+pub async fn ban(
+    State(connection_manager): State<Arc<ConnectionManager>>,
+    user: CurrentUser,
+    ValidatedJson(payload): ValidatedJson<BanRequest>,
+) -> ApiResponse<BanItem> {
+    let admin_id = match admin_id(&user) {
+        Ok(admin_id) => admin_id,
+        Err((code, message)) => return ApiResponse::error(code, message),
+    };
+    let target = payload.target.trim().to_string();
+    if target == user.id {
+        return ApiResponse::error(400, "不能封禁自己");
+    }
+
+    let Ok(expires_at) = payload
+        .duration
+        .map(|duration| i64::try_from(now().saturating_add(duration)))
+        .transpose()
+    else {
+        return ApiResponse::error(400, "封禁时长无效");
+    };
+    // 游客重新领取身份即可换一个ID，同时封禁其领取身份时的IP
+    let ip = guest::issued_ip(&target).await;
+    let record = NewBan {
+        target: target.clone(),
+        ip,
+        reason: payload.reason.trim().to_string(),
+        created_by: admin_id,
+        expires_at,
+    };
+    let ban = match sys_ban_repository::create(record).await {
+        Ok(ban) => ban,
+        Err(e) => {
+            tracing::error!("封禁客户端 {} 失败: {}", target, e);
+            return ApiResponse::error(500, "封禁失败");
+        }
+    };
+
+    connection_manager.disconnect(&target, "你已被封禁").await;
+    tracing::info!(
+        "管理员 {} 封禁了客户端 {}（封禁 {}，IP {:?}）",
+        admin_id,
+        target,
+        ban.id,
+        ban.ip
+    );
+
+    ApiResponse::success(BanItem {
+        id: ban.id,
+        target: ban.target,
+        ip: ban.ip,
+        reason: ban.reason,
+        created_by: ban.created_by,
+        expires_at: ban.expires_at,
+        created_at: ban.created_at.timestamp(),
+    })
+}
+
+/// 解除封禁
+pub async fn lift_ban(user: CurrentUser, Path(id): Path<i32>) -> ApiResponse<()> {
+    let admin_id = match admin_id(&user) {
+        Ok(admin_id) => admin_id,
+        Err((code, message)) => return ApiResponse::error(code, message),
+    };
+
+    match sys_ban_repository::lift(id, admin_id, now() as i64).await {
+        Ok(true) => {
+            tracing::info!("管理员 {} 解除了封禁 {}", admin_id, id);
+            ApiResponse::success_with_message((), "已解除封禁")
+        }
+        Ok(false) => ApiResponse::error(404, "封禁不存在或已失效"),
+        Err(e) => {
+            tracing::error!("解除封禁 {} 失败: {}", id, e);
+            ApiResponse::error(500, "解除封禁失败")
+        }
+    }
+}
+
+/// 处理举报和封禁需要记录操作的管理员，记录保存在数据库中
+fn admin_id(user: &CurrentUser) -> Result<i32, (i32, &'static str)> {
+    let Some(admin_id) = user.user_id() else {
+        return Err((403, "没有操作权限"));
+    };
+    if database::get_db().is_none() {
+        return Err((503, "未配置用户数据库"));
+    }
+    Ok(admin_id)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -0,0 +1,319 @@
+use crate::storage::Attachments;
+use crate::storage::imaging::{self, ProcessedImage};
+use crate::websocket::session::SessionManager;
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+    http::{HeaderValue, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use common::request::attachment::UploadQuery;
+use common::response::attachment::UploadResponse;
+use common::utils::response::ApiResponse;
+use common::validator::query::ValidatedQuery;
+use kernel::config::storage_config;
+use middleware_fn::auth::CurrentUser;
+use std::sync::Arc;
+
+/// 附件接口共享状态
+#[derive(Clone)]
+pub struct AttachmentState {
+    pub attachments: Arc<Attachments>,
+    pub sessions: Arc<SessionManager>,
+}
+
+/// 附件接口路由
+pub fn set_attachment_api(state: AttachmentState) -> Router {
+    // multipart 的边界和字段头会占用少量额外空间
+    let body_limit = storage_config().max_size + 64 * 1024;
+
+    Router::new()
+        .route("/", post(upload).layer(DefaultBodyLimit::max(body_limit)))
+        .route("/{file_id}", get(download))
+        .with_state(state)
+}
+
+/// 上传附件（只有进行中会话的参与者可以上传），文件类型按内容识别
+pub async fn upload(
+    State(state): State<AttachmentState>,
+    user: CurrentUser,
+    ValidatedQuery(query): ValidatedQuery<UploadQuery>,
+    mut multipart: Multipart,
+) -> ApiResponse<UploadResponse> {
+    match state.sessions.get(&query.session_id).await {
+        Some(session) if session.is_participant(&user.id) => {}
+        Some(_) => return ApiResponse::error(403, "无权向该会话上传附件"),
+        None => return ApiResponse::error(404, "会话不存在或已结束"),
+    }
+
+    let data = match read_file_field(&mut multipart).await {
+        Ok(data) => data,
+        Err(e) => return ApiResponse::error(400, &e),
+    };
+
+    let config = storage_config();
+    if data.is_empty() {
+        return ApiResponse::error(400, "文件不能为空");
+    }
+    if data.len() > config.max_size {
+        return ApiResponse::error(413, "文件过大");
+    }
+    let Some(mime) = infer::get(&data)
+        .map(|kind| kind.mime_type())
+        .filter(|mime| config.allowed_types.iter().any(|t| t == mime))
+    else {
+        return ApiResponse::error(415, "不支持的文件类型");
+    };
+
+    // 图片去除元数据并生成缩略图，解码在阻塞线程中进行
+    let (data, thumbnail, dimensions) = if mime.starts_with("image/") {
+        match tokio::task::spawn_blocking(move || imaging::process_image(&data, mime)).await {
+            Ok(Ok(ProcessedImage {
+                data,
+                width,
+                height,
+                thumbnail,
+            })) => (data, Some(thumbnail), Some((width, height))),
+            Ok(Err(e)) => return ApiResponse::error(422, &e),
+            Err(e) => {
+                tracing::error!("图片处理任务失败: {}", e);
+                return ApiResponse::error(500, "图片处理失败");
+            }
+        }
+    } else {
+        (data, None, None)
+    };
+
+    let key = format!("{}/{}", query.session_id, uuid::Uuid::new_v4());
+    let size = data.len();
+    if let Err(e) = state.attachments.store.put(&key, data).await {
+        tracing::error!("保存附件 {} 失败: {}", key, e);
+        return ApiResponse::error(500, "保存附件失败");
+    }
+
+    let thumbnail_id = match thumbnail {
+        Some(thumbnail) => {
+            let thumbnail_key = format!("{}.thumb", key);
+            let thumbnail_size = thumbnail.len();
+            if let Err(e) = state.attachments.store.put(&thumbnail_key, thumbnail).await {
+                tracing::error!("保存缩略图 {} 失败: {}", thumbnail_key, e);
+                return ApiResponse::error(500, "保存附件失败");
+            }
+            let (thumbnail_id, _) = state.attachments.signer.sign(
+                &thumbnail_key,
+                &query.session_id,
+                "image/jpeg",
+                thumbnail_size,
+            );
+            Some(thumbnail_id)
+        }
+        None => None,
+    };
+
+    let (file_id, expires_at) = state
+        .attachments
+        .signer
+        .sign(&key, &query.session_id, mime, size);
+    tracing::info!(
+        "用户 {} 上传附件 {}（{}，{} 字节）",
+        user.id,
+        key,
+        mime,
+        size
+    );
+
+    ApiResponse::success(UploadResponse {
+        file_id,
+        mime: mime.to_string(),
+        size,
+        expires_at,
+        thumbnail_id,
+        width: dimensions.map(|(width, _)| width),
+        height: dimensions.map(|(_, height)| height),
+    })
+}
+
+/// 读取名为 file 的表单字段
+async fn read_file_field(multipart: &mut Multipart) -> Result<Bytes, String> {
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| format!("表单解析失败: {}", e))?
+    {
+        if field.name() == Some("file") {
+            return field
+                .bytes()
+                .await
+                .map_err(|e| format!("读取文件失败: {}", e));
+        }
+    }
+
+    Err("缺少 file 字段".to_string())
+}
+
+/// 下载附件（只有附件所属会话的参与者可以下载）
+pub async fn download(
+    State(state): State<AttachmentState>,
+    user: CurrentUser,
+    Path(file_id): Path<String>,
+) -> Response {
+    let claims = match state.attachments.signer.verify(&file_id) {
+        Ok(claims) => claims,
+        Err(e) => return ApiResponse::<()>::error(403, &e).into_response(),
+    };
+    match state.sessions.get(&claims.session_id).await {
+        Some(session) if session.is_participant(&user.id) => {}
+        _ => return ApiResponse::<()>::error(403, "无权下载该附件").into_response(),
+    }
+
+    let data = match state.attachments.store.get(&claims.key).await {
+        Ok(Some(data)) => data,
+        Ok(None) => return ApiResponse::<()>::error(404, "附件不存在").into_response(),
+        Err(e) => {
+            tracing::error!("读取附件 {} 失败: {}", claims.key, e);
+            return ApiResponse::<()>::error(500, "读取附件失败").into_response();
+        }
+    };
+
+    let content_type = HeaderValue::from_str(&claims.mime)
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (
+                header::CACHE_CONTROL,
+                HeaderValue::from_static("private, max-age=3600"),
+            ),
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ),
+        ],
+        Body::from(data),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{file_id::FileIdSigner, local::LocalBlobStore};
+    use axum::extract::{FromRequest, Request};
+    use kernel::auth::token::{Claims, TokenKind};
+
+    fn state() -> AttachmentState {
+        let root = std::env::temp_dir().join(format!("attachments-{}", uuid::Uuid::new_v4()));
+        AttachmentState {
+            attachments: Arc::new(Attachments {
+                store: Arc::new(LocalBlobStore::new(root.to_str().unwrap())),
+                signer: FileIdSigner::new(b"test-secret", 3600),
+            }),
+            sessions: Arc::new(SessionManager::new()),
+        }
+    }
+
+    /// 认证中间件校验令牌后放入请求扩展的用户
+    fn user(sub: &str) -> CurrentUser {
+        CurrentUser {
+            id: sub.to_string(),
+            claims: Claims {
+                sub: sub.to_string(),
+                kind: TokenKind::Guest,
+                jti: uuid::Uuid::new_v4().to_string(),
+                sid: None,
+                ip: None,
+                iss: "test".to_string(),
+                iat: 0,
+                exp: u64::MAX,
+            },
+            account: None,
+        }
+    }
+
+    async fn code_of(response: Response) -> i64 {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()["code"]
+            .as_i64()
+            .unwrap()
+    }
+
+    async fn multipart() -> Multipart {
+        let body = "--b\r\nContent-Disposition: form-data; name=\"file\"\r\n\r\nx\r\n--b--\r\n";
+        let request = Request::builder()
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=b")
+            .body(Body::from(body))
+            .unwrap();
+        Multipart::from_request(request, &()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn upload_requires_participant() {
+        let state = state();
+        let session = state.sessions.start("a", "b", false).await.unwrap();
+
+        let query = UploadQuery {
+            session_id: session.id.clone(),
+        };
+        let response = upload(
+            State(state.clone()),
+            user("c"),
+            ValidatedQuery(query),
+            multipart().await,
+        )
+        .await;
+        assert_eq!(response.code, 403);
+
+        let query = UploadQuery {
+            session_id: "missing".to_string(),
+        };
+        let response = upload(
+            State(state),
+            user("a"),
+            ValidatedQuery(query),
+            multipart().await,
+        )
+        .await;
+        assert_eq!(response.code, 404);
+    }
+
+    #[tokio::test]
+    async fn download_requires_participant() {
+        let state = state();
+        let session = state.sessions.start("a", "b", false).await.unwrap();
+        let key = format!("{}/file", session.id);
+        state
+            .attachments
+            .store
+            .put(&key, Bytes::from_static(b"data"))
+            .await
+            .unwrap();
+        let (file_id, _) = state
+            .attachments
+            .signer
+            .sign(&key, &session.id, "image/png", 4);
+
+        let response = download(State(state.clone()), user("c"), Path(file_id.clone())).await;
+        assert_eq!(code_of(response).await, 403);
+
+        let response = download(State(state), user("b"), Path(file_id)).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"data");
+    }
+
+    #[tokio::test]
+    async fn download_rejects_forged_file_id() {
+        let state = state();
+        let session = state.sessions.start("a", "b", false).await.unwrap();
+        let key = format!("{}/file", session.id);
+        let (forged, _) = FileIdSigner::new(b"other", 3600).sign(&key, &session.id, "image/png", 4);
+
+        let response = download(State(state), user("a"), Path(forged)).await;
+        assert_eq!(code_of(response).await, 403);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::websocket::session::SessionManager;
+use axum::{
+    Router,
+    extract::{Path, State},
+    routing::get,
+};
+use common::request::chat::TranscriptQuery;
+use common::response::chat::{ChatMessageItem, TranscriptResponse};
+use common::utils::response::ApiResponse;
+use common::validator::query::ValidatedQuery;
+use database::repository::chat_message_repository;
+use middleware_fn::auth::CurrentUser;
+use std::sync::Arc;
+
+/// 默认每页返回的消息条数
+const DEFAULT_PAGE_SIZE: u64 = 50;
+
+/// 聊天记录接口路由
+pub fn set_chat_api(sessions: Arc<SessionManager>) -> Router {
+    Router::new()
+        .route("/sessions/{session_id}/messages", get(transcript))
+        .with_state(sessions)
+}
+
+/// 获取进行中会话的聊天记录（只有会话双方可以查看）
+pub async fn transcript(
+    State(sessions): State<Arc<SessionManager>>,
+    user: CurrentUser,
+    Path(session_id): Path<String>,
+    ValidatedQuery(query): ValidatedQuery<TranscriptQuery>,
+) -> ApiResponse<TranscriptResponse> {
+    let Some(session) = sessions.get(&session_id).await else {
+        return ApiResponse::error(404, "会话不存在或已结束");
+    };
+    if !session.is_participant(&user.id) {
+        return ApiResponse::error(403, "无权查看该会话");
+    }
+    if database::get_db().is_none() {
+        return ApiResponse::error(503, "未开启消息存储");
+    }
+
+    // 多取一条用于判断是否还有更早的消息
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let mut records = match chat_message_repository::find_by_session(
+        &session.id,
+        query.before,
+        limit + 1,
+    )
+    .await
+    {
+        Ok(records) => records,
+        Err(e) => {
+            tracing::error!("查询会话 {} 聊天记录失败: {}", session.id, e);
+            return ApiResponse::error(500, "查询聊天记录失败");
+        }
+    };
+    let has_more = records.len() as u64 > limit;
+    if has_more {
+        records.remove(0);
+    }
+
+    let messages = records
+        .into_iter()
+        .map(|record| ChatMessageItem {
+            seq: record.id,
+            id: record.message_id,
+            from: record.sender,
+            to: record.receiver,
+            r#type: record.message_type,
+            text: record.text,
+            timestamp: record.timestamp,
+        })
+        .collect();
+
+    ApiResponse::success(TranscriptResponse {
+        session_id: session.id,
+        participants: session.participants.to_vec(),
+        messages,
+        has_more,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::auth::token::{Claims, TokenKind};
+
+    fn user(sub: &str) -> CurrentUser {
+        CurrentUser {
+            id: sub.to_string(),
+            claims: Claims {
+                sub: sub.to_string(),
+                kind: TokenKind::Guest,
+                jti: uuid::Uuid::new_v4().to_string(),
+                sid: None,
+                ip: None,
+                iss: "test".to_string(),
+                iat: 0,
+                exp: u64::MAX,
+            },
+            account: None,
+        }
+    }
+
+    fn query() -> ValidatedQuery<TranscriptQuery> {
+        ValidatedQuery(TranscriptQuery {
+            before: None,
+            limit: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn transcript_requires_participant() {
+        let sessions = Arc::new(SessionManager::new());
+        let session = sessions.start("a", "b", false).await.unwrap();
+
+        let response = transcript(
+            State(sessions.clone()),
+            user("c"),
+            Path(session.id.clone()),
+            query(),
+        )
+        .await;
+        assert_eq!(response.code, 403);
+
+        let response =
+            transcript(State(sessions), user("a"), Path("missing".into()), query()).await;
+        assert_eq!(response.code, 404);
+    }
+}
@@ -0,0 +1,109 @@
+use common::response::guest::GuestResponse;
+use common::utils::response::ApiResponse;
+use kernel::auth::token::TokenService;
+use kernel::config::auth_config;
+use kernel::redis::get_redis_pool;
+use kernel::redis::service::RedisService;
+use kernel::throttle;
+use middleware_fn::auth::check_ban;
+use middleware_fn::client_ip::ClientIp;
+
+/// 随机昵称的形容词部分
+const ADJECTIVES: [&str; 16] = [
+    "快乐的",
+    "安静的",
+    "勇敢的",
+    "迷路的",
+    "好奇的",
+    "慵懒的",
+    "机智的",
+    "害羞的",
+    "热情的",
+    "神秘的",
+    "温柔的",
+    "倔强的",
+    "认真的",
+    "爱笑的",
+    "失眠的",
+    "路过的",
+];
+/// 随机昵称的名词部分
+const NOUNS: [&str; 16] = [
+    "熊猫", "狐狸", "企鹅", "海豚", "猫咪", "柴犬", "松鼠", "刺猬", "考拉", "鲸鱼", "兔子", "水獭",
+    "仓鼠", "河马", "羊驼", "浣熊",
+];
+
+/// 游客身份领取时的IP，封禁游客时一并封禁该IP
+fn guest_ip_key(guest_id: &str) -> String {
+    format!("auth:guest_ip:{}", guest_id)
+}
+
+/// 游客领取身份时的IP，未配置 redis 或记录已过期时为空
+pub async fn issued_ip(guest_id: &str) -> Option<String> {
+    get_redis_pool().ok()?;
+    RedisService::get(&guest_ip_key(guest_id))
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("读取游客 {} 的领取IP失败: {}", guest_id, e);
+            None
+        })
+}
+
+/// 签发匿名游客身份，无需注册即可连接 websocket，按IP限制领取频率
+///
+/// 被封禁的游客所在的IP不能再领取新的游客身份
+pub async fn create_guest(ClientIp(ip): ClientIp) -> ApiResponse<GuestResponse> {
+    let config = auth_config();
+    let throttle_key = format!("guest:{}", ip);
+    if !throttle::allow(
+        &throttle_key,
+        config.guest_rate_limit,
+        config.guest_rate_window,
+    )
+    .await
+    {
+        tracing::warn!("IP {} 领取游客身份过于频繁", ip);
+        return ApiResponse::error(429, "领取过于频繁，请稍后再试");
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let ip = ip.to_string();
+    if let Err(rejection) = check_ban(&id, Some(&ip)).await {
+        tracing::warn!("已封禁的 IP {} 尝试领取游客身份", ip);
+        return ApiResponse::error(rejection.code, &rejection.message);
+    }
+    let (token, claims) = match TokenService::global().issue_guest(&id, config.guest_token_ttl, &ip)
+    {
+        Ok(issued) => issued,
+        Err(e) => {
+            tracing::error!("签发游客令牌失败: {}", e);
+            return ApiResponse::error(500, "签发游客令牌失败");
+        }
+    };
+    // 记录领取IP，封禁游客时按ID查找
+    if get_redis_pool().is_ok()
+        && let Err(e) =
+            RedisService::set(&guest_ip_key(&id), &ip, Some(config.guest_token_ttl)).await
+    {
+        tracing::error!("记录游客 {} 的领取IP失败: {}", id, e);
+    }
+    tracing::info!("IP {} 领取游客身份 {}", ip, id);
+
+    ApiResponse::success(GuestResponse {
+        id,
+        nickname: random_nickname(),
+        avatar_seed: uuid::Uuid::new_v4().simple().to_string()[..16].to_string(),
+        token,
+        token_type: "Bearer".to_string(),
+        expires_at: claims.exp,
+    })
+}
+
+/// 随机昵称，如「好奇的水獭1024」
+fn random_nickname() -> String {
+    let bytes = uuid::Uuid::new_v4().into_bytes();
+    let adjective = ADJECTIVES[bytes[0] as usize % ADJECTIVES.len()];
+    let noun = NOUNS[bytes[1] as usize % NOUNS.len()];
+    let suffix = u16::from_be_bytes([bytes[2], bytes[3]]) % 10000;
+    format!("{}{}{}", adjective, noun, suffix)
+}
@@ -1,4 +1,12 @@
+pub mod admin;
+pub mod attachment;
 pub mod case;
+pub mod chat;
+pub mod guest;
+pub mod password;
+pub mod register;
+pub mod report;
+pub mod session;
 pub mod system;
 
 
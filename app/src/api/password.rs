@@ -0,0 +1,281 @@
+use crate::api::system::{SystemState, end_all_sessions};
+use axum::extract::State;
+use common::request::system::{ForgotPasswordRequest, ResetPasswordRequest};
+use common::utils::response::ApiResponse;
+use common::validator::json::ValidatedJson;
+use database::entity::sys_user::UserStatus;
+use database::repository::sys_user_repository;
+use kernel::auth::password;
+use kernel::config::auth_config;
+use kernel::mail::{self, Email};
+use kernel::redis::service::RedisService;
+use kernel::redis::{RedisResult, get_redis_pool};
+use kernel::throttle;
+use middleware_fn::client_ip::ClientIp;
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+
+/// 单个IP每小时可提交的找回密码或重置密码请求数
+const RESET_IP_LIMIT: u32 = 10;
+/// 单个邮箱每小时可发送的重置邮件数
+const RESET_EMAIL_LIMIT: u32 = 3;
+
+/// 邮箱是否已注册使用同一个提示
+const RESET_ACCEPTED: &str = "如果该邮箱已注册，重置密码邮件已发送，请查收";
+
+/// 重置令牌，只保存令牌的哈希，值为用户ID
+fn reset_token_key(token: &str) -> String {
+    let digest = Sha256::digest(token.as_bytes());
+    let hash: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("auth:reset:{}", hash)
+}
+
+/// 找回密码和重置密码共用的IP计数
+fn reset_ip_key(ip: &IpAddr) -> String {
+    format!("reset:ip:{}", ip)
+}
+
+/// 找回密码：向已注册的邮箱发送一次性重置令牌
+///
+/// 查询账号、保存令牌和发送邮件都在后台完成，无论邮箱是否已注册、邮件是否发送成功都立即返回相同的结果，
+/// 不能通过响应内容或耗时判断邮箱是否已注册
+pub async fn forgot_password(
+    ClientIp(ip): ClientIp,
+    ValidatedJson(payload): ValidatedJson<ForgotPasswordRequest>,
+) -> ApiResponse<()> {
+    if database::get_db().is_none() || get_redis_pool().is_err() {
+        return ApiResponse::error(503, "找回密码功能需要配置数据库和 Redis");
+    }
+
+    let email = payload.email.trim().to_lowercase();
+    if !throttle::allow(&reset_ip_key(&ip), RESET_IP_LIMIT, 3600).await
+        || !throttle::allow(&format!("reset:email:{}", email), RESET_EMAIL_LIMIT, 3600).await
+    {
+        return ApiResponse::error(429, "操作过于频繁，请稍后再试");
+    }
+
+    tokio::spawn(send_reset_email(email));
+
+    ApiResponse::success_with_message((), RESET_ACCEPTED)
+}
+
+/// 向已注册且未禁用的邮箱发送重置令牌，失败只记录日志
+async fn send_reset_email(email: String) {
+    let user = match sys_user_repository::find_by_email(&email).await {
+        Ok(Some(user)) if user.status != UserStatus::Disabled => user,
+        Ok(_) => return,
+        Err(e) => {
+            tracing::error!("查询用户失败: {}", e);
+            return;
+        }
+    };
+
+    let config = auth_config();
+    let token = match issue_reset_token(user.id, config.password_reset_ttl).await {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("保存重置令牌失败: {}", e);
+            return;
+        }
+    };
+
+    let link = match config.password_reset_url.as_str() {
+        "" => String::new(),
+        url => format!("\n\n重置链接：{}?token={}", url, token),
+    };
+    let email_message = Email {
+        to: email,
+        subject: "重置密码".to_string(),
+        body: format!(
+            "你的重置令牌是 {}，{} 分钟内有效且只能使用一次。{}\n\n如果不是你本人操作，请忽略本邮件，你的密码不会改变。",
+            token,
+            config.password_reset_ttl / 60,
+            link
+        ),
+    };
+    if let Err(e) = mail::global().send(&email_message).await {
+        tracing::error!("发送重置密码邮件失败: {}", e);
+        return;
+    }
+    tracing::info!("用户 {} 申请重置密码", user.id);
+}
+
+/// 为用户生成重置令牌并保存，返回发给用户的令牌
+async fn issue_reset_token(user_id: i32, ttl: u64) -> RedisResult<String> {
+    let token = reset_token();
+    RedisService::set(&reset_token_key(&token), &user_id.to_string(), Some(ttl)).await?;
+    Ok(token)
+}
+
+/// 取出重置令牌对应的用户ID，取出的同时删除，令牌只能使用一次
+async fn take_reset_token(token: &str) -> RedisResult<Option<i32>> {
+    let value = RedisService::get_del(&reset_token_key(token)).await?;
+    Ok(value.and_then(|value| value.parse::<i32>().ok()))
+}
+
+/// 使用重置令牌设置新密码，成功后吊销该用户所有已签发的令牌并断开其 websocket
+pub async fn reset_password(
+    State(state): State<SystemState>,
+    ClientIp(ip): ClientIp,
+    ValidatedJson(payload): ValidatedJson<ResetPasswordRequest>,
+) -> ApiResponse<()> {
+    if database::get_db().is_none() || get_redis_pool().is_err() {
+        return ApiResponse::error(503, "找回密码功能需要配置数据库和 Redis");
+    }
+
+    if !throttle::allow(&reset_ip_key(&ip), RESET_IP_LIMIT, 3600).await {
+        return ApiResponse::error(429, "操作过于频繁，请稍后再试");
+    }
+
+    let user_id = match take_reset_token(payload.token.trim()).await {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            tracing::error!("读取重置令牌失败: {}", e);
+            return ApiResponse::error(500, "重置失败，请稍后再试");
+        }
+    };
+    let user = match user_id {
+        Some(id) => sys_user_repository::find_by_id(id).await,
+        None => return ApiResponse::error(400, "重置令牌无效或已过期"),
+    };
+    let user = match user {
+        Ok(Some(user)) if user.status != UserStatus::Disabled => user,
+        Ok(_) => return ApiResponse::error(400, "重置令牌无效或已过期"),
+        Err(e) => {
+            tracing::error!("查询用户失败: {}", e);
+            return ApiResponse::error(500, "重置失败，请稍后再试");
+        }
+    };
+
+    let new_password = payload.password;
+    let hashed = tokio::task::spawn_blocking(move || password::hash_password(&new_password)).await;
+    let hash = match hashed {
+        Ok(Ok(hash)) => hash,
+        Ok(Err(e)) => {
+            tracing::error!("{}", e);
+            return ApiResponse::error(500, "重置失败，请稍后再试");
+        }
+        Err(e) => {
+            tracing::error!("密码哈希任务失败: {}", e);
+            return ApiResponse::error(500, "重置失败，请稍后再试");
+        }
+    };
+
+    if let Err(e) = sys_user_repository::update_password(user.id, hash).await {
+        tracing::error!("更新用户 {} 的密码失败: {}", user.id, e);
+        return ApiResponse::error(500, "重置失败，请稍后再试");
+    }
+    // 通过邮件收到令牌即证明拥有该邮箱，待验证的账号一并激活
+    if user.status == UserStatus::Pending
+        && let Err(e) = sys_user_repository::update_status(user.id, UserStatus::Active).await
+    {
+        tracing::warn!("激活用户 {} 失败: {}", user.id, e);
+    }
+
+    tracing::info!("用户 {} 已重置密码", user.id);
+    if let Err(e) = end_all_sessions(&state, user.id, "密码已重置，请重新登录").await {
+        tracing::error!("重置密码后吊销用户 {} 的令牌失败: {}", user.id, e);
+        return ApiResponse::error(
+            e.code(),
+            "密码已重置，但未能让其他设备退出登录，请稍后在登录设备管理中退出所有设备",
+        );
+    }
+
+    ApiResponse::success_with_message((), "密码已重置，请重新登录")
+}
+
+/// 64位十六进制随机令牌
+fn reset_token() -> String {
+    let mut token = uuid::Uuid::new_v4().simple().to_string();
+    token.push_str(&uuid::Uuid::new_v4().simple().to_string());
+    token
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::types::ConnectionManager;
+    use kernel::auth::revocation;
+    use kernel::auth::token::{Claims, TokenError, TokenKind};
+    use kernel::config::AppConfig;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn reset_ends_all_sessions() {
+        AppConfig::init_from_env().unwrap();
+        let connections = Arc::new(ConnectionManager::new());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let claims = Claims {
+            sub: "4242".to_string(),
+            kind: TokenKind::Access,
+            jti: uuid::Uuid::new_v4().to_string(),
+            sid: Some(uuid::Uuid::new_v4().to_string()),
+            ip: None,
+            iss: "test".to_string(),
+            iat: 0,
+            exp: u64::MAX,
+        };
+        assert!(connections.register("4242".to_string(), tx, &claims).await);
+        let state = SystemState {
+            connections: Some(connections.clone()),
+        };
+
+        // 未配置数据库时只吊销令牌并断开连接，没有会话记录可标记
+        let ended = end_all_sessions(&state, 4242, "密码已重置，请重新登录")
+            .await
+            .unwrap();
+        assert_eq!(ended, 0);
+
+        // 重置前签发的令牌失效，websocket 收到原因后被断开
+        assert!(matches!(
+            revocation::check(&claims).await,
+            Err(TokenError::Revoked)
+        ));
+        let error: serde_json::Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(error["type"], "error");
+        assert_eq!(error["data"]["message"], "密码已重置，请重新登录");
+        assert!(!connections.is_online("4242").await);
+    }
+
+    /// 使用全局 Redis 连接池，多个测试共用，重复初始化的错误忽略
+    async fn init_redis() {
+        let url = std::env::var("TEST_REDIS_URL")
+            .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let _ = kernel::redis::init_redis(&url).await;
+    }
+
+    #[test]
+    fn reset_token_is_stored_as_hash() {
+        let token = reset_token();
+        assert_eq!(token.len(), 64);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let key = reset_token_key(&token);
+        assert!(key.starts_with("auth:reset:"));
+        assert!(!key.contains(&token));
+        assert_ne!(key, reset_token_key(&reset_token()));
+    }
+
+    /// 需要本地 redis-server：cargo test -p app password -- --ignored
+    #[tokio::test]
+    #[ignore = "需要本地 redis-server"]
+    async fn reset_token_works_only_once() {
+        init_redis().await;
+        let token = issue_reset_token(42, 60).await.unwrap();
+
+        assert_eq!(take_reset_token(&token).await.unwrap(), Some(42));
+        assert_eq!(take_reset_token(&token).await.unwrap(), None);
+        assert_eq!(take_reset_token(&reset_token()).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地 redis-server"]
+    async fn expired_reset_token_is_rejected() {
+        init_redis().await;
+        let token = issue_reset_token(42, 1).await.unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+        assert_eq!(take_reset_token(&token).await.unwrap(), None);
+    }
+}
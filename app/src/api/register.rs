@@ -0,0 +1,260 @@
+use common::request::system::{RegisterRequest, VerifyEmailRequest};
+use common::utils::response::ApiResponse;
+use common::validator::json::ValidatedJson;
+use database::entity::sys_user::UserStatus;
+use database::repository::sys_user_repository;
+use kernel::auth::password;
+use kernel::config::auth_config;
+use kernel::mail::{self, Email};
+use kernel::redis::get_redis_pool;
+use kernel::redis::service::RedisService;
+use kernel::throttle;
+use middleware_fn::client_ip::ClientIp;
+use serde::{Deserialize, Serialize};
+
+/// 单个IP每小时可提交的注册次数
+const REGISTER_IP_LIMIT: u32 = 10;
+/// 单个邮箱在验证码有效期内可发送的邮件数
+const REGISTER_EMAIL_LIMIT: u32 = 3;
+/// 单个邮箱在验证码有效期内可尝试验证的次数
+const VERIFY_ATTEMPT_LIMIT: u32 = 5;
+
+/// 注册成功和邮箱已注册使用同一个提示，不暴露邮箱是否已注册
+const REGISTER_ACCEPTED: &str = "验证码已发送，请查收邮件";
+
+/// 邮箱验证码及对应注册请求
+fn email_code_key(email: &str) -> String {
+    format!("auth:email_code:{}", email)
+}
+
+/// 等待验证的注册请求，和验证码一起保存在 Redis 中
+///
+/// 密码只在验证通过时写入账号，每次注册都会覆盖上一次的记录，验证码只能激活发出它的那次注册
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingRegistration {
+    code: String,
+    password_hash: String,
+    name: Option<String>,
+}
+
+impl PendingRegistration {
+    /// 验证码匹配时返回注册请求，记录无法解析时视为不存在
+    fn verify(stored: &str, code: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(stored)
+            .ok()
+            .filter(|pending| pending.code == code)
+    }
+}
+
+/// 注册：创建待验证的账号并发送邮箱验证码
+///
+/// 待验证的账号重复注册时发送新的验证码，之前的验证码失效，密码以验证通过的那次注册为准；
+/// 邮箱已注册时改为发送提醒邮件，接口返回相同的结果
+pub async fn register(
+    ClientIp(ip): ClientIp,
+    ValidatedJson(payload): ValidatedJson<RegisterRequest>,
+) -> ApiResponse<()> {
+    if database::get_db().is_none() || get_redis_pool().is_err() {
+        return ApiResponse::error(503, "注册功能需要配置数据库和 Redis");
+    }
+
+    let config = auth_config();
+    let email = payload.email.trim().to_lowercase();
+    if !throttle::allow(&format!("register:ip:{}", ip), REGISTER_IP_LIMIT, 3600).await
+        || !throttle::allow(
+            &format!("register:email:{}", email),
+            REGISTER_EMAIL_LIMIT,
+            config.email_code_ttl,
+        )
+        .await
+    {
+        return ApiResponse::error(429, "操作过于频繁，请稍后再试");
+    }
+
+    let user = match sys_user_repository::find_by_email(&email).await {
+        Ok(user) => user,
+        Err(e) => {
+            tracing::error!("查询用户失败: {}", e);
+            return ApiResponse::error(500, "注册失败，请稍后再试");
+        }
+    };
+
+    // 无论邮箱是否已注册都计算一次哈希，响应耗时保持一致（只有待验证的账号会保存）
+    let password = payload.password;
+    let hashed = tokio::task::spawn_blocking(move || password::hash_password(&password)).await;
+    let hash = match hashed {
+        Ok(Ok(hash)) => hash,
+        Ok(Err(e)) => {
+            tracing::error!("{}", e);
+            return ApiResponse::error(500, "注册失败，请稍后再试");
+        }
+        Err(e) => {
+            tracing::error!("密码哈希任务失败: {}", e);
+            return ApiResponse::error(500, "注册失败，请稍后再试");
+        }
+    };
+
+    let saved = match &user {
+        None => sys_user_repository::create_pending(&email)
+            .await
+            .map(|user| Some(user.id)),
+        Some(user) if user.status == UserStatus::Pending => Ok(Some(user.id)),
+        Some(_) => Ok(None),
+    };
+    let email_message = match saved {
+        Ok(Some(user_id)) => {
+            let code = verification_code();
+            let pending = PendingRegistration {
+                code: code.clone(),
+                password_hash: hash,
+                name: payload.name,
+            };
+            let value = match serde_json::to_string(&pending) {
+                Ok(value) => value,
+                Err(e) => {
+                    tracing::error!("序列化注册请求失败: {}", e);
+                    return ApiResponse::error(500, "注册失败，请稍后再试");
+                }
+            };
+            if let Err(e) =
+                RedisService::set(&email_code_key(&email), &value, Some(config.email_code_ttl))
+                    .await
+            {
+                tracing::error!("保存邮箱验证码失败: {}", e);
+                return ApiResponse::error(500, "注册失败，请稍后再试");
+            }
+            tracing::info!("用户 {} 注册，等待验证邮箱", user_id);
+            Email {
+                to: email,
+                subject: "邮箱验证码".to_string(),
+                body: format!(
+                    "你的验证码是 {}，{} 分钟内有效。如果不是你本人操作，请忽略本邮件。",
+                    code,
+                    config.email_code_ttl / 60
+                ),
+            }
+        }
+        Ok(None) => Email {
+            to: email,
+            subject: "该邮箱已注册".to_string(),
+            body: "有人尝试使用该邮箱注册账号，但该邮箱已经注册。如果是你本人操作，请直接登录；如果忘记密码，请使用找回密码功能。".to_string(),
+        },
+        Err(e) => {
+            tracing::error!("保存注册用户失败: {}", e);
+            return ApiResponse::error(500, "注册失败，请稍后再试");
+        }
+    };
+
+    if let Err(e) = mail::global().send(&email_message).await {
+        tracing::error!("发送注册邮件失败: {}", e);
+        return ApiResponse::error(500, "邮件发送失败，请稍后再试");
+    }
+
+    ApiResponse::success_with_message((), REGISTER_ACCEPTED)
+}
+
+/// 校验邮箱验证码，写入对应注册请求的密码并激活账号，验证码只能使用一次
+pub async fn verify_email(
+    ValidatedJson(payload): ValidatedJson<VerifyEmailRequest>,
+) -> ApiResponse<()> {
+    if database::get_db().is_none() || get_redis_pool().is_err() {
+        return ApiResponse::error(503, "注册功能需要配置数据库和 Redis");
+    }
+
+    let email = payload.email.trim().to_lowercase();
+    if !throttle::allow(
+        &format!("verify:email:{}", email),
+        VERIFY_ATTEMPT_LIMIT,
+        auth_config().email_code_ttl,
+    )
+    .await
+    {
+        return ApiResponse::error(429, "尝试次数过多，请重新获取验证码");
+    }
+
+    let key = email_code_key(&email);
+    let pending = match RedisService::get(&key).await {
+        Ok(Some(stored)) => PendingRegistration::verify(&stored, &payload.code),
+        Ok(None) => None,
+        Err(e) => {
+            tracing::error!("读取邮箱验证码失败: {}", e);
+            return ApiResponse::error(500, "验证失败，请稍后再试");
+        }
+    };
+    let Some(pending) = pending else {
+        return ApiResponse::error(400, "验证码错误或已过期");
+    };
+    if let Err(e) = RedisService::delete(&key).await {
+        tracing::warn!("删除邮箱验证码失败: {}", e);
+    }
+
+    let activated = match sys_user_repository::find_by_email(&email).await {
+        Ok(Some(user)) if user.status == UserStatus::Pending => {
+            sys_user_repository::activate(user.id, pending.password_hash, pending.name)
+                .await
+                .map(|()| Some(user.id))
+        }
+        Ok(_) => Ok(None),
+        Err(e) => Err(e),
+    };
+    match activated {
+        Ok(Some(user_id)) => tracing::info!("用户 {} 邮箱验证成功", user_id),
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("激活用户失败: {}", e);
+            return ApiResponse::error(500, "验证失败，请稍后再试");
+        }
+    }
+
+    ApiResponse::success_with_message((), "邮箱验证成功，请登录")
+}
+
+/// 6位数字验证码
+fn verification_code() -> String {
+    let bytes = uuid::Uuid::new_v4().into_bytes();
+    let number = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) % 1_000_000;
+    format!("{:06}", number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(code: &str, password_hash: &str) -> String {
+        serde_json::to_string(&PendingRegistration {
+            code: code.to_string(),
+            password_hash: password_hash.to_string(),
+            name: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn verification_activates_the_registration_that_received_the_code() {
+        // 抢注者先用受害者的邮箱注册，验证码发到受害者的邮箱
+        let squatter = stored("111111", "squatter-hash");
+        assert!(PendingRegistration::verify(&squatter, "111111").is_some());
+
+        // 受害者本人再次注册，覆盖了等待验证的记录，用收到的验证码激活的是自己的密码
+        let current = stored("222222", "victim-hash");
+
+        assert!(PendingRegistration::verify(&current, "111111").is_none());
+        let pending = PendingRegistration::verify(&current, "222222").unwrap();
+        assert_eq!(pending.password_hash, "victim-hash");
+    }
+
+    #[test]
+    fn unreadable_record_never_verifies() {
+        assert!(PendingRegistration::verify("123456", "123456").is_none());
+        assert!(PendingRegistration::verify("", "").is_none());
+    }
+
+    #[test]
+    fn verification_code_has_six_digits() {
+        for _ in 0..100 {
+            let code = verification_code();
+            assert_eq!(code.len(), 6);
+            assert!(code.chars().all(|c| c.is_ascii_digit()));
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use axum::{Router, routing::post};
+use common::request::report::ReportRequest;
+use common::utils::response::ApiResponse;
+use common::validator::json::ValidatedJson;
+use database::repository::sys_report_repository::{self, NewReport};
+use kernel::throttle;
+use middleware_fn::auth::CurrentUser;
+
+/// 单个客户端每小时可提交的举报数
+const REPORT_LIMIT: u32 = 10;
+
+/// 举报接口，登录用户和游客都可以举报
+pub fn set_report_api() -> Router {
+    Router::new().route("/", post(create_report))
+}
+
+/// 举报其他用户，由拥有 `reports:handle` 权限的管理员处理
+pub async fn create_report(
+    user: CurrentUser,
+    ValidatedJson(payload): ValidatedJson<ReportRequest>,
+) -> ApiResponse<()> {
+    if database::get_db().is_none() {
+        return ApiResponse::error(503, "未配置用户数据库");
+    }
+
+    let target = payload.target.trim().to_string();
+    if target == user.id {
+        return ApiResponse::error(400, "不能举报自己");
+    }
+    if !throttle::allow(&format!("report:{}", user.id), REPORT_LIMIT, 3600).await {
+        return ApiResponse::error(429, "举报过于频繁，请稍后再试");
+    }
+
+    let report = NewReport {
+        reporter: user.id.clone(),
+        target,
+        session_id: payload.session_id,
+        reason: payload.reason.trim().to_string(),
+        detail: payload.detail.filter(|detail| !detail.trim().is_empty()),
+    };
+    match sys_report_repository::create(report).await {
+        Ok(id) => {
+            tracing::info!("客户端 {} 提交了举报 {}", user.id, id);
+            ApiResponse::success_with_message((), "举报已提交，我们会尽快处理")
+        }
+        Err(e) => {
+            tracing::error!("记录举报失败: {}", e);
+            ApiResponse::error(500, "举报失败，请稍后再试")
+        }
+    }
+}
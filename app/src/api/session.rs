@@ -0,0 +1,238 @@
+use crate::api::system::{SystemState, end_all_sessions, end_session};
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::{HeaderMap, header::USER_AGENT},
+    routing::{delete, get},
+};
+use common::response::session::{RevokeSessionsResponse, SessionItem, SessionsResponse};
+use common::utils::response::ApiResponse;
+use database::entity::sys_user_session;
+use database::repository::sys_user_session_repository;
+use middleware_fn::auth::CurrentUser;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 记录的 User-Agent 最大长度（字符）
+const USER_AGENT_MAX_LEN: usize = 512;
+
+/// 当前用户的登录会话（设备）管理接口
+pub fn set_session_api(state: SystemState) -> Router {
+    Router::new()
+        .route("/", get(list_sessions).delete(revoke_all_sessions))
+        .route("/{session_id}", delete(revoke_session))
+        .with_state(state)
+}
+
+/// 列出当前用户未吊销且未过期的登录会话
+pub async fn list_sessions(user: CurrentUser) -> ApiResponse<SessionsResponse> {
+    let user_id = match session_owner(&user) {
+        Ok(user_id) => user_id,
+        Err((code, message)) => return ApiResponse::error(code, message),
+    };
+
+    let sessions = match sys_user_session_repository::find_active_by_user(user_id, now()).await {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            tracing::error!("查询用户 {} 的登录会话失败: {}", user_id, e);
+            return ApiResponse::error(500, "查询登录会话失败");
+        }
+    };
+    let current = user.claims.sid.as_deref();
+    let sessions = sessions
+        .into_iter()
+        .map(|session| SessionItem {
+            current: current == Some(session.id.as_str()),
+            id: session.id,
+            device: session.device,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            created_at: session.created_at.timestamp(),
+            last_seen_at: session.last_seen_at,
+            expires_at: session.expires_at,
+        })
+        .collect();
+
+    ApiResponse::success(SessionsResponse { sessions })
+}
+
+/// 吊销当前用户的某个登录会话，并断开使用该会话令牌建立的 websocket
+pub async fn revoke_session(
+    State(state): State<SystemState>,
+    user: CurrentUser,
+    Path(session_id): Path<String>,
+) -> ApiResponse<()> {
+    let user_id = match session_owner(&user) {
+        Ok(user_id) => user_id,
+        Err((code, message)) => return ApiResponse::error(code, message),
+    };
+
+    // 其他用户的会话同样提示不存在
+    match sys_user_session_repository::find_by_id(&session_id).await {
+        Ok(Some(session)) if revocable(&session, user_id, now()) => {}
+        Ok(_) => return ApiResponse::error(404, "登录会话不存在"),
+        Err(e) => {
+            tracing::error!("查询登录会话 {} 失败: {}", session_id, e);
+            return ApiResponse::error(500, "吊销登录会话失败");
+        }
+    }
+
+    if let Err(e) = end_session(&state, &user.id, &session_id).await {
+        return ApiResponse::error(e.code(), &e.to_string());
+    }
+    tracing::info!("用户 {} 吊销登录会话 {}", user_id, session_id);
+
+    ApiResponse::success_with_message((), "登录会话已吊销")
+}
+
+/// 吊销当前用户的所有登录会话（包括发起请求的会话），并断开该用户的 websocket
+pub async fn revoke_all_sessions(
+    State(state): State<SystemState>,
+    user: CurrentUser,
+) -> ApiResponse<RevokeSessionsResponse> {
+    let user_id = match session_owner(&user) {
+        Ok(user_id) => user_id,
+        Err((code, message)) => return ApiResponse::error(code, message),
+    };
+
+    let revoked = match end_all_sessions(&state, user_id, "已在所有设备上退出登录").await
+    {
+        Ok(revoked) => revoked,
+        Err(e) => return ApiResponse::error(e.code(), &e.to_string()),
+    };
+    tracing::info!("用户 {} 吊销了全部 {} 个登录会话", user_id, revoked);
+
+    ApiResponse::success(RevokeSessionsResponse { revoked })
+}
+
+/// 登录会话只属于登录用户，游客没有会话；会话记录保存在数据库中
+fn session_owner(user: &CurrentUser) -> Result<i32, (i32, &'static str)> {
+    let Some(user_id) = user.user_id() else {
+        return Err((403, "游客没有登录会话"));
+    };
+    if database::get_db().is_none() {
+        return Err((503, "未配置用户数据库"));
+    }
+    Ok(user_id)
+}
+
+/// 会话属于该用户且未吊销、未过期时才能吊销
+fn revocable(session: &sys_user_session::Model, user_id: i32, now: i64) -> bool {
+    session.user_id == user_id && session.revoked_at.is_none() && session.expires_at > now
+}
+
+/// 读取请求的 User-Agent，超出长度的部分截断
+pub(crate) fn user_agent(headers: &HeaderMap) -> String {
+    headers
+        .get(USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.chars().take(USER_AGENT_MAX_LEN).collect())
+        .unwrap_or_default()
+}
+
+/// 根据 User-Agent 粗略推断设备名称，客户端未提供设备名称时使用
+pub(crate) fn device_name(user_agent: &str) -> String {
+    const PLATFORMS: [(&str, &str); 7] = [
+        ("iPhone", "iPhone"),
+        ("iPad", "iPad"),
+        ("Android", "Android"),
+        ("Windows", "Windows"),
+        ("Macintosh", "Mac"),
+        ("CrOS", "Chromebook"),
+        ("Linux", "Linux"),
+    ];
+    const BROWSERS: [(&str, &str); 5] = [
+        ("Edg/", "Edge"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+        ("curl/", "curl"),
+    ];
+
+    let platform = PLATFORMS
+        .iter()
+        .find(|(pattern, _)| user_agent.contains(pattern))
+        .map(|(_, name)| *name);
+    let browser = BROWSERS
+        .iter()
+        .find(|(pattern, _)| user_agent.contains(pattern))
+        .map(|(_, name)| *name);
+    match (browser, platform) {
+        (Some(browser), Some(platform)) => format!("{} · {}", browser, platform),
+        (Some(name), None) | (None, Some(name)) => name.to_string(),
+        (None, None) => "未知设备".to_string(),
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn session(user_id: i32, expires_at: i64, revoked_at: Option<i64>) -> sys_user_session::Model {
+        sys_user_session::Model {
+            id: "sid".to_string(),
+            user_id,
+            device: "Chrome · Windows".to_string(),
+            user_agent: String::new(),
+            ip: "127.0.0.1".to_string(),
+            last_seen_at: 900,
+            expires_at,
+            revoked_at,
+            created_at: Default::default(),
+        }
+    }
+
+    #[test]
+    fn only_own_active_sessions_are_revocable() {
+        assert!(revocable(&session(1, 2000, None), 1, 1000));
+
+        // 其他用户的会话、已吊销或已过期的会话都视为不存在
+        assert!(!revocable(&session(2, 2000, None), 1, 1000));
+        assert!(!revocable(&session(1, 2000, Some(950)), 1, 1000));
+        assert!(!revocable(&session(1, 1000, None), 1, 1000));
+    }
+
+    #[test]
+    fn infers_device_name_from_user_agent() {
+        let cases = [
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0",
+                "Edge · Windows",
+            ),
+            (
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1",
+                "Safari · iPhone",
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+                "Chrome · Android",
+            ),
+            (
+                "Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0",
+                "Firefox · Linux",
+            ),
+            ("curl/8.4.0", "curl"),
+            ("", "未知设备"),
+        ];
+        for (user_agent, device) in cases {
+            assert_eq!(device_name(user_agent), device, "{}", user_agent);
+        }
+    }
+
+    #[test]
+    fn truncates_long_user_agent() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(user_agent(&headers), "");
+
+        let long = "a".repeat(USER_AGENT_MAX_LEN + 10);
+        headers.insert(USER_AGENT, HeaderValue::from_str(&long).unwrap());
+        assert_eq!(user_agent(&headers).len(), USER_AGENT_MAX_LEN);
+    }
+}
@@ -1,17 +1,327 @@
-use common::request::system::LoginRequest;
+use crate::api::password::{forgot_password, reset_password};
+use crate::api::session;
+use crate::websocket::types::ConnectionManager;
+use axum::{Router, extract::State, http::HeaderMap, middleware, routing::post};
+use common::request::system::{LoginRequest, RefreshTokenRequest};
 use common::response::login::LoginResponse;
 use common::utils::response::ApiResponse;
 use common::validator::json::ValidatedJson;
+use database::entity::sys_user::UserStatus;
+use database::repository::sys_user_repository;
+use database::repository::sys_user_session_repository::{self, NewUserSession};
+use kernel::auth::token::{TokenError, TokenKind, TokenService};
+use kernel::auth::{password, revocation};
+use kernel::config::auth_config;
+use kernel::throttle;
+use middleware_fn::auth::{CurrentUser, authenticate, check_ban};
+use middleware_fn::client_ip::ClientIp;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+/// 账号不存在和密码错误使用同一个提示
+const INVALID_CREDENTIALS: &str = "邮箱或密码错误";
+
+/// 登录尝试的计数周期（秒）
+const LOGIN_WINDOW: u64 = 900;
+/// 单个IP每个周期可尝试登录的次数
+const LOGIN_IP_LIMIT: u32 = 30;
+/// 单个邮箱每个周期可尝试登录的次数
+const LOGIN_EMAIL_LIMIT: u32 = 10;
+
+/// 登录相关接口的共享状态
+#[derive(Clone)]
+pub struct SystemState {
+    /// 连接管理器，用于断开使用已吊销令牌的 websocket，未开启 ws 时为空
+    pub connections: Option<Arc<ConnectionManager>>,
+}
+
+/// 登录、刷新令牌、退出登录和找回密码接口
+pub fn set_system_api(state: SystemState) -> Router {
+    Router::new()
+        .route("/login", post(login))
+        .route("/token/refresh", post(refresh_token))
+        .route("/password/forgot", post(forgot_password))
+        .route("/password/reset", post(reset_password))
+        .route(
+            "/logout",
+            post(logout).layer(middleware::from_fn(authenticate)),
+        )
+        .with_state(state)
+}
+
+/// 邮箱密码登录，签发访问令牌和刷新令牌，并记录登录会话（设备、User-Agent、IP）
 pub async fn login(
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
     ValidatedJson(payload): ValidatedJson<LoginRequest>,
 ) -> ApiResponse<LoginResponse> {
-    println!("{:?}", payload);
+    if database::get_db().is_none() {
+        return ApiResponse::error(503, "未配置用户数据库");
+    }
+
+    // 按IP和邮箱限制尝试次数，防止暴力破解和撞库
+    let email = payload.email.trim();
+    if !throttle::allow(&format!("login:ip:{}", ip), LOGIN_IP_LIMIT, LOGIN_WINDOW).await
+        || !throttle::allow(
+            &format!("login:email:{}", email.to_lowercase()),
+            LOGIN_EMAIL_LIMIT,
+            LOGIN_WINDOW,
+        )
+        .await
+    {
+        return ApiResponse::error(429, "登录尝试过于频繁，请稍后再试");
+    }
+
+    let user = match sys_user_repository::find_by_email(email).await {
+        Ok(user) => user,
+        Err(e) => {
+            tracing::error!("查询用户失败: {}", e);
+            return ApiResponse::error(500, "登录失败，请稍后再试");
+        }
+    };
+
+    // 账号不存在时也完整计算一次哈希，响应耗时不暴露账号是否存在
+    let hash = user.as_ref().map(|user| user.password_hash.clone());
+    let password = payload.password;
+    let matched =
+        tokio::task::spawn_blocking(move || password::verify_password(&password, hash.as_deref()))
+            .await
+            .unwrap_or(false);
+    let Some(user) = user.filter(|_| matched) else {
+        return ApiResponse::error(401, INVALID_CREDENTIALS);
+    };
+
+    // 密码正确后才提示账号状态
+    if let Some(message) = inactive_reason(user.status) {
+        return ApiResponse::error(403, message);
+    }
+    if let Err(rejection) = check_ban(&user.id.to_string(), None).await {
+        return ApiResponse::error(rejection.code, &rejection.message);
+    }
 
-    let response = LoginResponse {
-        token: String::from("冢中枯骨，吾早晚必擒之！"),
-        token_type: String::from("追比圣贤，本是读书人的愿望！"),
-        message: String::from("为天地立心，为生民立命，为往圣继绝学，为万世开太平!"),
+    let config = auth_config();
+    let refresh_ttl = match payload.remember_me.unwrap_or(false) {
+        true => config.remember_token_ttl,
+        false => config.refresh_token_ttl,
     };
+    // 每次登录开启一个新的登录会话，轮换出的令牌都属于该会话
+    let sid = uuid::Uuid::new_v4().to_string();
+    let response = match issue_tokens(user.id, &sid, refresh_ttl) {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::error!("签发登录令牌失败: {}", e);
+            return ApiResponse::error(500, "登录失败，请稍后再试");
+        }
+    };
+
+    let user_agent = session::user_agent(&headers);
+    let record = NewUserSession {
+        id: sid.clone(),
+        user_id: user.id,
+        device: payload
+            .device
+            .unwrap_or_else(|| session::device_name(&user_agent)),
+        user_agent,
+        ip: ip.to_string(),
+        last_seen_at: now() as i64,
+        expires_at: response.refresh_expires_at as i64,
+    };
+    if let Err(e) = sys_user_session_repository::create(record).await {
+        tracing::error!("记录登录会话失败: {}", e);
+        return ApiResponse::error(500, "登录失败，请稍后再试");
+    }
+
+    tracing::info!("用户 {} 登录成功，登录会话 {}", user.id, sid);
     ApiResponse::success(response)
 }
+
+/// 使用刷新令牌换取新的访问令牌和刷新令牌
+///
+/// 刷新令牌只能使用一次，再次使用已轮换的刷新令牌视为令牌泄露，整个登录会话随即失效
+pub async fn refresh_token(
+    State(state): State<SystemState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<RefreshTokenRequest>,
+) -> ApiResponse<LoginResponse> {
+    let claims = match TokenService::global().verify(&payload.refresh_token, &[TokenKind::Refresh])
+    {
+        Ok(claims) => claims,
+        Err(e) => return ApiResponse::error(401, &e.to_string()),
+    };
+    let (Some(sid), Ok(user_id)) = (claims.sid.clone(), claims.sub.parse::<i32>()) else {
+        return ApiResponse::error(401, &TokenError::Invalid.to_string());
+    };
+    // 已轮换的刷新令牌也记录在吊销列表中，这里只检查登录会话，令牌本身由 consume 判断是否重复使用
+    if let Err(e) = revocation::check_session(&claims).await {
+        return ApiResponse::error(e.code(), &e.to_string());
+    }
+
+    match revocation::consume(&claims).await {
+        Ok(()) => {}
+        Err(TokenError::Reused) => {
+            tracing::warn!(
+                "用户 {} 重复使用已轮换的刷新令牌，吊销登录会话 {}",
+                user_id,
+                sid
+            );
+            if let Err(e) = end_session(&state, &claims.sub, &sid).await {
+                tracing::error!("吊销登录会话 {} 失败: {}", sid, e);
+            }
+            return ApiResponse::error(401, &TokenError::Reused.to_string());
+        }
+        Err(e) => return ApiResponse::error(e.code(), &e.to_string()),
+    }
+
+    // 账号在登录后被禁用时不再续期
+    if database::get_db().is_some() {
+        match sys_user_repository::get_by_id(&claims.sub).await {
+            Ok(user) if inactive_reason(user.status).is_none() => {}
+            Ok(user) => {
+                return ApiResponse::error(403, inactive_reason(user.status).unwrap());
+            }
+            Err(e) => {
+                tracing::warn!("刷新令牌时查询用户 {} 失败: {}", user_id, e);
+                return ApiResponse::error(401, &TokenError::Invalid.to_string());
+            }
+        }
+    }
+    // 被封禁期间不再续期
+    if let Err(rejection) = check_ban(&claims.sub, None).await {
+        return ApiResponse::error(rejection.code, &rejection.message);
+    }
+
+    // 轮换后的刷新令牌沿用原来的过期时间，登录会话不会因为刷新而无限延长
+    let remaining = claims.exp.saturating_sub(now());
+    let response = match issue_tokens(user_id, &sid, remaining) {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::error!("轮换令牌失败: {}", e);
+            return ApiResponse::error(500, "刷新令牌失败，请稍后再试");
+        }
+    };
+
+    if database::get_db().is_some()
+        && let Err(e) = sys_user_session_repository::touch(
+            &sid,
+            ip.to_string(),
+            session::user_agent(&headers),
+            now() as i64,
+        )
+        .await
+    {
+        tracing::warn!("更新登录会话 {} 失败: {}", sid, e);
+    }
+
+    ApiResponse::success(response)
+}
+
+/// 退出登录：吊销当前令牌及所属登录会话，并断开使用该会话令牌建立的 websocket
+pub async fn logout(State(state): State<SystemState>, user: CurrentUser) -> ApiResponse<()> {
+    let claims = user.claims;
+    let mut revoked = revocation::revoke_token(&claims).await;
+    disconnect_revoked(&state, &claims.sub, &claims.jti).await;
+    if let Some(sid) = &claims.sid {
+        revoked = revoked.and(end_session(&state, &claims.sub, sid).await);
+    }
+    if let Err(e) = revoked {
+        return ApiResponse::error(e.code(), &e.to_string());
+    }
+    tracing::info!("用户 {} 退出登录", claims.sub);
+
+    ApiResponse::success_with_message((), "已退出登录")
+}
+
+/// 签发同一登录会话的访问令牌和刷新令牌
+fn issue_tokens(user_id: i32, sid: &str, refresh_ttl: u64) -> Result<LoginResponse, TokenError> {
+    let service = TokenService::global();
+    let subject = user_id.to_string();
+    let (token, claims) = service.issue_in_session(
+        &subject,
+        TokenKind::Access,
+        auth_config().access_token_ttl,
+        Some(sid),
+    )?;
+    let (refresh_token, refresh_claims) =
+        service.issue_in_session(&subject, TokenKind::Refresh, refresh_ttl, Some(sid))?;
+
+    Ok(LoginResponse {
+        user_id,
+        token,
+        token_type: "Bearer".to_string(),
+        expires_at: claims.exp,
+        refresh_token,
+        refresh_expires_at: refresh_claims.exp,
+    })
+}
+
+/// 账号不可登录时的提示
+fn inactive_reason(status: UserStatus) -> Option<&'static str> {
+    match status {
+        UserStatus::Active => None,
+        UserStatus::Pending => Some("账号尚未验证邮箱"),
+        UserStatus::Disabled => Some("账号已被禁用"),
+    }
+}
+
+/// 结束登录会话：吊销会话内的令牌，标记会话记录，并断开使用该会话令牌建立的 websocket
+///
+/// 吊销记录写入失败时会话仍然有效，不标记会话记录，返回错误
+pub(crate) async fn end_session(
+    state: &SystemState,
+    sub: &str,
+    sid: &str,
+) -> Result<(), TokenError> {
+    let revoked = revocation::revoke_session(sid).await;
+    if revoked.is_ok()
+        && database::get_db().is_some()
+        && let Err(e) = sys_user_session_repository::revoke(sid, now() as i64).await
+    {
+        tracing::warn!("标记登录会话 {} 已吊销失败: {}", sid, e);
+    }
+    disconnect_revoked(state, sub, sid).await;
+    revoked
+}
+
+/// 结束用户的所有登录会话：吊销此前签发的所有令牌并断开该用户的 websocket，返回结束的会话数
+///
+/// 吊销记录写入失败时令牌仍然有效，不标记会话记录，返回错误
+pub(crate) async fn end_all_sessions(
+    state: &SystemState,
+    user_id: i32,
+    reason: &str,
+) -> Result<usize, TokenError> {
+    let sub = user_id.to_string();
+    let revoked = revocation::revoke_user(&sub).await;
+    if let Some(connections) = &state.connections {
+        connections.disconnect(&sub, reason).await;
+    }
+    revoked?;
+
+    if database::get_db().is_none() {
+        return Ok(0);
+    }
+    match sys_user_session_repository::revoke_all_by_user(user_id, now() as i64).await {
+        Ok(ids) => Ok(ids.len()),
+        Err(e) => {
+            tracing::warn!("标记用户 {} 的登录会话已吊销失败: {}", user_id, e);
+            Ok(0)
+        }
+    }
+}
+
+/// 断开使用已吊销令牌或登录会话建立的 websocket
+async fn disconnect_revoked(state: &SystemState, client_id: &str, token: &str) {
+    if let Some(connections) = &state.connections {
+        connections
+            .disconnect_revoked(client_id, token, "登录已失效，请重新登录")
+            .await;
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
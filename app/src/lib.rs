@@ -1,3 +1,4 @@
 pub mod api;
 pub mod route;
+pub mod storage;
 pub mod websocket;
\ No newline at end of file
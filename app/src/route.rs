@@ -1,25 +1,112 @@
 use crate::api;
-use std::sync::Arc;
+use crate::api::system::SystemState;
+use crate::websocket::types::ConnectionManager;
+use std::{process, sync::Arc};
 
 use axum::http::StatusCode;
 use axum::{
     Router, middleware,
     routing::{get, post},
 };
-use kernel::config::server_config;
+use kernel::config::{database_config, redis_config, server_config};
+use middleware_fn::auth::authenticate;
 use middleware_fn::request::{logging_middleware, rate_limiter};
 
-pub fn build_router() -> Router {
+pub async fn build_router() -> Router {
     let config = server_config();
 
     let mut router = Router::new();
+    // 连接管理器，退出登录时用于断开 websocket，未开启 ws 时为空
+    let mut connection_manager = None;
 
     // ws服务
     if config.ws_open {
-        use crate::websocket::{types::ConnectionManager, set_websocket_api};
-        // 创建连接管理器
-        let connection_manager = Arc::new(ConnectionManager::new());
-        router = router.nest(&config.ws_path, set_websocket_api(connection_manager));
+        use crate::api::attachment::AttachmentState;
+        use crate::storage::Attachments;
+        use crate::websocket::{
+            WsState, call::CallManager, cluster::ClusterBus, history::BroadcastHistory,
+            persist::ChatWriter, room::RoomManager, session::SessionManager, set_websocket_api,
+        };
+        // 创建连接管理器、会话管理器、通话管理器和广播历史，集群模式下通过 redis 跨节点投递消息并共享一对一会话、通话和广播历史
+        let (connections, sessions, calls, history) = if config.ws_cluster {
+            let redis_url = &redis_config().redis_url;
+            let node_id = match config.ws_node_id.is_empty() {
+                true => uuid::Uuid::new_v4().to_string(),
+                false => config.ws_node_id.clone(),
+            };
+            let manager = match ClusterBus::connect(redis_url, node_id).await {
+                Ok(bus) => {
+                    let sessions = SessionManager::with_redis(bus.connection());
+                    let calls = CallManager::with_redis(bus.connection());
+                    let history =
+                        BroadcastHistory::with_redis(bus.connection(), config.ws_broadcast_history);
+                    ConnectionManager::with_cluster(bus)
+                        .await
+                        .map(|manager| (manager, sessions, calls, history))
+                }
+                Err(e) => Err(e),
+            };
+            match manager {
+                Ok(managers) => managers,
+                Err(e) => {
+                    eprintln!("❌ Failed to join ws cluster: {}", e);
+                    eprintln!("💡 Make sure Redis is running at: {}", redis_url);
+                    process::exit(1);
+                }
+            }
+        } else {
+            (
+                ConnectionManager::new(),
+                SessionManager::new(),
+                CallManager::new(),
+                BroadcastHistory::new(config.ws_broadcast_history),
+            )
+        };
+        // 附件存储
+        let attachments = match Attachments::from_config() {
+            Ok(attachments) => Arc::new(attachments),
+            Err(e) => {
+                eprintln!("❌ Failed to initialize attachment storage: {}", e);
+                process::exit(1);
+            }
+        };
+        // 房间管理器和广播历史
+        let state = WsState {
+            connections: Arc::new(connections),
+            rooms: Arc::new(RoomManager::new()),
+            history: Arc::new(history),
+            sessions: Arc::new(sessions),
+            calls: Arc::new(calls),
+            // 配置了数据库时持久化私聊消息
+            chat_writer: match database_config().database_url.is_empty() {
+                true => None,
+                false => Some(ChatWriter::spawn()),
+            },
+            attachments: attachments.clone(),
+        };
+        // 管理接口（公告需要通过连接管理器推送），按角色权限校验
+        router = router.nest(
+            "/api/admin",
+            api::admin::set_admin_api(state.connections.clone())
+                .layer(middleware::from_fn(authenticate)),
+        );
+        // 聊天记录接口
+        router = router.nest(
+            "/api/chat",
+            api::chat::set_chat_api(state.sessions.clone())
+                .layer(middleware::from_fn(authenticate)),
+        );
+        // 附件上传下载接口
+        router = router.nest(
+            "/api/attachments",
+            api::attachment::set_attachment_api(AttachmentState {
+                attachments,
+                sessions: state.sessions.clone(),
+            })
+            .layer(middleware::from_fn(authenticate)),
+        );
+        connection_manager = Some(state.connections.clone());
+        router = router.nest(&config.ws_path, set_websocket_api(state));
     }
 
     if config.debug {
@@ -28,7 +115,7 @@ pub fn build_router() -> Router {
     }
 
     // 添加 API 路由
-    router = add_api_routes(router);
+    router = add_api_routes(router, connection_manager);
 
     if config.log_enable_oper_log {
         // 整体记录请求
@@ -40,13 +127,27 @@ pub fn build_router() -> Router {
         .fallback(handle_404)
 }
 
-fn add_api_routes(router: Router) -> Router {
+fn add_api_routes(router: Router, connections: Option<Arc<ConnectionManager>>) -> Router {
+    let state = SystemState { connections };
     router
         .route("/", get(index).post(index))
         .nest("/index", Router::new().route("/", get(index)))
+        // 登录会话（设备）管理接口
+        .nest(
+            "/api/sessions",
+            api::session::set_session_api(state.clone()).layer(middleware::from_fn(authenticate)),
+        )
+        // 举报接口
+        .nest(
+            "/api/reports",
+            api::report::set_report_api().layer(middleware::from_fn(authenticate)),
+        )
         .nest(
             "/api",
-            Router::new().route("/login", post(api::system::login)),
+            api::system::set_system_api(state)
+                .route("/guest", post(api::guest::create_guest))
+                .route("/register", post(api::register::register))
+                .route("/register/verify", post(api::register::verify_email)),
         )
 }
 
@@ -0,0 +1,182 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 附件ID中携带的信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileClaims {
+    /// 存储后端中的对象 key
+    pub key: String,
+    /// 上传时所在的会话
+    pub session_id: String,
+    pub mime: String,
+    pub size: usize,
+    /// 过期时间（秒级时间戳）
+    pub exp: u64,
+}
+
+/// 附件ID签名器：附件ID为 `base64(claims).base64(hmac)`，无需额外存储即可校验归属和有效期
+pub struct FileIdSigner {
+    secret: Vec<u8>,
+    ttl: u64,
+}
+
+impl FileIdSigner {
+    pub fn new(secret: &[u8], ttl: u64) -> Self {
+        Self {
+            secret: secret.to_vec(),
+            ttl,
+        }
+    }
+
+    /// 使用随机密钥
+    pub fn random(ttl: u64) -> Self {
+        let secret = [
+            uuid::Uuid::new_v4().into_bytes(),
+            uuid::Uuid::new_v4().into_bytes(),
+        ]
+        .concat();
+        Self::new(&secret, ttl)
+    }
+
+    /// 签发附件ID，返回附件ID和过期时间
+    pub fn sign(&self, key: &str, session_id: &str, mime: &str, size: usize) -> (String, u64) {
+        let claims = FileClaims {
+            key: key.to_string(),
+            session_id: session_id.to_string(),
+            mime: mime.to_string(),
+            size,
+            exp: now_secs() + self.ttl,
+        };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap_or_default());
+        let signature =
+            URL_SAFE_NO_PAD.encode(self.mac(payload.as_bytes()).finalize().into_bytes());
+
+        (format!("{}.{}", payload, signature), claims.exp)
+    }
+
+    /// 校验签名和有效期
+    pub fn verify(&self, file_id: &str) -> Result<FileClaims, String> {
+        let (payload, signature) = file_id.split_once('.').ok_or("附件ID无效")?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| "附件ID无效")?;
+        self.mac(payload.as_bytes())
+            .verify_slice(&signature)
+            .map_err(|_| "附件ID无效")?;
+
+        let claims: FileClaims = URL_SAFE_NO_PAD
+            .decode(payload)
+            .ok()
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .ok_or("附件ID无效")?;
+        if claims.exp < now_secs() {
+            return Err("附件已过期".to_string());
+        }
+
+        Ok(claims)
+    }
+
+    fn mac(&self, data: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC 支持任意长度的密钥");
+        mac.update(data);
+        mac
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer() -> FileIdSigner {
+        FileIdSigner::new(b"test-secret", 3600)
+    }
+
+    /// 用指定的过期时间签发附件ID
+    fn sign_with_exp(signer: &FileIdSigner, exp: u64) -> String {
+        let claims = FileClaims {
+            key: "s/k".to_string(),
+            session_id: "s".to_string(),
+            mime: "image/png".to_string(),
+            size: 1,
+            exp,
+        };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        let signature =
+            URL_SAFE_NO_PAD.encode(signer.mac(payload.as_bytes()).finalize().into_bytes());
+        format!("{}.{}", payload, signature)
+    }
+
+    #[test]
+    fn sign_then_verify() {
+        let signer = signer();
+        let (file_id, exp) = signer.sign("s/k", "s", "image/png", 42);
+        let claims = signer.verify(&file_id).unwrap();
+        assert_eq!(claims.key, "s/k");
+        assert_eq!(claims.session_id, "s");
+        assert_eq!(claims.mime, "image/png");
+        assert_eq!(claims.size, 42);
+        assert_eq!(claims.exp, exp);
+        assert!(exp >= now_secs() + 3599);
+    }
+
+    #[test]
+    fn rejects_other_secret() {
+        let (file_id, _) = signer().sign("s/k", "s", "image/png", 42);
+        assert!(FileIdSigner::new(b"other", 3600).verify(&file_id).is_err());
+        assert!(FileIdSigner::random(3600).verify(&file_id).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_claims() {
+        let signer = signer();
+        let (file_id, _) = signer.sign("s/k", "s", "image/png", 42);
+        let (_, signature) = file_id.split_once('.').unwrap();
+
+        // 换成其他会话的载荷，签名不再匹配
+        let forged = FileClaims {
+            key: "other/k".to_string(),
+            session_id: "other".to_string(),
+            mime: "image/png".to_string(),
+            size: 42,
+            exp: u64::MAX,
+        };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap());
+        assert!(
+            signer
+                .verify(&format!("{}.{}", payload, signature))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        let signer = signer();
+        for file_id in ["", "abc", "abc.", ".abc", "abc.!!!", "a.b.c"] {
+            assert!(signer.verify(file_id).is_err(), "{}", file_id);
+        }
+    }
+
+    #[test]
+    fn rejects_expired() {
+        let signer = signer();
+        let expired = sign_with_exp(&signer, now_secs() - 1);
+        assert_eq!(signer.verify(&expired).unwrap_err(), "附件已过期");
+        assert!(
+            signer
+                .verify(&sign_with_exp(&signer, now_secs() + 60))
+                .is_ok()
+        );
+    }
+}
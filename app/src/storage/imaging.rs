@@ -0,0 +1,414 @@
+use axum::body::Bytes;
+use image::{
+    DynamicImage, ImageDecoder, ImageReader, Limits,
+    codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+    metadata::Orientation,
+};
+use kernel::config::storage_config;
+use std::io::Cursor;
+
+/// 原图重新编码的 JPEG 质量
+const JPEG_QUALITY: u8 = 90;
+/// 缩略图的 JPEG 质量
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/// 处理后的图片
+pub struct ProcessedImage {
+    /// 去除元数据后的原图
+    pub data: Bytes,
+    pub width: u32,
+    pub height: u32,
+    /// JPEG 缩略图
+    pub thumbnail: Bytes,
+}
+
+/// 校验并处理上传的图片：拒绝像素数超限的图片（解压炸弹），按 EXIF 方向摆正后重新编码以去除 EXIF/GPS 等元数据，并生成缩略图
+///
+/// 解码比较耗时，需要在阻塞线程中调用
+pub fn process_image(data: &[u8], mime: &str) -> Result<ProcessedImage, String> {
+    let config = storage_config();
+    process(data, mime, config.image_max_pixels, config.thumbnail_size)
+}
+
+fn process(
+    data: &[u8],
+    mime: &str,
+    max_pixels: u64,
+    thumbnail_size: u32,
+) -> Result<ProcessedImage, String> {
+    // 解码前先读取尺寸，只解析文件头
+    let (width, height) = reader(data)?
+        .into_dimensions()
+        .map_err(|e| format!("图片解析失败: {}", e))?;
+    if width == 0 || height == 0 || width as u64 * height as u64 > max_pixels {
+        return Err("图片尺寸过大".to_string());
+    }
+
+    // 解码时限制内存分配，防止文件头伪造尺寸
+    let mut limits = Limits::default();
+    limits.max_alloc = Some(max_pixels * 4 + 1024 * 1024);
+    let mut reader = reader(data)?;
+    reader.limits(limits);
+
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|e| format!("图片解析失败: {}", e))?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image =
+        DynamicImage::from_decoder(decoder).map_err(|e| format!("图片解码失败: {}", e))?;
+    image.apply_orientation(orientation);
+
+    let data = match mime {
+        "image/jpeg" => encode_jpeg(&image, JPEG_QUALITY)?,
+        "image/png" => {
+            let mut buffer = Vec::new();
+            image
+                .write_with_encoder(PngEncoder::new(&mut buffer))
+                .map_err(|e| format!("图片编码失败: {}", e))?;
+            buffer
+        }
+        "image/webp" => {
+            let image = match image.color().has_alpha() {
+                true => DynamicImage::ImageRgba8(image.to_rgba8()),
+                false => DynamicImage::ImageRgb8(image.to_rgb8()),
+            };
+            let mut buffer = Vec::new();
+            image
+                .write_with_encoder(WebPEncoder::new_lossless(&mut buffer))
+                .map_err(|e| format!("图片编码失败: {}", e))?;
+            buffer
+        }
+        // 重新编码会丢失动画，改为逐块去除扩展块中的元数据
+        "image/gif" => strip_gif_metadata(data)?,
+        _ => return Err("不支持的图片格式".to_string()),
+    };
+
+    let thumbnail = encode_jpeg(
+        &image.thumbnail(thumbnail_size, thumbnail_size),
+        THUMBNAIL_QUALITY,
+    )?;
+
+    Ok(ProcessedImage {
+        data: Bytes::from(data),
+        width: image.width(),
+        height: image.height(),
+        thumbnail: Bytes::from(thumbnail),
+    })
+}
+
+/// GIF 不携带 EXIF，但注释、XMP、ICC 等扩展块以及结束符之后的数据可能带有元数据或任意内容：
+/// 逐块复制，只保留图像数据、图形控制扩展和循环播放扩展
+fn strip_gif_metadata(data: &[u8]) -> Result<Vec<u8>, String> {
+    let invalid = || "GIF 文件无效".to_string();
+
+    // 文件头、逻辑屏幕描述符和全局颜色表
+    let screen = data.get(..13).ok_or_else(invalid)?;
+    let mut pos = 13 + color_table_len(screen[10]);
+    let mut out = data.get(..pos).ok_or_else(invalid)?.to_vec();
+
+    loop {
+        let start = pos;
+        match *data.get(pos).ok_or_else(invalid)? {
+            // 图像描述符、局部颜色表、LZW 最小码长和图像数据
+            0x2C => {
+                let descriptor = data.get(pos..pos + 10).ok_or_else(invalid)?;
+                pos = skip_sub_blocks(data, pos + 10 + color_table_len(descriptor[9]) + 1)?;
+                out.extend_from_slice(&data[start..pos]);
+            }
+            0x21 => {
+                let label = *data.get(pos + 1).ok_or_else(invalid)?;
+                pos = skip_sub_blocks(data, pos + 2)?;
+                let keep = match label {
+                    0xF9 => true,
+                    0xFF => matches!(
+                        data.get(start + 3..start + 14),
+                        Some(b"NETSCAPE2.0" | b"ANIMEXTS1.0")
+                    ),
+                    _ => false,
+                };
+                if keep {
+                    out.extend_from_slice(&data[start..pos]);
+                }
+            }
+            0x3B => {
+                out.push(0x3B);
+                return Ok(out);
+            }
+            _ => return Err(invalid()),
+        }
+    }
+}
+
+/// 颜色表的字节数，由描述符中的标志位决定
+fn color_table_len(packed: u8) -> usize {
+    match packed & 0x80 {
+        0 => 0,
+        _ => 3 << ((packed & 0x07) + 1),
+    }
+}
+
+/// 跳过以长度为 0 的子块结尾的一串数据子块，返回之后的位置
+fn skip_sub_blocks(data: &[u8], mut pos: usize) -> Result<usize, String> {
+    loop {
+        let size = *data.get(pos).ok_or("GIF 文件无效")? as usize;
+        pos += 1 + size;
+        if size == 0 {
+            return Ok(pos);
+        }
+    }
+}
+
+fn reader(data: &[u8]) -> Result<ImageReader<Cursor<&[u8]>>, String> {
+    ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| format!("图片解析失败: {}", e))
+}
+
+/// JPEG 不支持透明通道，统一转换为 RGB
+fn encode_jpeg(image: &DynamicImage, quality: u8) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    DynamicImage::ImageRgb8(image.to_rgb8())
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut buffer, quality))
+        .map_err(|e| format!("图片编码失败: {}", e))?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1x1 GIF：文件头、带 2 色全局颜色表的屏幕描述符
+    const HEADER: &[u8] = &[
+        0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0xFF, 0xFF,
+        0xFF, 0x00, 0x00, 0x00,
+    ];
+    /// 图形控制扩展
+    const GRAPHIC_CONTROL: &[u8] = &[0x21, 0xF9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00];
+    /// 图像描述符和图像数据
+    const FRAME: &[u8] = &[
+        0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00,
+    ];
+    /// 循环播放扩展
+    const LOOP: &[u8] = &[
+        0x21, 0xFF, 0x0B, b'N', b'E', b'T', b'S', b'C', b'A', b'P', b'E', b'2', b'.', b'0', 0x03,
+        0x01, 0x00, 0x00, 0x00,
+    ];
+
+    fn comment(text: &[u8]) -> Vec<u8> {
+        [&[0x21, 0xFE, text.len() as u8], text, &[0x00]].concat()
+    }
+
+    fn xmp(payload: &[u8]) -> Vec<u8> {
+        [
+            &[0x21, 0xFF, 0x0B][..],
+            b"XMP DataXMP",
+            &[payload.len() as u8],
+            payload,
+            &[0x00],
+        ]
+        .concat()
+    }
+
+    /// 测试用的像素上限和缩略图边长
+    const MAX_PIXELS: u64 = 1_000_000;
+    const THUMBNAIL_SIZE: u32 = 8;
+    /// 写入元数据中的坐标，处理后不应再出现
+    const GPS_MARKER: &[u8] = b"31.2304N121.4737E";
+
+    fn contains(data: &[u8], needle: &[u8]) -> bool {
+        data.windows(needle.len()).any(|window| window == needle)
+    }
+
+    fn sample_image() -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(16, 12, |x, y| {
+            image::Rgb([x as u8 * 16, y as u8 * 20, 128])
+        }))
+    }
+
+    /// 带 GPS 信息的 EXIF：IFD0 中的 GPS 指针指向 GPS IFD，GPS IFD 引用一段包含坐标的 ASCII
+    fn exif_with_gps() -> Vec<u8> {
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend_from_slice(&8u32.to_le_bytes());
+        // IFD0：1 项，GPSInfo(0x8825) LONG 指向偏移 26
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&[0x25, 0x88, 0x04, 0x00]);
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&26u32.to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        // GPS IFD：1 项，GPSAreaInformation(0x001C) ASCII 存放在偏移 44
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&[0x1C, 0x00, 0x02, 0x00]);
+        tiff.extend_from_slice(&(GPS_MARKER.len() as u32 + 1).to_le_bytes());
+        tiff.extend_from_slice(&44u32.to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        tiff.extend_from_slice(GPS_MARKER);
+        tiff.push(0);
+        tiff
+    }
+
+    /// 在 SOI 之后插入 APP1 EXIF 段
+    fn jpeg_with_exif() -> Vec<u8> {
+        let jpeg = encode_jpeg(&sample_image(), 90).unwrap();
+        let payload = [&b"Exif\0\0"[..], &exif_with_gps()].concat();
+        let length = (payload.len() as u16 + 2).to_be_bytes();
+        [&jpeg[..2], &[0xFF, 0xE1], &length, &payload, &jpeg[2..]].concat()
+    }
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = 0xFFFF_FFFFu32;
+        for byte in data {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    fn png_chunk(kind: &[u8], payload: &[u8]) -> Vec<u8> {
+        let crc = crc32(&[kind, payload].concat());
+        [
+            &(payload.len() as u32).to_be_bytes()[..],
+            kind,
+            payload,
+            &crc.to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    fn sample_png() -> Vec<u8> {
+        let mut png = Vec::new();
+        sample_image()
+            .write_with_encoder(PngEncoder::new(&mut png))
+            .unwrap();
+        png
+    }
+
+    /// 在 IHDR 之后插入 eXIf 和 tEXt 块
+    fn png_with_metadata() -> Vec<u8> {
+        let png = sample_png();
+        // 8 字节签名 + IHDR（4 长度 + 4 类型 + 13 数据 + 4 CRC）
+        let ihdr_end = 8 + 25;
+        [
+            &png[..ihdr_end],
+            &png_chunk(b"eXIf", &exif_with_gps()),
+            &png_chunk(b"tEXt", &[&b"Comment\0"[..], GPS_MARKER].concat()),
+            &png[ihdr_end..],
+        ]
+        .concat()
+    }
+
+    /// 把 PNG 文件头中的宽高改为 width x height，不改变图像数据
+    fn png_claiming(width: u32, height: u32) -> Vec<u8> {
+        let mut png = sample_png();
+        png[16..20].copy_from_slice(&width.to_be_bytes());
+        png[20..24].copy_from_slice(&height.to_be_bytes());
+        let crc = crc32(&png[12..29]);
+        png[29..33].copy_from_slice(&crc.to_be_bytes());
+        png
+    }
+
+    fn gif(blocks: &[&[u8]]) -> Vec<u8> {
+        let mut data = HEADER.to_vec();
+        for block in blocks {
+            data.extend_from_slice(block);
+        }
+        data
+    }
+
+    #[test]
+    fn strips_jpeg_exif_and_gps() {
+        let data = jpeg_with_exif();
+        assert!(contains(&data, b"Exif\0\0"));
+        assert!(contains(&data, GPS_MARKER));
+
+        let processed = process(&data, "image/jpeg", MAX_PIXELS, THUMBNAIL_SIZE).unwrap();
+        assert!(!contains(&processed.data, b"Exif"));
+        assert!(!contains(&processed.data, GPS_MARKER));
+        assert!(!contains(&processed.thumbnail, GPS_MARKER));
+        assert_eq!((processed.width, processed.height), (16, 12));
+        assert!(image::load_from_memory(&processed.data).is_ok());
+    }
+
+    #[test]
+    fn strips_png_metadata_chunks() {
+        let data = png_with_metadata();
+        assert!(image::load_from_memory(&data).is_ok());
+
+        let processed = process(&data, "image/png", MAX_PIXELS, THUMBNAIL_SIZE).unwrap();
+        assert!(!contains(&processed.data, b"eXIf"));
+        assert!(!contains(&processed.data, b"tEXt"));
+        assert!(!contains(&processed.data, GPS_MARKER));
+        assert!(image::load_from_memory(&processed.data).is_ok());
+    }
+
+    #[test]
+    fn thumbnail_fits_configured_size() {
+        let processed = process(&sample_png(), "image/png", MAX_PIXELS, THUMBNAIL_SIZE).unwrap();
+        let thumbnail = image::load_from_memory(&processed.thumbnail).unwrap();
+        assert!(thumbnail.width() <= THUMBNAIL_SIZE && thumbnail.height() <= THUMBNAIL_SIZE);
+    }
+
+    #[test]
+    fn rejects_decompression_bomb() {
+        // 文件只有几百字节，文件头声称 100000x100000 像素，解码前按尺寸拒绝
+        let data = png_claiming(100_000, 100_000);
+        assert!(data.len() < 1024);
+        assert_eq!(
+            process(&data, "image/png", MAX_PIXELS, THUMBNAIL_SIZE).err(),
+            Some("图片尺寸过大".to_string())
+        );
+
+        // 恰好超过上限一个像素同样拒绝
+        let data = png_claiming(1001, 1000);
+        assert!(process(&data, "image/png", MAX_PIXELS, THUMBNAIL_SIZE).is_err());
+    }
+
+    #[test]
+    fn keeps_plain_gif_unchanged() {
+        let data = gif(&[LOOP, GRAPHIC_CONTROL, FRAME, &[0x3B]]);
+        assert_eq!(strip_gif_metadata(&data).unwrap(), data);
+    }
+
+    #[test]
+    fn strips_metadata_blocks() {
+        let data = gif(&[
+            &comment(b"lat=31.2,lng=121.5"),
+            LOOP,
+            &xmp(b"<x:xmpmeta/>"),
+            GRAPHIC_CONTROL,
+            FRAME,
+            &comment(b"secret"),
+            GRAPHIC_CONTROL,
+            FRAME,
+            &[0x3B],
+            b"trailing data",
+        ]);
+        let stripped = strip_gif_metadata(&data).unwrap();
+        assert_eq!(
+            stripped,
+            gif(&[
+                LOOP,
+                GRAPHIC_CONTROL,
+                FRAME,
+                GRAPHIC_CONTROL,
+                FRAME,
+                &[0x3B]
+            ])
+        );
+        assert!(image::load_from_memory(&stripped).is_ok());
+    }
+
+    #[test]
+    fn rejects_truncated_gif() {
+        let data = gif(&[GRAPHIC_CONTROL, FRAME, &[0x3B]]);
+        for len in [5, 15, HEADER.len() + 3, data.len() - 1] {
+            assert!(strip_gif_metadata(&data[..len]).is_err(), "{}", len);
+        }
+        assert!(strip_gif_metadata(&gif(&[&[0x00]])).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use crate::storage::BlobStore;
+use axum::body::Bytes;
+use futures_util::future::BoxFuture;
+use std::{io::ErrorKind, path::PathBuf};
+
+/// 本地文件系统存储
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: &str) -> Self {
+        Self {
+            root: PathBuf::from(root),
+        }
+    }
+
+    /// 对象路径，拒绝跳出存储目录的 key
+    fn path_of(&self, key: &str) -> Result<PathBuf, String> {
+        if key.is_empty()
+            || key.starts_with('/')
+            || key
+                .split('/')
+                .any(|part| part.is_empty() || part == "." || part == "..")
+        {
+            return Err(format!("非法的存储路径: {}", key));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+impl BlobStore for LocalBlobStore {
+    fn put<'a>(&'a self, key: &'a str, data: Bytes) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let path = self.path_of(key)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| format!("创建存储目录失败: {}", e))?;
+            }
+            tokio::fs::write(&path, &data)
+                .await
+                .map_err(|e| format!("写入文件失败: {}", e))
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Bytes>, String>> {
+        Box::pin(async move {
+            match tokio::fs::read(self.path_of(key)?).await {
+                Ok(data) => Ok(Some(Bytes::from(data))),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(format!("读取文件失败: {}", e)),
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            match tokio::fs::remove_file(self.path_of(key)?).await {
+                Ok(()) => Ok(()),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(format!("删除文件失败: {}", e)),
+            }
+        })
+    }
+
+    fn delete_dir<'a>(&'a self, dir: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            match tokio::fs::remove_dir_all(self.path_of(dir)?).await {
+                Ok(()) => Ok(()),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(format!("删除目录失败: {}", e)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn delete_dir_removes_only_that_dir() {
+        let root = std::env::temp_dir().join(format!("blobs-{}", uuid::Uuid::new_v4()));
+        let store = LocalBlobStore::new(root.to_str().unwrap());
+        for key in ["s1/a", "s1/a.thumb", "s2/b"] {
+            store.put(key, Bytes::from_static(b"x")).await.unwrap();
+        }
+
+        store.delete_dir("s1").await.unwrap();
+        assert!(store.get("s1/a").await.unwrap().is_none());
+        assert!(store.get("s1/a.thumb").await.unwrap().is_none());
+        assert!(store.get("s2/b").await.unwrap().is_some());
+
+        // 目录不存在时忽略，拒绝跳出存储目录
+        assert!(store.delete_dir("s1").await.is_ok());
+        assert!(store.delete_dir("..").await.is_err());
+
+        let _ = tokio::fs::remove_dir_all(root).await;
+    }
+}
@@ -0,0 +1,57 @@
+use axum::body::Bytes;
+use futures_util::future::BoxFuture;
+use kernel::config::storage_config;
+use std::sync::Arc;
+
+pub mod file_id;
+pub mod imaging;
+pub mod local;
+pub mod s3;
+
+use file_id::FileIdSigner;
+use local::LocalBlobStore;
+use s3::S3BlobStore;
+
+/// 附件存储后端
+pub trait BlobStore: Send + Sync {
+    /// 保存对象，已存在时覆盖
+    fn put<'a>(&'a self, key: &'a str, data: Bytes) -> BoxFuture<'a, Result<(), String>>;
+
+    /// 读取对象，不存在时返回 None
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Bytes>, String>>;
+
+    /// 删除对象，不存在时忽略
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), String>>;
+
+    /// 删除 key 以 `dir/` 开头的所有对象，不存在时忽略
+    fn delete_dir<'a>(&'a self, dir: &'a str) -> BoxFuture<'a, Result<(), String>>;
+}
+
+/// 附件服务：存储后端和附件ID签名
+pub struct Attachments {
+    pub store: Arc<dyn BlobStore>,
+    pub signer: FileIdSigner,
+}
+
+impl Attachments {
+    /// 根据附件存储配置创建
+    pub fn from_config() -> Result<Self, String> {
+        let config = storage_config();
+
+        let store: Arc<dyn BlobStore> = match config.backend.as_str() {
+            "s3" => Arc::new(S3BlobStore::from_config()?),
+            _ => Arc::new(LocalBlobStore::new(&config.local_dir)),
+        };
+
+        let signer = if config.file_id_secret.is_empty() {
+            tracing::warn!(
+                "未配置 STORAGE_FILE_ID_SECRET，使用随机密钥，重启后已签发的附件ID将失效"
+            );
+            FileIdSigner::random(config.file_id_ttl)
+        } else {
+            FileIdSigner::new(config.file_id_secret.as_bytes(), config.file_id_ttl)
+        };
+
+        Ok(Self { store, signer })
+    }
+}
@@ -0,0 +1,92 @@
+use crate::storage::BlobStore;
+use axum::body::Bytes;
+use futures_util::{StreamExt, TryStreamExt, future::BoxFuture};
+use kernel::config::storage_config;
+use object_store::{ObjectStore, aws::AmazonS3Builder, path::Path};
+
+/// S3 兼容存储（AWS S3、MinIO 等）
+pub struct S3BlobStore {
+    store: Box<dyn ObjectStore>,
+}
+
+impl S3BlobStore {
+    /// 根据附件存储配置创建
+    pub fn from_config() -> Result<Self, String> {
+        let config = storage_config();
+        if config.s3_bucket.is_empty() {
+            return Err("未配置 STORAGE_S3_BUCKET".to_string());
+        }
+
+        let mut builder = AmazonS3Builder::new()
+            .with_bucket_name(&config.s3_bucket)
+            .with_region(&config.s3_region)
+            .with_access_key_id(&config.s3_access_key)
+            .with_secret_access_key(&config.s3_secret_key);
+        // 自建的 S3 兼容服务使用路径风格访问，并允许 http
+        if !config.s3_endpoint.is_empty() {
+            builder = builder
+                .with_endpoint(&config.s3_endpoint)
+                .with_virtual_hosted_style_request(false)
+                .with_allow_http(config.s3_endpoint.starts_with("http://"));
+        }
+
+        let store = builder
+            .build()
+            .map_err(|e| format!("创建 S3 存储失败: {}", e))?;
+        Ok(Self {
+            store: Box::new(store),
+        })
+    }
+}
+
+impl BlobStore for S3BlobStore {
+    fn put<'a>(&'a self, key: &'a str, data: Bytes) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            self.store
+                .put(&Path::from(key), data.into())
+                .await
+                .map_err(|e| format!("上传对象失败: {}", e))?;
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Bytes>, String>> {
+        Box::pin(async move {
+            let result = match self.store.get(&Path::from(key)).await {
+                Ok(result) => result,
+                Err(object_store::Error::NotFound { .. }) => return Ok(None),
+                Err(e) => return Err(format!("读取对象失败: {}", e)),
+            };
+            let data = result
+                .bytes()
+                .await
+                .map_err(|e| format!("读取对象失败: {}", e))?;
+            Ok(Some(data))
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            match self.store.delete(&Path::from(key)).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+                Err(e) => Err(format!("删除对象失败: {}", e)),
+            }
+        })
+    }
+
+    fn delete_dir<'a>(&'a self, dir: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let locations = self
+                .store
+                .list(Some(&Path::from(dir)))
+                .map_ok(|meta| meta.location)
+                .boxed();
+            self.store
+                .delete_stream(locations)
+                .try_collect::<Vec<_>>()
+                .await
+                .map_err(|e| format!("删除对象失败: {}", e))?;
+            Ok(())
+        })
+    }
+}
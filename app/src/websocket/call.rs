@@ -0,0 +1,662 @@
+use crate::websocket::session::{ChatSession, SESSION_IDLE_TTL_SECONDS};
+use futures_util::future::BoxFuture;
+use redis::{AsyncCommands, Script, aio::MultiplexedConnection};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{sync::RwLock, task::JoinHandle};
+
+/// SDP 的最大长度
+pub const MAX_SDP_LENGTH: usize = 32 * 1024;
+/// ICE 候选字符串的最大长度
+pub const MAX_CANDIDATE_LENGTH: usize = 1024;
+
+/// 通话状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallStatus {
+    /// 已呼叫，等待对方接听
+    Ringing,
+    /// 对方已接听
+    Accepted,
+    /// 任一方挂断或会话结束
+    Ended,
+    /// 超时未接听
+    TimedOut,
+}
+
+/// ICE 候选，字段与浏览器的 RTCIceCandidateInit 一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IceCandidateInit {
+    pub candidate: String,
+    pub sdp_mid: Option<String>,
+    pub sdp_m_line_index: Option<u16>,
+    pub username_fragment: Option<String>,
+}
+
+/// 一对一会话中的语音/视频通话
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Call {
+    pub id: String,
+    pub session_id: String,
+    /// 发起方
+    pub caller: String,
+    /// 接听方
+    pub callee: String,
+    pub video: bool,
+    pub status: CallStatus,
+    pub started_at: u64,
+}
+
+impl Call {
+    /// 通话的另一方，不是通话成员时返回 None
+    pub fn peer_of(&self, client_id: &str) -> Option<&str> {
+        if self.caller == client_id {
+            Some(&self.callee)
+        } else if self.callee == client_id {
+            Some(&self.caller)
+        } else {
+            None
+        }
+    }
+}
+
+/// 通话存储
+///
+/// 每个用户同时只能有一个通话；发起、接听和结束通话都必须是原子操作
+pub trait CallStore: Send + Sync {
+    /// 保存新通话。双方任一方已在通话中时不保存，返回已在通话中的一方
+    fn insert(&self, call: Call) -> BoxFuture<'_, Result<Option<String>, String>>;
+
+    /// 按ID获取通话
+    fn get<'a>(&'a self, call_id: &'a str) -> BoxFuture<'a, Result<Option<Call>, String>>;
+
+    /// 某个用户正在进行的通话ID
+    fn active_of<'a>(&'a self, client_id: &'a str)
+    -> BoxFuture<'a, Result<Option<String>, String>>;
+
+    /// 把振铃中的通话改为已接听，返回接听后的通话；通话不存在或不在振铃中时返回 None
+    fn accept<'a>(&'a self, call_id: &'a str) -> BoxFuture<'a, Result<Option<Call>, String>>;
+
+    /// 移除通话，ringing_only 为 true 时只移除仍在振铃的通话，返回被移除的通话
+    fn remove<'a>(
+        &'a self,
+        call_id: &'a str,
+        ringing_only: bool,
+    ) -> BoxFuture<'a, Result<Option<Call>, String>>;
+}
+
+#[derive(Default)]
+struct CallIndex {
+    /// call_id -> 通话
+    calls: HashMap<String, Call>,
+    /// client_id -> 正在进行的 call_id
+    active: HashMap<String, String>,
+}
+
+/// 进程内通话存储
+#[derive(Default)]
+pub struct MemoryCallStore {
+    index: RwLock<CallIndex>,
+}
+
+impl MemoryCallStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CallStore for MemoryCallStore {
+    fn insert(&self, call: Call) -> BoxFuture<'_, Result<Option<String>, String>> {
+        Box::pin(async move {
+            let mut index = self.index.write().await;
+            for client_id in [&call.caller, &call.callee] {
+                if index.active.contains_key(client_id) {
+                    return Ok(Some(client_id.clone()));
+                }
+            }
+            index.active.insert(call.caller.clone(), call.id.clone());
+            index.active.insert(call.callee.clone(), call.id.clone());
+            index.calls.insert(call.id.clone(), call);
+            Ok(None)
+        })
+    }
+
+    fn get<'a>(&'a self, call_id: &'a str) -> BoxFuture<'a, Result<Option<Call>, String>> {
+        Box::pin(async move { Ok(self.index.read().await.calls.get(call_id).cloned()) })
+    }
+
+    fn active_of<'a>(
+        &'a self,
+        client_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, String>> {
+        Box::pin(async move { Ok(self.index.read().await.active.get(client_id).cloned()) })
+    }
+
+    fn accept<'a>(&'a self, call_id: &'a str) -> BoxFuture<'a, Result<Option<Call>, String>> {
+        Box::pin(async move {
+            let mut index = self.index.write().await;
+            match index.calls.get_mut(call_id) {
+                Some(call) if call.status == CallStatus::Ringing => {
+                    call.status = CallStatus::Accepted;
+                    Ok(Some(call.clone()))
+                }
+                _ => Ok(None),
+            }
+        })
+    }
+
+    fn remove<'a>(
+        &'a self,
+        call_id: &'a str,
+        ringing_only: bool,
+    ) -> BoxFuture<'a, Result<Option<Call>, String>> {
+        Box::pin(async move {
+            let mut index = self.index.write().await;
+            match index.calls.get(call_id) {
+                Some(call) if !ringing_only || call.status == CallStatus::Ringing => {}
+                _ => return Ok(None),
+            }
+            let call = index.calls.remove(call_id).unwrap();
+            for client_id in [&call.caller, &call.callee] {
+                if index.active.get(client_id) == Some(&call.id) {
+                    index.active.remove(client_id);
+                }
+            }
+            Ok(Some(call))
+        })
+    }
+}
+
+/// 通话：call_id -> Call JSON
+const CALL_KEY_PREFIX: &str = "ws:call:";
+/// 用户正在进行的通话：client_id -> call_id
+const CLIENT_KEY_PREFIX: &str = "ws:call:client:";
+
+/// 保存通话：KEYS[1] 通话，KEYS[2]、KEYS[3] 双方的当前通话；ARGV[1] 通话ID，ARGV[2] 通话，ARGV[3] 过期时间，
+/// ARGV[4]、ARGV[5] 双方ID。任一方已在通话中时返回该方ID，当前通话已不存在的索引视为失效
+const INSERT_SCRIPT: &str = r#"
+for i = 2, 3 do
+    local active = redis.call('GET', KEYS[i])
+    if active and redis.call('EXISTS', 'ws:call:' .. active) == 1 then
+        return ARGV[i + 2]
+    end
+end
+redis.call('SET', KEYS[1], ARGV[2], 'EX', ARGV[3])
+redis.call('SET', KEYS[2], ARGV[1], 'EX', ARGV[3])
+redis.call('SET', KEYS[3], ARGV[1], 'EX', ARGV[3])
+return false
+"#;
+
+/// 接听：通话仍在振铃时改为已接听，返回接听后的通话
+const ACCEPT_SCRIPT: &str = r#"
+local raw = redis.call('GET', KEYS[1])
+if not raw then
+    return false
+end
+local call = cjson.decode(raw)
+if call.status ~= 'ringing' then
+    return false
+end
+call.status = 'accepted'
+raw = cjson.encode(call)
+redis.call('SET', KEYS[1], raw, 'EX', ARGV[1])
+return raw
+"#;
+
+/// 移除通话：ARGV[1] 为 1 时只移除仍在振铃的通话，同时清理双方的当前通话，返回被移除的通话
+const REMOVE_SCRIPT: &str = r#"
+local raw = redis.call('GET', KEYS[1])
+if not raw then
+    return false
+end
+local call = cjson.decode(raw)
+if ARGV[1] == '1' and call.status ~= 'ringing' then
+    return false
+end
+for _, client in ipairs({call.caller, call.callee}) do
+    local key = 'ws:call:client:' .. client
+    if redis.call('GET', key) == call.id then
+        redis.call('DEL', key)
+    end
+end
+redis.call('DEL', KEYS[1])
+return raw
+"#;
+
+/// 基于 redis 的通话存储，集群中的节点共享通话状态，双方连接在不同节点时也能接听和挂断
+pub struct RedisCallStore {
+    conn: MultiplexedConnection,
+}
+
+impl RedisCallStore {
+    pub fn new(conn: MultiplexedConnection) -> Self {
+        Self { conn }
+    }
+}
+
+fn decode(raw: Option<String>) -> Result<Option<Call>, String> {
+    raw.map(|raw| serde_json::from_str(&raw).map_err(|e| format!("解析失败: {}", e)))
+        .transpose()
+}
+
+impl CallStore for RedisCallStore {
+    fn insert(&self, call: Call) -> BoxFuture<'_, Result<Option<String>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let raw = serde_json::to_string(&call).map_err(|e| format!("序列化失败: {}", e))?;
+            Script::new(INSERT_SCRIPT)
+                .key(format!("{}{}", CALL_KEY_PREFIX, call.id))
+                .key(format!("{}{}", CLIENT_KEY_PREFIX, call.caller))
+                .key(format!("{}{}", CLIENT_KEY_PREFIX, call.callee))
+                .arg(&call.id)
+                .arg(raw)
+                .arg(SESSION_IDLE_TTL_SECONDS)
+                .arg(&call.caller)
+                .arg(&call.callee)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| format!("保存通话失败: {}", e))
+        })
+    }
+
+    fn get<'a>(&'a self, call_id: &'a str) -> BoxFuture<'a, Result<Option<Call>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let raw: Option<String> = conn
+                .get(format!("{}{}", CALL_KEY_PREFIX, call_id))
+                .await
+                .map_err(|e| format!("读取通话失败: {}", e))?;
+            decode(raw)
+        })
+    }
+
+    fn active_of<'a>(
+        &'a self,
+        client_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            conn.get(format!("{}{}", CLIENT_KEY_PREFIX, client_id))
+                .await
+                .map_err(|e| format!("读取通话失败: {}", e))
+        })
+    }
+
+    fn accept<'a>(&'a self, call_id: &'a str) -> BoxFuture<'a, Result<Option<Call>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let raw: Option<String> = Script::new(ACCEPT_SCRIPT)
+                .key(format!("{}{}", CALL_KEY_PREFIX, call_id))
+                .arg(SESSION_IDLE_TTL_SECONDS)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| format!("接听通话失败: {}", e))?;
+            decode(raw)
+        })
+    }
+
+    fn remove<'a>(
+        &'a self,
+        call_id: &'a str,
+        ringing_only: bool,
+    ) -> BoxFuture<'a, Result<Option<Call>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let raw: Option<String> = Script::new(REMOVE_SCRIPT)
+                .key(format!("{}{}", CALL_KEY_PREFIX, call_id))
+                .arg(if ringing_only { 1 } else { 0 })
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| format!("移除通话失败: {}", e))?;
+            decode(raw)
+        })
+    }
+}
+
+/// 通话管理器：只负责信令的状态流转（振铃 -> 接听 -> 结束/超时），媒体流由双方点对点传输
+///
+/// 集群模式下通话状态保存在 redis 中；振铃超时定时器只在发起呼叫的节点上运行，
+/// 其他节点接听或挂断后定时器到期时发现通话已不在振铃，不会再结束通话
+#[derive(Clone)]
+pub struct CallManager {
+    store: Arc<dyn CallStore>,
+    /// 本节点发起的通话的振铃超时定时器：call_id -> 定时器
+    timers: Arc<RwLock<HashMap<String, JoinHandle<()>>>>,
+}
+
+impl Default for CallManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallManager {
+    /// 通话状态保存在本进程内
+    pub fn new() -> Self {
+        Self::with_store(Arc::new(MemoryCallStore::new()))
+    }
+
+    /// 通话状态保存在 redis 中，供集群中的节点共享
+    pub fn with_redis(conn: MultiplexedConnection) -> Self {
+        Self::with_store(Arc::new(RedisCallStore::new(conn)))
+    }
+
+    fn with_store(store: Arc<dyn CallStore>) -> Self {
+        Self {
+            store,
+            timers: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// 在会话中发起呼叫，双方都不能已在通话中
+    pub async fn offer(
+        &self,
+        session: &ChatSession,
+        caller: &str,
+        video: bool,
+    ) -> Result<Call, String> {
+        let Some(callee) = session.participants.iter().find(|p| *p != caller) else {
+            return Err("不是该会话的参与者".to_string());
+        };
+
+        let call = Call {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session.id.clone(),
+            caller: caller.to_string(),
+            callee: callee.clone(),
+            video,
+            status: CallStatus::Ringing,
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        };
+        match self.store.insert(call.clone()).await? {
+            Some(busy) if busy == caller => return Err("你正在通话中".to_string()),
+            Some(_) => return Err("对方正在通话中".to_string()),
+            None => {}
+        }
+        tracing::info!(
+            "用户 {} 呼叫 {}，通话 {}",
+            call.caller,
+            call.callee,
+            call.id
+        );
+
+        Ok(call)
+    }
+
+    /// 设置振铃超时定时器，通话已不在振铃时直接取消定时器
+    pub async fn set_timer(&self, call_id: &str, timer: JoinHandle<()>) {
+        let mut timers = self.timers.write().await;
+        match self.store.get(call_id).await {
+            Ok(Some(call)) if call.status == CallStatus::Ringing => {
+                timers.insert(call_id.to_string(), timer);
+            }
+            _ => timer.abort(),
+        }
+    }
+
+    /// 获取通话，只有通话双方可以获取
+    pub async fn get_for(&self, call_id: &str, client_id: &str) -> Result<Call, String> {
+        match self.store.get(call_id).await? {
+            Some(call) if call.peer_of(client_id).is_some() => Ok(call),
+            _ => Err("通话不存在或已结束".to_string()),
+        }
+    }
+
+    /// 接听：只有被叫方可以在振铃中接听
+    pub async fn answer(&self, call_id: &str, client_id: &str) -> Result<Call, String> {
+        let Some(call) = self.store.get(call_id).await? else {
+            return Err("通话不存在或已结束".to_string());
+        };
+        if call.callee != client_id {
+            return Err("只有被叫方可以接听".to_string());
+        }
+        if call.status != CallStatus::Ringing {
+            return Err("通话已接听".to_string());
+        }
+
+        // 读取后通话可能已被挂断或超时
+        let Some(call) = self.store.accept(call_id).await? else {
+            return Err("通话不存在或已结束".to_string());
+        };
+        self.cancel_timer(call_id).await;
+        Ok(call)
+    }
+
+    /// 挂断（振铃中由被叫方挂断即为拒接）
+    pub async fn hangup(&self, call_id: &str, client_id: &str) -> Result<Call, String> {
+        self.get_for(call_id, client_id).await?;
+        self.finish(call_id)
+            .await?
+            .ok_or_else(|| "通话不存在或已结束".to_string())
+    }
+
+    /// 结束通话（会话已结束或信令无法送达时调用）
+    pub async fn end(&self, call_id: &str) -> Option<Call> {
+        self.finish(call_id).await.unwrap_or_else(|e| {
+            tracing::error!("结束通话 {} 失败: {}", call_id, e);
+            None
+        })
+    }
+
+    /// 结束会话中的通话
+    pub async fn end_session(&self, session: &ChatSession) -> Option<Call> {
+        for client_id in &session.participants {
+            let call_id = match self.store.active_of(client_id).await {
+                Ok(Some(call_id)) => call_id,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::error!("获取用户 {} 的通话失败: {}", client_id, e);
+                    continue;
+                }
+            };
+            let call = match self.store.get(&call_id).await {
+                Ok(call) => call,
+                Err(e) => {
+                    tracing::error!("获取通话 {} 失败: {}", call_id, e);
+                    continue;
+                }
+            };
+            if call.is_some_and(|call| call.session_id == session.id) {
+                return self.end(&call_id).await;
+            }
+        }
+        None
+    }
+
+    /// 振铃超时，由定时器调用：通话仍在振铃时结束并返回
+    pub async fn expire(&self, call_id: &str) -> Option<Call> {
+        // 定时器就是当前任务，不能取消
+        self.timers.write().await.remove(call_id);
+        let mut call = match self.store.remove(call_id, true).await {
+            Ok(call) => call?,
+            Err(e) => {
+                tracing::error!("结束超时通话 {} 失败: {}", call_id, e);
+                return None;
+            }
+        };
+        call.status = CallStatus::TimedOut;
+        tracing::info!("通话 {} 超时未接听", call_id);
+        Some(call)
+    }
+
+    async fn finish(&self, call_id: &str) -> Result<Option<Call>, String> {
+        let Some(mut call) = self.store.remove(call_id, false).await? else {
+            return Ok(None);
+        };
+        self.cancel_timer(call_id).await;
+        call.status = CallStatus::Ended;
+        tracing::info!("通话 {} 已结束", call_id);
+        Ok(Some(call))
+    }
+
+    async fn cancel_timer(&self, call_id: &str) {
+        if let Some(timer) = self.timers.write().await.remove(call_id) {
+            timer.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::session::SessionManager;
+
+    #[tokio::test]
+    async fn offer_answer_hangup() {
+        let sessions = SessionManager::new();
+        let calls = CallManager::new();
+        let session = sessions.start("a", "b", false).await.unwrap();
+
+        let call = calls.offer(&session, "a", true).await.unwrap();
+        assert_eq!(call.callee, "b");
+        assert_eq!(call.status, CallStatus::Ringing);
+        assert!(calls.get_for(&call.id, "c").await.is_err());
+
+        // 只有被叫方可以接听，且只能接听一次
+        assert!(calls.answer(&call.id, "a").await.is_err());
+        let answered = calls.answer(&call.id, "b").await.unwrap();
+        assert_eq!(answered.status, CallStatus::Accepted);
+        assert!(calls.answer(&call.id, "b").await.is_err());
+        // 已接听的通话不会超时
+        assert!(calls.expire(&call.id).await.is_none());
+
+        assert!(calls.hangup(&call.id, "c").await.is_err());
+        let ended = calls.hangup(&call.id, "a").await.unwrap();
+        assert_eq!(ended.status, CallStatus::Ended);
+        assert!(calls.get_for(&call.id, "b").await.is_err());
+        assert!(calls.hangup(&call.id, "b").await.is_err());
+
+        // 挂断后双方都可以再次呼叫
+        assert!(calls.offer(&session, "b", false).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn busy_participants_cannot_be_called() {
+        let sessions = SessionManager::new();
+        let calls = CallManager::new();
+        let ab = sessions.start("a", "b", false).await.unwrap();
+        let ac = sessions.start("a", "c", false).await.unwrap();
+        let bd = sessions.start("b", "d", false).await.unwrap();
+
+        calls.offer(&ab, "a", false).await.unwrap();
+        assert_eq!(
+            calls.offer(&ac, "a", false).await.unwrap_err(),
+            "你正在通话中"
+        );
+        assert_eq!(
+            calls.offer(&bd, "d", false).await.unwrap_err(),
+            "对方正在通话中"
+        );
+        assert!(calls.offer(&ab, "b", false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn ringing_call_times_out() {
+        let sessions = SessionManager::new();
+        let calls = CallManager::new();
+        let session = sessions.start("a", "b", false).await.unwrap();
+        let call = calls.offer(&session, "a", false).await.unwrap();
+
+        let expired = calls.expire(&call.id).await.unwrap();
+        assert_eq!(expired.status, CallStatus::TimedOut);
+        assert!(calls.answer(&call.id, "b").await.is_err());
+        assert!(calls.offer(&session, "a", false).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn disconnect_ends_calls_in_ended_sessions() {
+        let sessions = SessionManager::new();
+        let calls = CallManager::new();
+        let ab = sessions.start("a", "b", false).await.unwrap();
+        let cd = sessions.start("c", "d", false).await.unwrap();
+        let ab_call = calls.offer(&ab, "a", false).await.unwrap();
+        let cd_call = calls.offer(&cd, "c", false).await.unwrap();
+        calls.answer(&ab_call.id, "b").await.unwrap();
+
+        // b 断开连接：结束其参与的会话和其中的通话
+        for session in sessions.end_all("b").await {
+            let ended = calls.end_session(&session).await.unwrap();
+            assert_eq!(ended.id, ab_call.id);
+            assert_eq!(ended.status, CallStatus::Ended);
+        }
+        assert!(calls.get_for(&ab_call.id, "a").await.is_err());
+        // 其他会话中的通话不受影响
+        assert!(calls.get_for(&cd_call.id, "d").await.is_ok());
+        assert!(calls.end_session(&ab).await.is_none());
+
+        // a 不再处于通话中
+        let ae = sessions.start("a", "e", false).await.unwrap();
+        assert!(calls.offer(&ae, "a", false).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn timer_is_dropped_once_answered() {
+        let sessions = SessionManager::new();
+        let calls = CallManager::new();
+        let session = sessions.start("a", "b", false).await.unwrap();
+        let call = calls.offer(&session, "a", false).await.unwrap();
+
+        let timer = tokio::spawn(std::future::pending::<()>());
+        calls.set_timer(&call.id, timer).await;
+        calls.answer(&call.id, "b").await.unwrap();
+        assert!(calls.timers.read().await.is_empty());
+
+        // 通话已不在振铃时设置的定时器直接取消
+        let timer = tokio::spawn(std::future::pending::<()>());
+        let handle = timer.abort_handle();
+        calls.set_timer(&call.id, timer).await;
+        tokio::task::yield_now().await;
+        assert!(handle.is_finished());
+    }
+
+    /// 需要本地 redis-server：cargo test -p app call -- --ignored
+    #[tokio::test]
+    #[ignore = "需要本地 redis-server"]
+    async fn redis_calls_are_shared() {
+        let url = std::env::var("TEST_REDIS_URL")
+            .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let client = redis::Client::open(url).unwrap();
+        let node_a =
+            CallManager::with_redis(client.get_multiplexed_async_connection().await.unwrap());
+        let node_b =
+            CallManager::with_redis(client.get_multiplexed_async_connection().await.unwrap());
+        let sessions =
+            SessionManager::with_redis(client.get_multiplexed_async_connection().await.unwrap());
+        let a = uuid::Uuid::new_v4().to_string();
+        let b = uuid::Uuid::new_v4().to_string();
+        let session = sessions.start(&a, &b, false).await.unwrap();
+
+        // 主叫连接在 A 节点，被叫连接在 B 节点
+        let call = node_a.offer(&session, &a, true).await.unwrap();
+        assert!(node_b.offer(&session, &b, false).await.is_err());
+        let answered = node_b.answer(&call.id, &b).await.unwrap();
+        assert_eq!(answered.status, CallStatus::Accepted);
+        assert!(answered.video);
+        // A 节点上的振铃定时器到期时通话已接听
+        assert!(node_a.expire(&call.id).await.is_none());
+        assert_eq!(
+            node_a.get_for(&call.id, &a).await.unwrap().status,
+            CallStatus::Accepted
+        );
+
+        // B 节点上挂断后双方在任一节点上都不再处于通话中
+        node_b.hangup(&call.id, &b).await.unwrap();
+        assert!(node_a.get_for(&call.id, &a).await.is_err());
+        let again = node_a.offer(&session, &a, false).await.unwrap();
+
+        // 会话在另一个节点上结束时其中的通话一并结束
+        let ended = node_b.end_session(&session).await.unwrap();
+        assert_eq!(ended.id, again.id);
+        assert!(node_a.offer(&session, &b, false).await.is_ok());
+        node_a.end_session(&session).await;
+        sessions.end(&a, &b).await;
+    }
+}
@@ -0,0 +1,422 @@
+use crate::websocket::types::ClientInfo;
+use kernel::redis::{RedisResult, model::RedisPool};
+use redis::{Script, aio::MultiplexedConnection, aio::PubSub};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 客户端所在节点：client_id -> node_id
+const CLIENTS_KEY: &str = "ws:clients";
+/// 所有注册过的节点
+const NODES_KEY: &str = "ws:nodes";
+/// 全局广播频道
+pub const BROADCAST_CHANNEL: &str = "ws:broadcast";
+/// 节点存活标记的过期时间（秒）
+pub const NODE_TTL_SECONDS: u64 = 30;
+
+/// 节点存活标记
+fn node_alive_key(node_id: &str) -> String {
+    format!("ws:node:{}:alive", node_id)
+}
+
+/// 节点上的客户端：client_id -> connected_at
+fn node_clients_key(node_id: &str) -> String {
+    format!("ws:node:{}:clients", node_id)
+}
+
+/// 节点的定向消息频道
+pub fn node_channel(node_id: &str) -> String {
+    format!("ws:node:{}", node_id)
+}
+
+/// 注册客户端：已被其他存活节点占用时返回 0
+const REGISTER_SCRIPT: &str = r#"
+local owner = redis.call('HGET', KEYS[1], ARGV[1])
+if owner and owner ~= ARGV[2] and redis.call('EXISTS', 'ws:node:' .. owner .. ':alive') == 1 then
+    return 0
+end
+if owner and owner ~= ARGV[2] then
+    redis.call('HDEL', 'ws:node:' .. owner .. ':clients', ARGV[1])
+end
+redis.call('HSET', KEYS[1], ARGV[1], ARGV[2])
+redis.call('HSET', KEYS[2], ARGV[1], ARGV[3])
+return 1
+"#;
+
+/// 注销客户端：只删除属于本节点的归属记录
+const UNREGISTER_SCRIPT: &str = r#"
+if redis.call('HGET', KEYS[1], ARGV[1]) == ARGV[2] then
+    redis.call('HDEL', KEYS[1], ARGV[1])
+end
+redis.call('HDEL', KEYS[2], ARGV[1])
+return 1
+"#;
+
+/// 节点间传递的事件
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum ClusterEvent {
+    /// 发给某个客户端的消息
+    Direct { target: String, payload: String },
+    /// 发给所有客户端的消息
+    Broadcast {
+        origin: String,
+        exclude: Option<String>,
+        payload: String,
+    },
+    /// 断开某个客户端，指定 token 时只断开使用该令牌或登录会话建立的连接
+    Disconnect {
+        target: String,
+        #[serde(default)]
+        token: Option<String>,
+        reason: String,
+    },
+}
+
+/// ws 集群总线：在 redis 中登记连接归属，并通过发布订阅跨节点投递消息
+#[derive(Clone)]
+pub struct ClusterBus {
+    node_id: String,
+    pool: RedisPool,
+    conn: MultiplexedConnection,
+}
+
+impl ClusterBus {
+    /// 连接 redis 并创建集群总线
+    pub async fn connect(redis_url: &str, node_id: String) -> RedisResult<Self> {
+        let pool = RedisPool::new(redis_url)?;
+        let conn = pool.get_connection().await?;
+
+        Ok(Self {
+            node_id,
+            pool,
+            conn,
+        })
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// 共享的 redis 连接
+    pub fn connection(&self) -> MultiplexedConnection {
+        self.conn.clone()
+    }
+
+    /// 订阅本节点频道和全局广播频道
+    pub async fn subscribe(&self) -> RedisResult<PubSub> {
+        let mut pubsub = self.pool.get_pubsub().await?;
+        pubsub.subscribe(node_channel(&self.node_id)).await?;
+        pubsub.subscribe(BROADCAST_CHANNEL).await?;
+        Ok(pubsub)
+    }
+
+    /// 刷新本节点存活标记，并清理已失效节点留下的连接记录
+    pub async fn heartbeat(&self) -> RedisResult<()> {
+        let mut conn = self.conn.clone();
+
+        let _: () = redis::cmd("SET")
+            .arg(node_alive_key(&self.node_id))
+            .arg(1)
+            .arg("EX")
+            .arg(NODE_TTL_SECONDS)
+            .query_async(&mut conn)
+            .await?;
+        let _: usize = redis::cmd("SADD")
+            .arg(NODES_KEY)
+            .arg(&self.node_id)
+            .query_async(&mut conn)
+            .await?;
+
+        let nodes: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(NODES_KEY)
+            .query_async(&mut conn)
+            .await?;
+        for node in nodes {
+            if node == self.node_id || self.is_alive(&node).await? {
+                continue;
+            }
+
+            let clients: Vec<String> = redis::cmd("HKEYS")
+                .arg(node_clients_key(&node))
+                .query_async(&mut conn)
+                .await?;
+            for client_id in clients {
+                let _: i32 = Script::new(UNREGISTER_SCRIPT)
+                    .key(CLIENTS_KEY)
+                    .key(node_clients_key(&node))
+                    .arg(&client_id)
+                    .arg(&node)
+                    .invoke_async(&mut conn)
+                    .await?;
+            }
+            let _: usize = redis::cmd("SREM")
+                .arg(NODES_KEY)
+                .arg(&node)
+                .query_async(&mut conn)
+                .await?;
+            tracing::info!("ws 集群节点 {} 已失效，清理其连接记录", node);
+        }
+
+        Ok(())
+    }
+
+    /// 登记客户端归属本节点，客户端已在其他存活节点在线时返回 false
+    pub async fn register(&self, client_id: &str, connected_at: u64) -> RedisResult<bool> {
+        let mut conn = self.conn.clone();
+        let registered: i32 = Script::new(REGISTER_SCRIPT)
+            .key(CLIENTS_KEY)
+            .key(node_clients_key(&self.node_id))
+            .arg(client_id)
+            .arg(&self.node_id)
+            .arg(connected_at)
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok(registered == 1)
+    }
+
+    /// 删除客户端的归属记录
+    pub async fn unregister(&self, client_id: &str) -> RedisResult<()> {
+        let mut conn = self.conn.clone();
+        let _: i32 = Script::new(UNREGISTER_SCRIPT)
+            .key(CLIENTS_KEY)
+            .key(node_clients_key(&self.node_id))
+            .arg(client_id)
+            .arg(&self.node_id)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// 查找客户端所在的存活节点
+    pub async fn locate(&self, client_id: &str) -> RedisResult<Option<String>> {
+        let mut conn = self.conn.clone();
+        let owner: Option<String> = redis::cmd("HGET")
+            .arg(CLIENTS_KEY)
+            .arg(client_id)
+            .query_async(&mut conn)
+            .await?;
+
+        match owner {
+            Some(node) if self.is_alive(&node).await? => Ok(Some(node)),
+            _ => Ok(None),
+        }
+    }
+
+    /// 向指定节点上的客户端投递消息
+    pub async fn send_direct(&self, node_id: &str, target: &str, payload: &str) -> RedisResult<()> {
+        let event = ClusterEvent::Direct {
+            target: target.to_string(),
+            payload: payload.to_string(),
+        };
+        self.publish(&node_channel(node_id), &event).await
+    }
+
+    /// 向其他节点广播消息
+    pub async fn broadcast(&self, exclude: Option<&str>, payload: &str) -> RedisResult<()> {
+        let event = ClusterEvent::Broadcast {
+            origin: self.node_id.clone(),
+            exclude: exclude.map(|e| e.to_string()),
+            payload: payload.to_string(),
+        };
+        self.publish(BROADCAST_CHANNEL, &event).await
+    }
+
+    /// 通知指定节点断开客户端
+    pub async fn disconnect(
+        &self,
+        node_id: &str,
+        target: &str,
+        token: Option<&str>,
+        reason: &str,
+    ) -> RedisResult<()> {
+        let event = ClusterEvent::Disconnect {
+            target: target.to_string(),
+            token: token.map(|token| token.to_string()),
+            reason: reason.to_string(),
+        };
+        self.publish(&node_channel(node_id), &event).await
+    }
+
+    /// 所有存活节点上的连接数之和
+    pub async fn online_count(&self) -> RedisResult<usize> {
+        let mut conn = self.conn.clone();
+        let mut count = 0;
+        for node in self.alive_nodes().await? {
+            let len: usize = redis::cmd("HLEN")
+                .arg(node_clients_key(&node))
+                .query_async(&mut conn)
+                .await?;
+            count += len;
+        }
+        Ok(count)
+    }
+
+    /// 所有存活节点上的客户端
+    pub async fn list_clients(&self) -> RedisResult<Vec<ClientInfo>> {
+        let mut conn = self.conn.clone();
+        let mut clients = Vec::new();
+        for node in self.alive_nodes().await? {
+            let entries: HashMap<String, u64> = redis::cmd("HGETALL")
+                .arg(node_clients_key(&node))
+                .query_async(&mut conn)
+                .await?;
+            clients.extend(
+                entries
+                    .into_iter()
+                    .map(|(id, connected_at)| ClientInfo { id, connected_at }),
+            );
+        }
+        Ok(clients)
+    }
+
+    async fn alive_nodes(&self) -> RedisResult<Vec<String>> {
+        let mut conn = self.conn.clone();
+        let nodes: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(NODES_KEY)
+            .query_async(&mut conn)
+            .await?;
+
+        let mut alive = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            if self.is_alive(&node).await? {
+                alive.push(node);
+            }
+        }
+        Ok(alive)
+    }
+
+    async fn is_alive(&self, node_id: &str) -> RedisResult<bool> {
+        let mut conn = self.conn.clone();
+        let exists: bool = redis::cmd("EXISTS")
+            .arg(node_alive_key(node_id))
+            .query_async(&mut conn)
+            .await?;
+        Ok(exists)
+    }
+
+    async fn publish(&self, channel: &str, event: &ClusterEvent) -> RedisResult<()> {
+        let mut conn = self.conn.clone();
+        let payload = serde_json::to_string(event)?;
+        let _: usize = redis::cmd("PUBLISH")
+            .arg(channel)
+            .arg(payload)
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+}
+
+/// 集群测试需要本地 redis-server：`cargo test -p app cluster -- --ignored`，
+/// 地址默认 `redis://127.0.0.1:6379`，可通过 `TEST_REDIS_URL` 指定
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::types::ConnectionManager;
+    use kernel::auth::token::{Claims, TokenKind};
+    use std::time::Duration;
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+    use tokio::time;
+
+    fn redis_url() -> String {
+        std::env::var("TEST_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string())
+    }
+
+    /// 同一进程内启动一个集群节点
+    async fn start_node() -> ConnectionManager {
+        let node_id = uuid::Uuid::new_v4().to_string();
+        let bus = ClusterBus::connect(&redis_url(), node_id)
+            .await
+            .expect("连接 redis 失败");
+        ConnectionManager::with_cluster(bus)
+            .await
+            .expect("加入集群失败")
+    }
+
+    fn claims(sub: &str) -> Claims {
+        Claims {
+            sub: sub.to_string(),
+            kind: TokenKind::Guest,
+            jti: uuid::Uuid::new_v4().to_string(),
+            sid: None,
+            ip: None,
+            iss: "test".to_string(),
+            iat: 0,
+            exp: u64::MAX,
+        }
+    }
+
+    /// 在节点上建立一个客户端连接，返回客户端ID和接收通道
+    async fn connect(node: &ConnectionManager) -> (String, UnboundedReceiver<String>) {
+        let client_id = uuid::Uuid::new_v4().to_string();
+        let (tx, rx) = mpsc::unbounded_channel();
+        assert!(
+            node.register(client_id.clone(), tx, &claims(&client_id))
+                .await
+        );
+        (client_id, rx)
+    }
+
+    async fn recv(rx: &mut UnboundedReceiver<String>) -> Option<String> {
+        time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("等待消息超时")
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地 redis-server"]
+    async fn client_cannot_join_two_nodes() {
+        let (node_a, node_b) = (start_node().await, start_node().await);
+        let (alice, _alice_rx) = connect(&node_a).await;
+
+        let (tx, _rx) = mpsc::unbounded_channel();
+        assert!(!node_b.register(alice.clone(), tx, &claims(&alice)).await);
+        assert!(node_b.is_online(&alice).await);
+
+        // 原节点断开后可以连接其他节点
+        node_a.unregister(&alice).await;
+        let (tx, _rx) = mpsc::unbounded_channel();
+        assert!(node_b.register(alice.clone(), tx, &claims(&alice)).await);
+        node_b.unregister(&alice).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地 redis-server"]
+    async fn messages_cross_nodes() {
+        let (node_a, node_b) = (start_node().await, start_node().await);
+        let (alice, mut alice_rx) = connect(&node_a).await;
+        let (bob, mut bob_rx) = connect(&node_b).await;
+
+        // 定向消息转发到对方所在节点
+        node_a.send_to(&bob, "hello".to_string()).await.unwrap();
+        assert_eq!(recv(&mut bob_rx).await.as_deref(), Some("hello"));
+        node_b.send_to(&alice, "hi".to_string()).await.unwrap();
+        assert_eq!(recv(&mut alice_rx).await.as_deref(), Some("hi"));
+
+        // 广播送达其他节点，不发给发送者
+        node_a.broadcast(1, &alice, "大家好", 0).await;
+        assert!(recv(&mut bob_rx).await.unwrap().contains("大家好"));
+        assert!(alice_rx.try_recv().is_err());
+
+        // 在线列表包含所有节点上的客户端
+        let clients = node_b.list_clients().await;
+        assert!(clients.iter().any(|c| c.id == alice));
+        assert!(clients.iter().any(|c| c.id == bob));
+
+        node_a.unregister(&alice).await;
+        node_b.unregister(&bob).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地 redis-server"]
+    async fn disconnect_cross_nodes() {
+        let (node_a, node_b) = (start_node().await, start_node().await);
+        let (bob, mut bob_rx) = connect(&node_b).await;
+
+        node_a.disconnect(&bob, "测试断开").await;
+        assert!(recv(&mut bob_rx).await.unwrap().contains("测试断开"));
+        // 发送通道随连接移除而关闭
+        assert_eq!(recv(&mut bob_rx).await, None);
+        assert!(node_a.send_to(&bob, "hello".to_string()).await.is_err());
+    }
+}
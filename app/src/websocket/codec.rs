@@ -0,0 +1,202 @@
+use crate::websocket::types::ClientMessage;
+use axum::{
+    extract::ws::{Message, Utf8Bytes, WebSocketUpgrade},
+    http::HeaderValue,
+};
+
+/// 连接使用的消息编码，升级时通过 Sec-WebSocket-Protocol 协商
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireFormat {
+    /// JSON 文本帧（默认）
+    Json,
+    /// MessagePack 二进制帧
+    MessagePack,
+}
+
+impl WireFormat {
+    /// 支持的子协议，客户端同时请求多个时按此顺序优先选择
+    pub const PROTOCOLS: [&'static str; 2] = ["msgpack", "json"];
+
+    /// 协商子协议：在客户端请求的子协议中按 PROTOCOLS 的顺序选择，返回选定的编码
+    pub fn negotiate(ws: WebSocketUpgrade) -> (WebSocketUpgrade, Self) {
+        let ws = ws.protocols(Self::PROTOCOLS);
+        let format = Self::from_protocol(ws.selected_protocol());
+        (ws, format)
+    }
+
+    /// 根据协商结果选择编码，客户端未请求子协议时使用 JSON
+    pub fn from_protocol(protocol: Option<&HeaderValue>) -> Self {
+        match protocol.and_then(|protocol| protocol.to_str().ok()) {
+            Some("msgpack") => WireFormat::MessagePack,
+            _ => WireFormat::Json,
+        }
+    }
+
+    /// 把服务端消息编码为 websocket 帧
+    ///
+    /// 服务端消息统一以 JSON 在连接通道和集群间传递，MessagePack 连接在发送前转码，两种编码共用同一套 serde 模型
+    pub fn encode(&self, message: String) -> Result<Message, String> {
+        match self {
+            WireFormat::Json => Ok(Message::Text(Utf8Bytes::from(message))),
+            WireFormat::MessagePack => {
+                let value = serde_json::from_str::<serde_json::Value>(&message)
+                    .map_err(|e| format!("消息解析失败: {}", e))?;
+                let data =
+                    rmp_serde::to_vec_named(&value).map_err(|e| format!("序列化失败: {}", e))?;
+                Ok(Message::Binary(data.into()))
+            }
+        }
+    }
+
+    /// 解析二进制帧，只有协商了 MessagePack 的连接可以发送二进制消息
+    pub fn decode_binary(&self, data: &[u8]) -> Result<ClientMessage, String> {
+        match self {
+            WireFormat::MessagePack => {
+                rmp_serde::from_slice(data).map_err(|e| format!("消息格式错误: {}", e))
+            }
+            WireFormat::Json => Err("不支持二进制消息".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::content::MessageContent;
+    use crate::websocket::types::{MessageStruct, ServerMessage};
+    use axum::{Router, response::IntoResponse, routing::get};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        sync::mpsc,
+    };
+
+    fn private_message() -> ClientMessage {
+        ClientMessage::Private {
+            to: "b".to_string(),
+            message: MessageStruct {
+                burn_after_read: true,
+                content: MessageContent::Location {
+                    latitude: 31.23,
+                    longitude: 121.47,
+                    name: None,
+                },
+            },
+        }
+    }
+
+    fn server_message() -> ServerMessage {
+        ServerMessage::Hello {
+            accepted: true,
+            protocol_version: 2,
+            min_protocol_version: 1,
+            capabilities: vec!["msgpack".to_string()],
+        }
+    }
+
+    fn json_value<T: serde::Serialize>(message: &T) -> serde_json::Value {
+        serde_json::to_value(message).unwrap()
+    }
+
+    #[test]
+    fn client_message_round_trips() {
+        let message = private_message();
+
+        let json = serde_json::to_string(&message).unwrap();
+        let decoded: ClientMessage = serde_json::from_str(&json).unwrap();
+        assert_eq!(json_value(&decoded), json_value(&message));
+
+        let data = rmp_serde::to_vec_named(&message).unwrap();
+        let decoded = WireFormat::MessagePack.decode_binary(&data).unwrap();
+        assert_eq!(json_value(&decoded), json_value(&message));
+
+        // JSON 连接不接受二进制帧
+        assert!(WireFormat::Json.decode_binary(&data).is_err());
+        assert!(
+            WireFormat::MessagePack
+                .decode_binary(b"not msgpack")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn server_message_round_trips() {
+        let message = server_message();
+        let json = serde_json::to_string(&message).unwrap();
+
+        match WireFormat::Json.encode(json.clone()).unwrap() {
+            Message::Text(text) => {
+                let decoded: ServerMessage = serde_json::from_str(text.as_str()).unwrap();
+                assert_eq!(json_value(&decoded), json_value(&message));
+            }
+            other => panic!("JSON 连接应发送文本帧: {:?}", other),
+        }
+        match WireFormat::MessagePack.encode(json).unwrap() {
+            Message::Binary(data) => {
+                let decoded: ServerMessage = rmp_serde::from_slice(&data).unwrap();
+                assert_eq!(json_value(&decoded), json_value(&message));
+            }
+            other => panic!("MessagePack 连接应发送二进制帧: {:?}", other),
+        }
+    }
+
+    /// 向测试服务发起升级请求，返回响应头中选定的子协议和服务端协商出的编码
+    async fn negotiate(requested: Option<&str>) -> (Option<String>, WireFormat) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/",
+            get(move |ws: WebSocketUpgrade| async move {
+                let (ws, format) = WireFormat::negotiate(ws);
+                tx.send(format).unwrap();
+                ws.on_upgrade(|_| async {}).into_response()
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut request = "GET / HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\n\
+            Upgrade: websocket\r\nSec-WebSocket-Version: 13\r\n\
+            Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n"
+            .to_string();
+        if let Some(requested) = requested {
+            request.push_str(&format!("Sec-WebSocket-Protocol: {}\r\n", requested));
+        }
+        request.push_str("\r\n");
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            let mut buf = [0u8; 1024];
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "连接在响应头结束前关闭");
+            response.extend_from_slice(&buf[..n]);
+        }
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101"), "{}", response);
+
+        let protocol = response.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("sec-websocket-protocol")
+                .then(|| value.trim().to_string())
+        });
+        (protocol, rx.recv().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn negotiates_wire_format() {
+        // 同时请求时优先选择 MessagePack，与客户端列出的顺序无关
+        assert_eq!(
+            negotiate(Some("json, msgpack")).await,
+            (Some("msgpack".to_string()), WireFormat::MessagePack)
+        );
+        assert_eq!(
+            negotiate(Some("json")).await,
+            (Some("json".to_string()), WireFormat::Json)
+        );
+        // 未请求或只请求不支持的子协议时使用 JSON，且不回应子协议
+        assert_eq!(negotiate(Some("cbor")).await, (None, WireFormat::Json));
+        assert_eq!(negotiate(None).await, (None, WireFormat::Json));
+    }
+}
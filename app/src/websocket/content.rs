@@ -0,0 +1,321 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+
+/// 文本消息的最大字符数
+pub const MAX_TEXT_LENGTH: usize = 2000;
+/// 语音消息的最长时长（秒）
+const MAX_VOICE_SECONDS: u32 = 60;
+/// 图片宽高的上限（像素）
+const MAX_IMAGE_SIDE: u32 = 10000;
+/// 加密消息密文的最大字节数
+const MAX_ENCRYPTED_SIZE: usize = 64 * 1024;
+/// 旧版客户端其他类型消息 text 的最大字节数，不超过入库字段的容量
+const MAX_LEGACY_SIZE: usize = 64 * 1024;
+/// 端到端加密公钥的最大长度
+pub const MAX_PUBLIC_KEY_LENGTH: usize = 1024;
+
+/// 私聊消息内容，按 `kind` 区分
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum MessageContent {
+    #[serde(rename = "text")]
+    Text { text: String },
+    #[serde(rename = "emoji")]
+    Emoji { code: String },
+    #[serde(rename = "sticker")]
+    Sticker { pack_id: String, sticker_id: String },
+    /// 图片，引用已上传的附件
+    #[serde(rename = "image")]
+    Image {
+        file_id: String,
+        /// 上传时生成的缩略图
+        thumbnail_id: Option<String>,
+        width: Option<u32>,
+        height: Option<u32>,
+    },
+    /// 语音，引用已上传的附件
+    #[serde(rename = "voice")]
+    Voice { file_id: String, duration: u32 },
+    #[serde(rename = "location")]
+    Location {
+        latitude: f64,
+        longitude: f64,
+        name: Option<String>,
+    },
+    /// 端到端加密的消息，payload 为 base64 编码的密文，服务端不解析内容
+    #[serde(rename = "encrypted")]
+    Encrypted { payload: String },
+    /// 旧版客户端发送的其他数字类型，原样转发
+    #[serde(rename = "legacy", skip_deserializing)]
+    Legacy { code: u32, text: String },
+}
+
+impl MessageContent {
+    /// 对应旧版协议中的数字类型
+    pub fn code(&self) -> u32 {
+        match self {
+            MessageContent::Text { .. } => 1,
+            MessageContent::Emoji { .. } => 2,
+            MessageContent::Sticker { .. } => 3,
+            MessageContent::Image { .. } => 4,
+            MessageContent::Voice { .. } => 5,
+            MessageContent::Location { .. } => 6,
+            MessageContent::Encrypted { .. } => 7,
+            MessageContent::Legacy { code, .. } => *code,
+        }
+    }
+
+    /// 引用的附件ID和要求的 MIME 大类
+    pub fn attachments(&self) -> Vec<(&str, &'static str)> {
+        match self {
+            MessageContent::Image {
+                file_id,
+                thumbnail_id,
+                ..
+            } => std::iter::once(file_id)
+                .chain(thumbnail_id)
+                .map(|id| (id.as_str(), "image/"))
+                .collect(),
+            MessageContent::Voice { file_id, .. } => vec![(file_id, "audio/")],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self, MessageContent::Encrypted { .. })
+    }
+
+    /// 从旧版的数字类型和文本转换
+    pub fn from_legacy(code: u32, text: String) -> Self {
+        match code {
+            1 => MessageContent::Text { text },
+            2 => MessageContent::Emoji { code: text },
+            code => MessageContent::Legacy { code, text },
+        }
+    }
+
+    /// 旧版客户端能展示的文本
+    pub fn legacy_text(&self) -> String {
+        match self {
+            MessageContent::Text { text } | MessageContent::Legacy { text, .. } => text.clone(),
+            MessageContent::Emoji { code } => code.clone(),
+            MessageContent::Sticker { .. } => "[表情包]".to_string(),
+            MessageContent::Image { .. } => "[图片]".to_string(),
+            MessageContent::Voice { .. } => "[语音]".to_string(),
+            MessageContent::Encrypted { .. } => "[加密消息]".to_string(),
+            MessageContent::Location { name, .. } => match name {
+                Some(name) => format!("[位置] {}", name),
+                None => "[位置]".to_string(),
+            },
+        }
+    }
+
+    /// 入库时保存的文本：文本类消息保存原文，其他类型保存 JSON
+    pub fn stored_text(&self) -> String {
+        match self {
+            MessageContent::Text { .. }
+            | MessageContent::Emoji { .. }
+            | MessageContent::Legacy { .. } => self.legacy_text(),
+            _ => serde_json::to_string(self).unwrap_or_default(),
+        }
+    }
+
+    /// 按类型校验内容，加密消息只校验编码和大小
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            MessageContent::Encrypted { payload } => {
+                // base64 编码后约为原始大小的 4/3
+                if payload.is_empty() || payload.len() > MAX_ENCRYPTED_SIZE / 3 * 4 + 4 {
+                    return Err("加密消息无效".to_string());
+                }
+                if STANDARD.decode(payload).is_err() {
+                    return Err("加密消息不是有效的 base64".to_string());
+                }
+            }
+            // 旧版客户端的其他类型原样转发，图片等以 base64 放在 text 中，只限制字节数
+            MessageContent::Legacy { text, .. } => {
+                if text.len() > MAX_LEGACY_SIZE {
+                    return Err(format!("消息内容不能超过 {} KB", MAX_LEGACY_SIZE / 1024));
+                }
+            }
+            MessageContent::Text { text } => {
+                if text.trim().is_empty() {
+                    return Err("消息内容不能为空".to_string());
+                }
+                if text.chars().count() > MAX_TEXT_LENGTH {
+                    return Err(format!("消息内容不能超过 {} 个字符", MAX_TEXT_LENGTH));
+                }
+            }
+            MessageContent::Emoji { code } => {
+                if code.is_empty() || code.chars().count() > 32 {
+                    return Err("表情编码无效".to_string());
+                }
+            }
+            MessageContent::Sticker {
+                pack_id,
+                sticker_id,
+            } => {
+                if !is_valid_ref(pack_id) || !is_valid_ref(sticker_id) {
+                    return Err("表情包ID无效".to_string());
+                }
+            }
+            MessageContent::Image {
+                file_id,
+                thumbnail_id,
+                width,
+                height,
+            } => {
+                if !is_valid_ref(file_id)
+                    || thumbnail_id.as_deref().is_some_and(|id| !is_valid_ref(id))
+                {
+                    return Err("图片附件ID无效".to_string());
+                }
+                let valid_side = |side: &Option<u32>| {
+                    side.is_none_or(|side| (1..=MAX_IMAGE_SIDE).contains(&side))
+                };
+                if !valid_side(width) || !valid_side(height) {
+                    return Err("图片尺寸无效".to_string());
+                }
+            }
+            MessageContent::Voice { file_id, duration } => {
+                if !is_valid_ref(file_id) {
+                    return Err("语音附件ID无效".to_string());
+                }
+                if !(1..=MAX_VOICE_SECONDS).contains(duration) {
+                    return Err(format!("语音时长必须在1到{}秒之间", MAX_VOICE_SECONDS));
+                }
+            }
+            MessageContent::Location {
+                latitude,
+                longitude,
+                name,
+            } => {
+                if !(-90.0..=90.0).contains(latitude) || !(-180.0..=180.0).contains(longitude) {
+                    return Err("经纬度无效".to_string());
+                }
+                if name.as_ref().is_some_and(|name| name.chars().count() > 100) {
+                    return Err("位置名称不能超过 100 个字符".to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 附件、表情包等引用ID：非空且长度有限
+fn is_valid_ref(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 1024
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::types::MessageStruct;
+
+    fn parse(json: &str) -> Result<MessageStruct, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn legacy_codes_map_to_content() {
+        assert!(matches!(
+            MessageContent::from_legacy(1, "你好".to_string()),
+            MessageContent::Text { .. }
+        ));
+        assert!(matches!(
+            MessageContent::from_legacy(2, "smile".to_string()),
+            MessageContent::Emoji { .. }
+        ));
+        let legacy = MessageContent::from_legacy(9, "data".to_string());
+        assert_eq!(legacy.code(), 9);
+        assert_eq!(legacy.legacy_text(), "data");
+        assert_eq!(legacy.stored_text(), "data");
+    }
+
+    #[test]
+    fn legacy_frame_is_parsed() {
+        let message = parse(r#"{"burnAfterRead": true, "type": 1, "text": "你好"}"#).unwrap();
+        assert!(message.burn_after_read);
+        assert!(matches!(message.content, MessageContent::Text { ref text } if text == "你好"));
+
+        assert!(parse(r#"{"burnAfterRead": false, "type": 1}"#).is_err());
+        assert!(parse(r#"{"burnAfterRead": false, "type": 1, "text": "  "}"#).is_err());
+    }
+
+    #[test]
+    fn legacy_non_text_has_no_character_limit() {
+        // 旧版客户端把 base64 图片放在 text 中
+        let image = STANDARD.encode(vec![0u8; MAX_TEXT_LENGTH * 4]);
+        let json = format!(
+            r#"{{"burnAfterRead": false, "type": 4, "text": "{}"}}"#,
+            image
+        );
+        let message = parse(&json).unwrap();
+        assert_eq!(message.content.code(), 4);
+        assert_eq!(message.content.legacy_text(), image);
+    }
+
+    #[test]
+    fn legacy_non_text_is_capped_by_size() {
+        let text = "a".repeat(MAX_LEGACY_SIZE);
+        let json = format!(
+            r#"{{"burnAfterRead": false, "type": 4, "text": "{}"}}"#,
+            text
+        );
+        assert!(parse(&json).is_ok());
+
+        let text = "a".repeat(MAX_LEGACY_SIZE + 1);
+        let json = format!(
+            r#"{{"burnAfterRead": false, "type": 4, "text": "{}"}}"#,
+            text
+        );
+        assert!(parse(&json).is_err());
+    }
+
+    #[test]
+    fn legacy_text_keeps_length_limit() {
+        let text = "字".repeat(MAX_TEXT_LENGTH + 1);
+        let json = format!(
+            r#"{{"burnAfterRead": false, "type": 1, "text": "{}"}}"#,
+            text
+        );
+        assert!(parse(&json).is_err());
+    }
+
+    #[test]
+    fn content_takes_precedence_over_legacy_fields() {
+        let message = parse(
+            r#"{"burnAfterRead": false, "content": {"kind": "emoji", "code": "smile"}, "type": 1, "text": "x"}"#,
+        )
+        .unwrap();
+        assert!(matches!(message.content, MessageContent::Emoji { .. }));
+    }
+
+    #[test]
+    fn legacy_kind_cannot_be_sent_as_content() {
+        assert!(
+            parse(
+                r#"{"burnAfterRead": false, "content": {"kind": "legacy", "code": 9, "text": "x"}}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn outgoing_message_carries_legacy_fields() {
+        let message = MessageStruct {
+            burn_after_read: false,
+            content: MessageContent::Image {
+                file_id: "f".to_string(),
+                thumbnail_id: None,
+                width: None,
+                height: None,
+            },
+        };
+        let value = serde_json::to_value(&message).unwrap();
+        assert_eq!(value["type"], 4);
+        assert_eq!(value["text"], "[图片]");
+        assert_eq!(value["content"]["kind"], "image");
+    }
+}
@@ -1,46 +1,88 @@
-use std::{
-    sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
-};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::websocket::types::{ClientMessage, ConnectionManager, ServerMessage};
+use crate::websocket::WsState;
+use crate::websocket::call::{Call, MAX_CANDIDATE_LENGTH, MAX_SDP_LENGTH};
+use crate::websocket::codec::WireFormat;
+use crate::websocket::content::{MAX_PUBLIC_KEY_LENGTH, MAX_TEXT_LENGTH};
+use crate::websocket::protocol;
+use crate::websocket::rate_limit::{MessageRateLimiter, RateLimitKind, RateLimitVerdict};
+use crate::websocket::room::{MAX_ROOM_NAME_LENGTH, MAX_ROOM_PASSWORD_LENGTH, RoomDeparture};
+use crate::websocket::session::{ChatSession, SentMessage};
+use crate::websocket::types::{ClientMessage, ServerMessage};
 use axum::{
     extract::{
         Query, State, WebSocketUpgrade,
-        ws::{Message, Utf8Bytes, WebSocket},
+        ws::{Message, WebSocket},
     },
-    response::IntoResponse,
+    http::StatusCode,
+    response::{IntoResponse, Response},
 };
 use common::request::websocket::WsRequestParams;
+use common::utils::response::ApiResponse;
+use database::repository::chat_message_repository::NewChatMessage;
 use futures_util::{SinkExt, StreamExt};
+use kernel::auth::revocation;
+use kernel::auth::token::{Claims, TokenError, TokenKind, TokenService};
+use kernel::config::server_config;
+use middleware_fn::auth::check_ban;
 use tokio::sync::mpsc;
 
 /// WebSocket 升级处理
 pub async fn websocket_handler(
     Query(args): Query<WsRequestParams>,
     ws: WebSocketUpgrade,
-    State(state): State<Arc<ConnectionManager>>,
-) -> impl IntoResponse {
-    tracing::info!("新的 WebSocket 连接请求, userKey = {:?}", args);
-    ws.on_upgrade(|socket| handle_websocket_connection(socket, state, args))
+    State(state): State<WsState>,
+) -> Response {
+    // 升级前校验令牌，过期、被篡改或已吊销的令牌直接拒绝
+    let verified =
+        match TokenService::global().verify(&args.token, &[TokenKind::Access, TokenKind::Guest]) {
+            Ok(claims) => revocation::check(&claims).await.map(|()| claims),
+            Err(e) => Err(e),
+        };
+    let claims = match verified {
+        Ok(claims) => claims,
+        Err(e) => {
+            tracing::info!("拒绝 WebSocket 连接请求: {}", e);
+            let status = match e {
+                TokenError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::UNAUTHORIZED,
+            };
+            return (status, ApiResponse::<()>::error(e.code(), &e.to_string())).into_response();
+        }
+    };
+    if let Err(rejection) = check_ban(&claims.sub, claims.ip.as_deref()).await {
+        tracing::info!("拒绝 WebSocket 连接请求: {}", rejection.message);
+        let status = StatusCode::from_u16(rejection.code as u16).unwrap_or(StatusCode::FORBIDDEN);
+        return (status, rejection).into_response();
+    }
+    tracing::info!("新的 WebSocket 连接请求, 用户 = {}", claims.sub);
+
+    // 协商消息编码：JSON 或 MessagePack
+    let (ws, format) = WireFormat::negotiate(ws);
+    ws.on_upgrade(move |socket| handle_websocket_connection(socket, state, claims, format))
 }
 
 /// 处理 WebSocket 连接
 async fn handle_websocket_connection(
     socket: WebSocket,
-    state: Arc<ConnectionManager>,
-    args: WsRequestParams,
+    state: WsState,
+    claims: Claims,
+    format: WireFormat,
 ) {
-    // 使用浏览器发送过来的userKey作为客户端ID
-    let client_id = args.key;
+    // 使用令牌中的用户ID作为客户端ID
+    let client_id = claims.sub.clone();
 
-    tracing::info!("处理客户端 {} 的 WebSocket 连接", client_id);
+    tracing::info!("处理客户端 {} 的 WebSocket 连接（{:?}）", client_id, format);
 
     // 创建与客户端的通信通道
     let (to_client_tx, mut to_client_rx) = mpsc::unbounded_channel();
 
     // 注册连接
-    if !state.register(client_id.clone(), to_client_tx).await {
+    if !state
+        .connections
+        .register(client_id.clone(), to_client_tx, &claims)
+        .await
+    {
         tracing::error!("客户端 {} 注册失败", client_id);
         return;
     }
@@ -54,10 +96,12 @@ async fn handle_websocket_connection(
         let client_id = client_id.clone();
 
         async move {
-            if let Err(e) = handle_send_task(sender, &mut to_client_rx, &state, &client_id).await {
+            if let Err(e) =
+                handle_send_task(sender, &mut to_client_rx, &state, &client_id, format).await
+            {
                 tracing::error!("客户端 {} 发送任务错误: {}", client_id, e);
             }
-            state.unregister(&client_id).await;
+            state.connections.unregister(&client_id).await;
         }
     });
 
@@ -67,7 +111,7 @@ async fn handle_websocket_connection(
         let client_id = client_id.clone();
 
         async move {
-            if let Err(e) = handle_receive_task(receiver, &state, &client_id).await {
+            if let Err(e) = handle_receive_task(receiver, &state, &client_id, format).await {
                 tracing::error!("客户端 {} 接收任务错误: {}", client_id, e);
             }
         }
@@ -80,55 +124,95 @@ async fn handle_websocket_connection(
     }
 
     // 从匹配队列中移除用户
-    state.remove_from_waiting_queue(&client_id).await;
+    state
+        .connections
+        .remove_from_waiting_queue(&client_id)
+        .await;
+
+    // 结束参与的所有会话及其中的通话，清理会话附件
+    for session in state.sessions.end_all(&client_id).await {
+        clean_up_session(&state, &session).await;
+    }
+
+    // 离开所有房间并通知其他成员
+    for departure in state.rooms.leave_all(&client_id).await {
+        notify_room_departure(&state, &client_id, &departure).await;
+    }
 
     // 确保连接被清理
-    state.unregister(&client_id).await;
+    state.connections.unregister(&client_id).await;
 }
 
 /// 处理发送任务：从通道接收消息并发送给客户端
 async fn handle_send_task(
     mut sender: futures_util::stream::SplitSink<WebSocket, Message>,
     to_client_rx: &mut mpsc::UnboundedReceiver<String>,
-    state: &ConnectionManager,
+    state: &WsState,
     client_id: &str,
+    format: WireFormat,
 ) -> Result<(), String> {
     // 发送连接成功消息
     let connected_msg = serde_json::to_string(&ServerMessage::Connected {
         client_id: client_id.to_string(),
-        online_count: state.online_count().await,
+        online_count: state.connections.online_count().await,
     })
     .map_err(|e| format!("序列化失败: {}", e))?;
 
     sender
-        .send(Message::Text(Utf8Bytes::from(connected_msg)))
+        .send(format.encode(connected_msg)?)
         .await
         .map_err(|e| format!("发送连接消息失败: {}", e))?;
 
+    // 推送最近的大厅广播，让新用户看到之前的聊天
+    let page_size = server_config().ws_broadcast_history_page;
+    if page_size > 0 {
+        let (messages, has_more) = state.history.page(None, page_size).await;
+        if !messages.is_empty() {
+            let history_msg =
+                serde_json::to_string(&ServerMessage::BroadcastHistory { messages, has_more })
+                    .map_err(|e| format!("序列化失败: {}", e))?;
+
+            sender
+                .send(format.encode(history_msg)?)
+                .await
+                .map_err(|e| format!("发送广播历史失败: {}", e))?;
+        }
+    }
+
     // 循环处理来自通道的消息
     while let Some(message) = to_client_rx.recv().await {
         sender
-            .send(Message::Text(Utf8Bytes::from(message)))
+            .send(format.encode(message)?)
             .await
             .map_err(|e| format!("发送消息失败: {}", e))?;
     }
 
+    // 通道关闭（连接被注销或被服务端断开），通知客户端关闭连接
+    let _ = sender.send(Message::Close(None)).await;
+
     Ok(())
 }
 
 /// 处理接收任务：从客户端接收消息
 async fn handle_receive_task(
     mut receiver: futures_util::stream::SplitStream<WebSocket>,
-    state: &ConnectionManager,
+    state: &WsState,
     client_id: &str,
+    format: WireFormat,
 ) -> Result<(), String> {
+    // 每个连接独立的消息限流器
+    let mut rate_limiter = MessageRateLimiter::from_config();
+
     while let Some(result) = receiver.next().await {
         match result {
             Ok(msg) => {
-                if let Err(e) = handle_client_message(msg, state, client_id).await {
-                    if !e.contains("连接关闭") {
-                        tracing::warn!("处理消息失败: {}", e);
+                if let Err(e) =
+                    handle_client_message(msg, state, client_id, format, &mut rate_limiter).await
+                {
+                    if e.contains("连接关闭") {
+                        break;
                     }
+                    tracing::warn!("处理消息失败: {}", e);
                 }
             }
             Err(e) => {
@@ -143,64 +227,150 @@ async fn handle_receive_task(
 /// 处理客户端消息
 async fn handle_client_message(
     msg: Message,
-    state: &ConnectionManager,
+    state: &WsState,
     client_id: &str,
+    format: WireFormat,
+    rate_limiter: &mut MessageRateLimiter,
 ) -> Result<(), String> {
-    match msg {
+    // 解析客户端消息：文本帧为 JSON，二进制帧按协商的编码解析
+    let parsed = match msg {
         Message::Text(text) => {
             tracing::debug!("收到客户端 {} 的消息: {}", client_id, text);
-
-            // 解析客户端消息
-            match serde_json::from_str::<ClientMessage>(&text) {
-                Ok(client_msg) => handle_parsed_message(client_msg, state, client_id).await,
-                Err(e) => Err(format!("消息格式错误: {}", e)),
-            }
+            serde_json::from_str::<ClientMessage>(&text).map_err(|e| format!("消息格式错误: {}", e))
+        }
+        Message::Binary(data) => {
+            let parsed = format.decode_binary(&data);
+            tracing::debug!("收到客户端 {} 的消息: {:?}", client_id, parsed);
+            parsed
         }
         Message::Close(_) => {
             tracing::info!("客户端 {} 请求关闭连接", client_id);
-            Err("连接关闭".to_string())
+            return Err("连接关闭".to_string());
         }
         Message::Ping(_data) => {
             tracing::debug!("收到客户端 {} 的 Ping", client_id);
             // 可以在这里发送 Pong 响应，但通常 axum 会自动处理
-            Ok(())
+            return Ok(());
         }
         Message::Pong(_) => {
             tracing::debug!("收到客户端 {} 的 Pong", client_id);
-            Ok(())
+            return Ok(());
+        }
+    };
+
+    // 按消息类别限流，解析失败的帧计入 Other 类别，避免客户端不受限制地发送畸形帧
+    let kind = parsed
+        .as_ref()
+        .map_or(RateLimitKind::Other, RateLimitKind::from);
+    match rate_limiter.check(kind) {
+        RateLimitVerdict::Allowed => handle_parsed_message(parsed?, state, client_id).await,
+        RateLimitVerdict::Limited => {
+            tracing::debug!("客户端 {} 消息发送过于频繁", client_id);
+            send_error(state, client_id, "消息发送过于频繁，请稍后再试").await
+        }
+        RateLimitVerdict::Disconnect => {
+            tracing::warn!("客户端 {} 持续超出消息频率限制，断开连接", client_id);
+            state
+                .connections
+                .disconnect(client_id, "消息发送过于频繁，连接已断开")
+                .await;
+            Err("连接关闭".to_string())
         }
-        Message::Binary(_) => Err("不支持二进制消息".to_string()),
     }
 }
 
 /// 处理解析后的消息
 async fn handle_parsed_message(
     msg: ClientMessage,
-    state: &ConnectionManager,
+    state: &WsState,
     client_id: &str,
 ) -> Result<(), String> {
     match msg {
+        ClientMessage::Hello {
+            protocol_version,
+            capabilities,
+        } => {
+            tracing::info!(
+                "客户端 {} 协议版本 {}，支持的功能: {:?}",
+                client_id,
+                protocol_version,
+                capabilities
+            );
+            protocol::handshake(
+                &state.connections,
+                client_id,
+                protocol_version,
+                protocol::capabilities(state),
+            )
+            .await
+        }
+
         ClientMessage::Meet {
             user_key,
             age_index,
             sex_index,
             location,
+            public_key,
         } => {
+            // 公钥由客户端自行编码，服务端只限制长度
+            if public_key
+                .as_ref()
+                .is_some_and(|key| key.is_empty() || key.len() > MAX_PUBLIC_KEY_LENGTH)
+            {
+                return send_error(state, client_id, "公钥无效").await;
+            }
+
             // 将用户添加到匹配队列
             state
+                .connections
                 .add_to_waiting_queue(
                     client_id.to_string(),
                     user_key.clone(),
                     age_index,
                     sex_index,
                     location,
+                    public_key,
                 )
-                .await;
+                .await?;
 
             // 尝试匹配用户
-            if let Some((user1, user2)) = state.match_users().await {
-                // 匹配成功，通知双方用户
-                if let Err(e) = state.notify_match_result(&user1, &user2).await {
+            if let Some((user1, user2)) = state.connections.match_users().await {
+                // 匹配成功，开始新会话并通知双方用户，双方都提供公钥时启用端到端加密
+                let e2e = user1.public_key.is_some() && user2.public_key.is_some();
+                let previous = state
+                    .sessions
+                    .between(&user1.client_id, &user2.client_id)
+                    .await;
+                let session = match state
+                    .sessions
+                    .start(&user1.client_id, &user2.client_id, e2e)
+                    .await
+                {
+                    Ok(session) => session,
+                    Err(e) => {
+                        // 双方都已移出匹配队列，通知双方重新匹配
+                        tracing::error!("开始会话失败: {}", e);
+                        for user in [&user1, &user2] {
+                            if let Err(e) =
+                                send_error(state, &user.client_id, "匹配失败，请重新匹配").await
+                            {
+                                tracing::warn!("通知用户 {} 匹配失败时出错: {}", user.client_id, e);
+                            }
+                        }
+                        return Ok(());
+                    }
+                };
+                // 双方再次匹配时旧会话被新会话替换
+                if let Some(previous) = previous
+                    && previous.id != session.id
+                {
+                    clean_up_session(state, &previous).await;
+                }
+                if let Err(e) = state
+                    .connections
+                    .notify_match_result(&user1, &user2, &session.id)
+                    .await
+                {
                     tracing::error!("发送匹配结果失败: {}", e);
                     return Err("发送匹配结果失败".to_string());
                 }
@@ -212,7 +382,7 @@ async fn handle_parsed_message(
                 })
                 .map_err(|e| format!("序列化失败: {}", e))?;
 
-                state.send_to(&client_id, private_msg).await
+                state.connections.send_to(client_id, private_msg).await
             }
         }
         // 离开某个1对1聊天
@@ -222,17 +392,22 @@ async fn handle_parsed_message(
                 return Err("不能自己离开聊天".to_string());
             }
 
+            // 结束双方的会话及其中的通话，清理会话附件
+            if let Some(session) = state.sessions.end(client_id, &to).await {
+                clean_up_session(state, &session).await;
+            }
+
             // 发送离开消息
             let depart_msg = serde_json::to_string(&ServerMessage::Depart {
                 from: client_id.to_string(),
             })
             .map_err(|e| format!("序列化失败: {}", e))?;
 
-            state.send_to(&to, depart_msg).await
+            state.connections.send_to(&to, depart_msg).await
         }
         ClientMessage::Private { to, message } => {
             // 检查目标用户是否存在
-            if !state.is_online(&to).await {
+            if !state.connections.is_online(&to).await {
                 return Err(format!("用户 {} 不在线", to));
             }
 
@@ -246,57 +421,657 @@ async fn handle_parsed_message(
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs();
+            let id = uuid::Uuid::new_v4().to_string();
+            // 只能在匹配建立的会话中私聊，会话结束后需要重新匹配
+            let Some(session) = state.sessions.between(client_id, &to).await else {
+                return Err("请先匹配后再私聊".to_string());
+            };
+
+            // 引用的附件必须属于当前会话，且类型与消息类型一致
+            for (file_id, mime_prefix) in message.content.attachments() {
+                let claims = state.attachments.signer.verify(file_id)?;
+                if claims.session_id != session.id {
+                    return Err("附件不属于当前会话".to_string());
+                }
+                if !claims.mime.starts_with(mime_prefix) {
+                    return Err("附件类型与消息类型不符".to_string());
+                }
+            }
+
+            // 阅后即焚的消息不入库，端到端加密会话只保存密文
+            let persist =
+                !message.burn_after_read && (!session.e2e || message.content.is_encrypted());
+            let record = match (&state.chat_writer, persist) {
+                (Some(_), true) => Some(NewChatMessage {
+                    message_id: id.clone(),
+                    session_id: session.id.clone(),
+                    sender: client_id.to_string(),
+                    receiver: to.clone(),
+                    message_type: message.content.code() as i32,
+                    text: message.content.stored_text(),
+                    burn_after_read: false,
+                    timestamp: timestamp as i64,
+                }),
+                _ => None,
+            };
 
             let private_msg = serde_json::to_string(&ServerMessage::Private {
+                id: id.clone(),
+                session_id: session.id.clone(),
                 from: client_id.to_string(),
                 message,
                 timestamp,
             })
             .map_err(|e| format!("序列化失败: {}", e))?;
 
-            state.send_to(&to, private_msg).await
+            state.connections.send_to(&to, private_msg).await?;
+
+            if let (Some(writer), Some(record)) = (&state.chat_writer, record) {
+                writer.write(record);
+            }
+
+            // 记录消息以便在撤回时间内撤回
+            let recall_window = server_config().ws_recall_window;
+            if recall_window > 0 {
+                let sent = SentMessage {
+                    id: id.clone(),
+                    session_id: session.id.clone(),
+                    sender: client_id.to_string(),
+                    receiver: to.clone(),
+                    timestamp,
+                };
+                state.sessions.record_message(sent, recall_window).await;
+            }
+
+            // 回执消息ID和会话ID给发送者
+            let sent_msg = serde_json::to_string(&ServerMessage::PrivateSent {
+                id,
+                session_id: session.id,
+                to,
+                timestamp,
+            })
+            .map_err(|e| format!("序列化失败: {}", e))?;
+
+            state.connections.send_to(client_id, sent_msg).await
+        }
+
+        ClientMessage::Recall { id } => {
+            // 撤回失败需要告知客户端
+            if let Err(e) = recall_message(state, client_id, &id).await {
+                send_error(state, client_id, &e).await?;
+            }
+            Ok(())
+        }
+
+        ClientMessage::CallOffer { .. }
+        | ClientMessage::CallAnswer { .. }
+        | ClientMessage::IceCandidate { .. }
+        | ClientMessage::CallHangup { .. } => {
+            // 通话信令失败需要告知客户端
+            if let Err(e) = handle_call_message(msg, state, client_id).await {
+                send_error(state, client_id, &e).await?;
+            }
+            Ok(())
         }
 
         ClientMessage::List => {
             // 获取在线用户列表
-            let clients = state.list_clients().await;
+            let clients = state.connections.list_clients().await;
             let list_msg = serde_json::to_string(&ServerMessage::List { clients })
                 .map_err(|e| format!("序列化失败: {}", e))?;
 
             // 发送给请求者
-            state.send_to(client_id, list_msg).await
+            state.connections.send_to(client_id, list_msg).await
         }
 
         ClientMessage::Ping => {
             // 发送 Pong 响应
-            let online_count = state.online_count().await;
+            let online_count = state.connections.online_count().await;
             let pong_msg = serde_json::to_string(&ServerMessage::Pong { online_count })
                 .map_err(|e| format!("序列化失败: {}", e))?;
-            println!("pong {}", pong_msg);
-            state.send_to(client_id, pong_msg).await
+            state.connections.send_to(client_id, pong_msg).await
         }
 
         ClientMessage::Broadcast { message } => {
+            let config = server_config();
+            if message.trim().is_empty() {
+                return send_error(state, client_id, "广播内容不能为空").await;
+            }
+            if message.chars().count() > config.ws_broadcast_max_length {
+                let error = format!("广播内容不能超过 {} 个字符", config.ws_broadcast_max_length);
+                return send_error(state, client_id, &error).await;
+            }
+
+            // 大厅慢速模式
+            if let Err(remaining) = state
+                .connections
+                .check_broadcast_cooldown(client_id, config.ws_broadcast_cooldown)
+                .await
+            {
+                let error = format!("发言过快，请 {} 秒后再广播", remaining);
+                return send_error(state, client_id, &error).await;
+            }
+
             // 广播消息
             let timestamp = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs();
 
+            // 记录到广播历史
+            let record = state.history.push(client_id, &message, timestamp).await?;
+
             // 先给自己发送广播确认
             let self_msg = serde_json::to_string(&ServerMessage::Broadcast {
+                id: record.id,
                 from: client_id.to_string(),
                 message: message.clone(),
                 timestamp,
             })
             .map_err(|e| format!("序列化失败: {}", e))?;
 
-            let _ = state.send_to(client_id, self_msg).await;
+            let _ = state.connections.send_to(client_id, self_msg).await;
 
             // 广播给其他用户
-            state.broadcast(client_id, &message, timestamp).await;
+            state
+                .connections
+                .broadcast(record.id, client_id, &message, timestamp)
+                .await;
+
+            Ok(())
+        }
+
+        ClientMessage::BroadcastHistory { before, limit } => {
+            // 单页条数不超过配置的页大小
+            let page_size = server_config().ws_broadcast_history_page;
+            let limit = limit.unwrap_or(page_size).min(page_size);
+
+            let (messages, has_more) = state.history.page(before, limit).await;
+            let history_msg =
+                serde_json::to_string(&ServerMessage::BroadcastHistory { messages, has_more })
+                    .map_err(|e| format!("序列化失败: {}", e))?;
+
+            state.connections.send_to(client_id, history_msg).await
+        }
+
+        ClientMessage::CreateRoom { .. }
+        | ClientMessage::JoinRoom { .. }
+        | ClientMessage::LeaveRoom { .. }
+        | ClientMessage::RoomMessage { .. }
+        | ClientMessage::RoomMembers { .. }
+        | ClientMessage::PromoteAdmin { .. }
+        | ClientMessage::DemoteAdmin { .. }
+        | ClientMessage::KickMember { .. }
+        | ClientMessage::MuteMember { .. }
+        | ClientMessage::UnmuteMember { .. }
+        | ClientMessage::SlowMode { .. } => {
+            // 房间操作失败需要告知客户端
+            if let Err(e) = handle_room_message(msg, state, client_id).await {
+                send_error(state, client_id, &e).await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// 撤回私聊消息：删除已保存的记录，并通知双方
+async fn recall_message(state: &WsState, client_id: &str, id: &str) -> Result<(), String> {
+    let recall_window = server_config().ws_recall_window;
+    if recall_window == 0 {
+        return Err("未开启消息撤回".to_string());
+    }
+
+    let message = state.sessions.recall(id, client_id, recall_window).await?;
+    if let Some(writer) = &state.chat_writer {
+        writer.delete(message.id.clone());
+    }
+    tracing::info!("用户 {} 撤回了消息 {}", client_id, message.id);
+
+    let recalled_msg = serde_json::to_string(&ServerMessage::Recalled {
+        id: message.id,
+        session_id: message.session_id,
+        from: message.sender,
+    })
+    .map_err(|e| format!("序列化失败: {}", e))?;
+
+    // 对方可能已经离线，撤回结果仍需告知发送者
+    let _ = state
+        .connections
+        .send_to(&message.receiver, recalled_msg.clone())
+        .await;
+    state.connections.send_to(client_id, recalled_msg).await
+}
+
+/// 处理通话信令：只在进行中会话的双方之间转发
+async fn handle_call_message(
+    msg: ClientMessage,
+    state: &WsState,
+    client_id: &str,
+) -> Result<(), String> {
+    match msg {
+        ClientMessage::CallOffer { to, sdp, video } => {
+            validate_sdp(&sdp)?;
+            if to == client_id {
+                return Err("不能呼叫自己".to_string());
+            }
+            let Some(session) = state.sessions.between(client_id, &to).await else {
+                return Err("只能呼叫会话中的对方".to_string());
+            };
+            if !state.connections.is_online(&to).await {
+                return Err(format!("用户 {} 不在线", to));
+            }
+
+            let call = state.calls.offer(&session, client_id, video).await?;
+            let offer_msg = serde_json::to_string(&ServerMessage::CallOffer {
+                call_id: call.id.clone(),
+                session_id: call.session_id.clone(),
+                from: client_id.to_string(),
+                sdp,
+                video,
+            })
+            .map_err(|e| format!("序列化失败: {}", e))?;
+
+            if let Err(e) = state.connections.send_to(&to, offer_msg).await {
+                state.calls.end(&call.id).await;
+                return Err(e);
+            }
+            start_ring_timer(state, &call).await;
+            notify_call_state(state, &call).await;
+            Ok(())
+        }
+
+        ClientMessage::CallAnswer { call_id, sdp } => {
+            validate_sdp(&sdp)?;
+            active_call(state, &call_id, client_id).await?;
+
+            let call = state.calls.answer(&call_id, client_id).await?;
+            let answer_msg = serde_json::to_string(&ServerMessage::CallAnswer {
+                call_id,
+                from: client_id.to_string(),
+                sdp,
+            })
+            .map_err(|e| format!("序列化失败: {}", e))?;
+
+            state.connections.send_to(&call.caller, answer_msg).await?;
+            notify_call_state(state, &call).await;
+            Ok(())
+        }
+
+        ClientMessage::IceCandidate { call_id, candidate } => {
+            if candidate.candidate.len() > MAX_CANDIDATE_LENGTH
+                || candidate
+                    .sdp_mid
+                    .as_ref()
+                    .is_some_and(|mid| mid.len() > MAX_CANDIDATE_LENGTH)
+                || candidate
+                    .username_fragment
+                    .as_ref()
+                    .is_some_and(|ufrag| ufrag.len() > MAX_CANDIDATE_LENGTH)
+            {
+                return Err("ICE 候选无效".to_string());
+            }
+
+            let call = active_call(state, &call_id, client_id).await?;
+            let peer = call.peer_of(client_id).unwrap_or_default();
+            let candidate_msg = serde_json::to_string(&ServerMessage::IceCandidate {
+                call_id,
+                from: client_id.to_string(),
+                candidate,
+            })
+            .map_err(|e| format!("序列化失败: {}", e))?;
+
+            state.connections.send_to(peer, candidate_msg).await
+        }
+
+        ClientMessage::CallHangup { call_id } => {
+            let call = state.calls.hangup(&call_id, client_id).await?;
+            let hangup_msg = serde_json::to_string(&ServerMessage::CallHangup {
+                call_id,
+                from: client_id.to_string(),
+            })
+            .map_err(|e| format!("序列化失败: {}", e))?;
+
+            // 对方可能已经离线，挂断结果仍需告知双方
+            let _ = state
+                .connections
+                .send_to(call.peer_of(client_id).unwrap_or_default(), hangup_msg)
+                .await;
+            notify_call_state(state, &call).await;
+            Ok(())
+        }
+
+        _ => Ok(()),
+    }
+}
+
+fn validate_sdp(sdp: &str) -> Result<(), String> {
+    if sdp.trim().is_empty() || sdp.len() > MAX_SDP_LENGTH {
+        return Err("SDP 无效".to_string());
+    }
+    Ok(())
+}
+
+/// 获取进行中的通话，所属会话已结束时一并结束通话
+async fn active_call(state: &WsState, call_id: &str, client_id: &str) -> Result<Call, String> {
+    let call = state.calls.get_for(call_id, client_id).await?;
+    if state.sessions.get(&call.session_id).await.is_none() {
+        if let Some(call) = state.calls.end(call_id).await {
+            notify_call_state(state, &call).await;
+        }
+        return Err("会话已结束".to_string());
+    }
+    Ok(call)
+}
+
+/// 振铃超时后结束通话并通知双方
+async fn start_ring_timer(state: &WsState, call: &Call) {
+    let timeout = Duration::from_secs(server_config().ws_call_ring_timeout);
+    let timer = tokio::spawn({
+        let state = state.clone();
+        let call_id = call.id.clone();
+
+        async move {
+            tokio::time::sleep(timeout).await;
+            if let Some(call) = state.calls.expire(&call_id).await {
+                notify_call_state(&state, &call).await;
+            }
+        }
+    });
+    state.calls.set_timer(&call.id, timer).await;
+}
+
+/// 会话结束后结束其中的通话，并在后台删除会话中上传的附件和缩略图
+async fn clean_up_session(state: &WsState, session: &ChatSession) {
+    end_session_call(state, session).await;
+
+    let attachments = state.attachments.clone();
+    let session_id = session.id.clone();
+    tokio::spawn(async move {
+        if let Err(e) = attachments.store.delete_dir(&session_id).await {
+            tracing::error!("删除会话 {} 的附件失败: {}", session_id, e);
+        }
+    });
+}
+
+/// 结束会话中的通话并通知双方
+async fn end_session_call(state: &WsState, session: &ChatSession) {
+    if let Some(call) = state.calls.end_session(session).await {
+        notify_call_state(state, &call).await;
+    }
+}
+
+/// 把通话状态发送给通话双方
+async fn notify_call_state(state: &WsState, call: &Call) {
+    match serde_json::to_string(&ServerMessage::CallState {
+        call_id: call.id.clone(),
+        session_id: call.session_id.clone(),
+        state: call.status,
+    }) {
+        Ok(state_msg) => {
+            for client_id in [&call.caller, &call.callee] {
+                let _ = state
+                    .connections
+                    .send_to(client_id, state_msg.clone())
+                    .await;
+            }
+        }
+        Err(e) => tracing::error!("序列化失败: {}", e),
+    }
+}
+
+/// 房间密码长度校验
+fn check_room_password(password: Option<&str>) -> Result<(), String> {
+    match password {
+        Some(password) if password.chars().count() > MAX_ROOM_PASSWORD_LENGTH => Err(format!(
+            "房间密码不能超过 {} 个字符",
+            MAX_ROOM_PASSWORD_LENGTH
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// 处理房间相关消息
+async fn handle_room_message(
+    msg: ClientMessage,
+    state: &WsState,
+    client_id: &str,
+) -> Result<(), String> {
+    match msg {
+        ClientMessage::CreateRoom {
+            name,
+            capacity,
+            password,
+        } => {
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                return Err("房间名称不能为空".to_string());
+            }
+            if name.chars().count() > MAX_ROOM_NAME_LENGTH {
+                return Err(format!("房间名称不能超过 {} 个字符", MAX_ROOM_NAME_LENGTH));
+            }
+            check_room_password(password.as_deref())?;
+
+            let max_capacity = server_config().ws_room_max_capacity;
+            if !(2..=max_capacity).contains(&capacity) {
+                return Err(format!("房间人数需在 2 到 {} 之间", max_capacity));
+            }
+
+            let room = state
+                .rooms
+                .create_room(client_id, name, capacity, password)
+                .await;
+            let created_msg = serde_json::to_string(&ServerMessage::RoomCreated { room })
+                .map_err(|e| format!("序列化失败: {}", e))?;
+
+            state.connections.send_to(client_id, created_msg).await
+        }
+
+        ClientMessage::JoinRoom { room_id, password } => {
+            check_room_password(password.as_deref())?;
+            let (room, existing) = state
+                .rooms
+                .join_room(&room_id, client_id, password.as_deref())
+                .await?;
+
+            // 通知房间内其他成员
+            let joined_msg = serde_json::to_string(&ServerMessage::RoomMemberJoined {
+                room_id: room_id.clone(),
+                client_id: client_id.to_string(),
+            })
+            .map_err(|e| format!("序列化失败: {}", e))?;
+            state
+                .rooms
+                .send_to_members(&state.connections, &existing, &joined_msg)
+                .await;
+
+            let mut members = existing;
+            members.push(client_id.to_string());
+            let self_msg = serde_json::to_string(&ServerMessage::RoomJoined { room, members })
+                .map_err(|e| format!("序列化失败: {}", e))?;
+
+            state.connections.send_to(client_id, self_msg).await
+        }
+
+        ClientMessage::LeaveRoom { room_id } => {
+            let departure = state.rooms.leave_room(&room_id, client_id).await?;
+            notify_room_departure(state, client_id, &departure).await;
+
+            let left_msg = serde_json::to_string(&ServerMessage::RoomLeft { room_id })
+                .map_err(|e| format!("序列化失败: {}", e))?;
+
+            state.connections.send_to(client_id, left_msg).await
+        }
 
+        ClientMessage::RoomMessage { room_id, message } => {
+            // 与私聊文本使用相同的长度限制
+            if message.trim().is_empty() {
+                return Err("消息内容不能为空".to_string());
+            }
+            if message.chars().count() > MAX_TEXT_LENGTH {
+                return Err(format!("消息内容不能超过 {} 个字符", MAX_TEXT_LENGTH));
+            }
+            // 禁言和慢速模式在服务端校验
+            let members = state.rooms.check_speak(&room_id, client_id).await?;
+
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            // 房间消息同样发回给发送者作为确认
+            let room_msg = serde_json::to_string(&ServerMessage::RoomMessage {
+                room_id,
+                from: client_id.to_string(),
+                message,
+                timestamp,
+            })
+            .map_err(|e| format!("序列化失败: {}", e))?;
+
+            state
+                .rooms
+                .send_to_members(&state.connections, &members, &room_msg)
+                .await;
+
+            Ok(())
+        }
+
+        ClientMessage::RoomMembers { room_id } => {
+            let (room, members) = state.rooms.members(&room_id, client_id).await?;
+            let members_msg = serde_json::to_string(&ServerMessage::RoomMembers { room, members })
+                .map_err(|e| format!("序列化失败: {}", e))?;
+
+            state.connections.send_to(client_id, members_msg).await
+        }
+
+        ClientMessage::PromoteAdmin {
+            room_id,
+            client_id: target,
+        } => {
+            let members = state
+                .rooms
+                .promote_admin(&room_id, client_id, &target)
+                .await?;
+            let notice = format!("{} 被房主设为管理员", target);
+            send_room_system(state, &room_id, &members, notice).await
+        }
+
+        ClientMessage::DemoteAdmin {
+            room_id,
+            client_id: target,
+        } => {
+            let members = state
+                .rooms
+                .demote_admin(&room_id, client_id, &target)
+                .await?;
+            let notice = format!("{} 被房主取消管理员", target);
+            send_room_system(state, &room_id, &members, notice).await
+        }
+
+        ClientMessage::KickMember {
+            room_id,
+            client_id: target,
+        } => {
+            // 踢出前的成员列表包含被踢者，使其也能收到通知
+            let members = state.rooms.kick(&room_id, client_id, &target).await?;
+            let notice = format!("{} 被 {} 踢出房间", target, client_id);
+            send_room_system(state, &room_id, &members, notice).await?;
+
+            let left_msg = serde_json::to_string(&ServerMessage::RoomLeft { room_id })
+                .map_err(|e| format!("序列化失败: {}", e))?;
+            let _ = state.connections.send_to(&target, left_msg).await;
             Ok(())
         }
+
+        ClientMessage::MuteMember {
+            room_id,
+            client_id: target,
+            duration,
+        } => {
+            let members = state
+                .rooms
+                .mute(&room_id, client_id, &target, duration)
+                .await?;
+            let notice = match duration {
+                Some(seconds) => format!("{} 被 {} 禁言 {} 秒", target, client_id, seconds),
+                None => format!("{} 被 {} 禁言", target, client_id),
+            };
+            send_room_system(state, &room_id, &members, notice).await
+        }
+
+        ClientMessage::UnmuteMember {
+            room_id,
+            client_id: target,
+        } => {
+            let members = state.rooms.unmute(&room_id, client_id, &target).await?;
+            let notice = format!("{} 被 {} 解除禁言", target, client_id);
+            send_room_system(state, &room_id, &members, notice).await
+        }
+
+        ClientMessage::SlowMode { room_id, seconds } => {
+            let members = state
+                .rooms
+                .set_slow_mode(&room_id, client_id, seconds)
+                .await?;
+            let notice = if seconds > 0 {
+                format!("{} 开启了慢速模式，每 {} 秒可发言一次", client_id, seconds)
+            } else {
+                format!("{} 关闭了慢速模式", client_id)
+            };
+            send_room_system(state, &room_id, &members, notice).await
+        }
+
+        _ => Ok(()),
+    }
+}
+
+/// 向房间成员发送系统通知
+async fn send_room_system(
+    state: &WsState,
+    room_id: &str,
+    members: &[String],
+    message: String,
+) -> Result<(), String> {
+    let system_msg = serde_json::to_string(&ServerMessage::System {
+        message,
+        room_id: Some(room_id.to_string()),
+    })
+    .map_err(|e| format!("序列化失败: {}", e))?;
+
+    state
+        .rooms
+        .send_to_members(&state.connections, members, &system_msg)
+        .await;
+
+    Ok(())
+}
+
+/// 通知房间剩余成员有人离开
+async fn notify_room_departure(state: &WsState, client_id: &str, departure: &RoomDeparture) {
+    if departure.remaining.is_empty() {
+        return;
+    }
+
+    match serde_json::to_string(&ServerMessage::RoomMemberLeft {
+        room_id: departure.room_id.clone(),
+        client_id: client_id.to_string(),
+        new_owner: departure.new_owner.clone(),
+    }) {
+        Ok(left_msg) => {
+            state
+                .rooms
+                .send_to_members(&state.connections, &departure.remaining, &left_msg)
+                .await
+        }
+        Err(e) => tracing::error!("序列化失败: {}", e),
     }
 }
+
+/// 给客户端发送错误消息
+async fn send_error(state: &WsState, client_id: &str, message: &str) -> Result<(), String> {
+    let error_msg = serde_json::to_string(&ServerMessage::Error {
+        message: message.to_string(),
+    })
+    .map_err(|e| format!("序列化失败: {}", e))?;
+
+    state.connections.send_to(client_id, error_msg).await
+}
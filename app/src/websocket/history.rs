@@ -0,0 +1,325 @@
+use futures_util::future::BoxFuture;
+use redis::{AsyncCommands, Script, aio::MultiplexedConnection};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, sync::Arc};
+use tokio::sync::RwLock;
+
+/// 广播ID计数器
+const SEQ_KEY: &str = "ws:history:seq";
+/// 广播历史列表，最新的在前
+const LIST_KEY: &str = "ws:history";
+
+/// 分配ID并写入历史：KEYS[1] 计数器，KEYS[2] 列表；ARGV[1] 不含ID的记录，ARGV[2] 容量
+const PUSH_SCRIPT: &str = r"
+local id = redis.call('INCR', KEYS[1])
+local record = cjson.decode(ARGV[1])
+record.id = id
+local raw = cjson.encode(record)
+local capacity = tonumber(ARGV[2])
+if capacity > 0 then
+    redis.call('LPUSH', KEYS[2], raw)
+    redis.call('LTRIM', KEYS[2], 0, capacity - 1)
+end
+return raw
+";
+
+/// 一条大厅广播记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BroadcastRecord {
+    /// 递增的消息ID，用于向前翻页
+    pub id: u64,
+    pub from: String,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+/// 广播历史存储
+///
+/// 记录的ID由存储分配，集群中的节点共享同一个递增序列
+pub trait HistoryStore: Send + Sync {
+    /// 分配ID并记录一条广播，超出容量时丢弃最旧的记录；容量为 0 时只分配ID
+    fn push(
+        &self,
+        from: String,
+        message: String,
+        timestamp: u64,
+        capacity: usize,
+    ) -> BoxFuture<'_, Result<BroadcastRecord, String>>;
+
+    /// 所有保存的记录，按时间正序
+    fn records(&self) -> BoxFuture<'_, Result<Vec<BroadcastRecord>, String>>;
+}
+
+struct HistoryBuffer {
+    records: VecDeque<BroadcastRecord>,
+    next_id: u64,
+}
+
+/// 进程内广播历史，固定容量的环形缓冲区
+pub struct MemoryHistoryStore {
+    buffer: RwLock<HistoryBuffer>,
+}
+
+impl MemoryHistoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for MemoryHistoryStore {
+    fn default() -> Self {
+        Self {
+            buffer: RwLock::new(HistoryBuffer {
+                records: VecDeque::new(),
+                next_id: 1,
+            }),
+        }
+    }
+}
+
+impl HistoryStore for MemoryHistoryStore {
+    fn push(
+        &self,
+        from: String,
+        message: String,
+        timestamp: u64,
+        capacity: usize,
+    ) -> BoxFuture<'_, Result<BroadcastRecord, String>> {
+        Box::pin(async move {
+            let mut buffer = self.buffer.write().await;
+            let record = BroadcastRecord {
+                id: buffer.next_id,
+                from,
+                message,
+                timestamp,
+            };
+            buffer.next_id += 1;
+
+            if capacity == 0 {
+                return Ok(record);
+            }
+            while buffer.records.len() >= capacity {
+                buffer.records.pop_front();
+            }
+            buffer.records.push_back(record.clone());
+
+            Ok(record)
+        })
+    }
+
+    fn records(&self) -> BoxFuture<'_, Result<Vec<BroadcastRecord>, String>> {
+        Box::pin(async move { Ok(self.buffer.read().await.records.iter().cloned().collect()) })
+    }
+}
+
+/// 保存在 redis 中的广播历史，集群中的节点共享ID序列和记录
+pub struct RedisHistoryStore {
+    conn: MultiplexedConnection,
+}
+
+impl RedisHistoryStore {
+    pub fn new(conn: MultiplexedConnection) -> Self {
+        Self { conn }
+    }
+}
+
+/// 写入 redis 的记录，ID由脚本分配
+#[derive(Serialize)]
+struct PendingRecord {
+    from: String,
+    message: String,
+    timestamp: u64,
+}
+
+impl HistoryStore for RedisHistoryStore {
+    fn push(
+        &self,
+        from: String,
+        message: String,
+        timestamp: u64,
+        capacity: usize,
+    ) -> BoxFuture<'_, Result<BroadcastRecord, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let pending = PendingRecord {
+                from,
+                message,
+                timestamp,
+            };
+            let raw = serde_json::to_string(&pending).map_err(|e| format!("序列化失败: {}", e))?;
+            let saved: String = Script::new(PUSH_SCRIPT)
+                .key(SEQ_KEY)
+                .key(LIST_KEY)
+                .arg(raw)
+                .arg(capacity)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| format!("记录广播历史失败: {}", e))?;
+
+            serde_json::from_str(&saved).map_err(|e| format!("解析失败: {}", e))
+        })
+    }
+
+    fn records(&self) -> BoxFuture<'_, Result<Vec<BroadcastRecord>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let raw: Vec<String> = conn
+                .lrange(LIST_KEY, 0, -1)
+                .await
+                .map_err(|e| format!("读取广播历史失败: {}", e))?;
+
+            // 列表中最新的在前
+            raw.iter()
+                .rev()
+                .map(|raw| serde_json::from_str(raw).map_err(|e| format!("解析失败: {}", e)))
+                .collect()
+        })
+    }
+}
+
+/// 大厅广播历史
+#[derive(Clone)]
+pub struct BroadcastHistory {
+    store: Arc<dyn HistoryStore>,
+    capacity: usize,
+}
+
+impl BroadcastHistory {
+    /// 历史保存在本进程内
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            store: Arc::new(MemoryHistoryStore::new()),
+            capacity,
+        }
+    }
+
+    /// 历史保存在 redis 中，供集群中的节点共享
+    pub fn with_redis(conn: MultiplexedConnection, capacity: usize) -> Self {
+        Self {
+            store: Arc::new(RedisHistoryStore::new(conn)),
+            capacity,
+        }
+    }
+
+    /// 记录一条广播，超出容量时丢弃最旧的记录
+    pub async fn push(
+        &self,
+        from: &str,
+        message: &str,
+        timestamp: u64,
+    ) -> Result<BroadcastRecord, String> {
+        self.store
+            .push(
+                from.to_string(),
+                message.to_string(),
+                timestamp,
+                self.capacity,
+            )
+            .await
+    }
+
+    /// 获取 ID 小于 before 的最近 limit 条记录（按时间正序），以及是否还有更早的记录
+    ///
+    /// 读取失败时视为没有历史
+    pub async fn page(&self, before: Option<u64>, limit: usize) -> (Vec<BroadcastRecord>, bool) {
+        let records = self.store.records().await.unwrap_or_else(|e| {
+            tracing::error!("获取广播历史失败: {}", e);
+            Vec::new()
+        });
+        let mut older: Vec<BroadcastRecord> = records
+            .into_iter()
+            .filter(|record| before.is_none_or(|before| record.id < before))
+            .collect();
+
+        let start = older.len().saturating_sub(limit);
+        (older.split_off(start), start > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(records: &[BroadcastRecord]) -> Vec<u64> {
+        records.iter().map(|record| record.id).collect()
+    }
+
+    #[tokio::test]
+    async fn push_assigns_increasing_ids_and_drops_oldest() {
+        let history = BroadcastHistory::new(3);
+        for i in 1..=5 {
+            let record = history.push("a", &format!("消息{}", i), i).await.unwrap();
+            assert_eq!(record.id, i);
+        }
+
+        let (records, has_more) = history.page(None, 10).await;
+        assert_eq!(ids(&records), vec![3, 4, 5]);
+        assert_eq!(records[0].message, "消息3");
+        assert!(!has_more);
+    }
+
+    #[tokio::test]
+    async fn page_walks_backwards() {
+        let history = BroadcastHistory::new(10);
+        for i in 1..=5 {
+            history.push("a", "hi", i).await.unwrap();
+        }
+
+        let (records, has_more) = history.page(None, 2).await;
+        assert_eq!(ids(&records), vec![4, 5]);
+        assert!(has_more);
+
+        let (records, has_more) = history.page(Some(4), 2).await;
+        assert_eq!(ids(&records), vec![2, 3]);
+        assert!(has_more);
+
+        let (records, has_more) = history.page(Some(2), 2).await;
+        assert_eq!(ids(&records), vec![1]);
+        assert!(!has_more);
+    }
+
+    #[tokio::test]
+    async fn zero_capacity_keeps_nothing() {
+        let history = BroadcastHistory::new(0);
+        assert_eq!(history.push("a", "hi", 1).await.unwrap().id, 1);
+        assert_eq!(history.push("a", "hi", 2).await.unwrap().id, 2);
+
+        let (records, has_more) = history.page(None, 10).await;
+        assert!(records.is_empty());
+        assert!(!has_more);
+    }
+
+    /// 需要本地 redis-server：cargo test -p app history -- --ignored
+    #[tokio::test]
+    #[ignore = "需要本地 redis-server"]
+    async fn redis_history_is_shared() {
+        let url = std::env::var("TEST_REDIS_URL")
+            .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let client = redis::Client::open(url).unwrap();
+        let node_a = BroadcastHistory::with_redis(
+            client.get_multiplexed_async_connection().await.unwrap(),
+            3,
+        );
+        let node_b = BroadcastHistory::with_redis(
+            client.get_multiplexed_async_connection().await.unwrap(),
+            3,
+        );
+
+        // 两个节点交替广播，ID不重复且递增
+        let first = node_a.push("a", "来自 A", 1).await.unwrap();
+        let second = node_b.push("b", "来自 B", 2).await.unwrap();
+        let third = node_a.push("a", "再来自 A", 3).await.unwrap();
+        assert!(first.id < second.id && second.id < third.id);
+
+        // 后加入的用户在任一节点上都能看到所有节点的广播
+        let (records, _) = node_b.page(None, 3).await;
+        assert_eq!(ids(&records), vec![first.id, second.id, third.id]);
+        assert_eq!(records[1].message, "来自 B");
+        assert_eq!(records[1].from, "b");
+
+        // 超出容量时丢弃最旧的记录
+        let fourth = node_b.push("b", "第四条", 4).await.unwrap();
+        let (records, _) = node_a.page(None, 10).await;
+        assert_eq!(ids(&records), vec![second.id, third.id, fourth.id]);
+    }
+}
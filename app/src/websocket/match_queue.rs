@@ -0,0 +1,328 @@
+use crate::websocket::cluster::NODE_TTL_SECONDS;
+use crate::websocket::types::WaitingUser;
+use futures_util::future::BoxFuture;
+use redis::{Script, aio::MultiplexedConnection};
+use std::{collections::VecDeque, sync::Arc};
+use tokio::sync::RwLock;
+
+/// 匹配等待超时时间（秒）
+pub const MATCH_TIMEOUT_SECONDS: u64 = 10;
+
+/// 匹配队列
+///
+/// 加入、移除和配对都必须是原子操作：超时定时器和配对同时取出同一个用户时，只能有一方成功
+pub trait MatchQueue: Send + Sync {
+    /// 加入队列，用户已在队列中时返回 false
+    fn join(&self, user: WaitingUser) -> BoxFuture<'_, Result<bool, String>>;
+
+    /// 移出队列，返回用户是否仍在队列中
+    fn remove<'a>(&'a self, client_id: &'a str) -> BoxFuture<'a, Result<bool, String>>;
+
+    /// 取出一对用户：优先匹配不同性别，否则匹配最先加入的两个用户
+    fn pop_pair(&self) -> BoxFuture<'_, Result<Option<(WaitingUser, WaitingUser)>, String>>;
+}
+
+/// 进程内匹配队列
+#[derive(Default)]
+pub struct MemoryMatchQueue {
+    queue: Arc<RwLock<VecDeque<WaitingUser>>>,
+}
+
+impl MemoryMatchQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MatchQueue for MemoryMatchQueue {
+    fn join(&self, user: WaitingUser) -> BoxFuture<'_, Result<bool, String>> {
+        Box::pin(async move {
+            let mut queue = self.queue.write().await;
+            if queue
+                .iter()
+                .any(|waiting| waiting.client_id == user.client_id)
+            {
+                return Ok(false);
+            }
+            queue.push_back(user);
+            Ok(true)
+        })
+    }
+
+    fn remove<'a>(&'a self, client_id: &'a str) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move {
+            let mut queue = self.queue.write().await;
+            let len = queue.len();
+            queue.retain(|user| user.client_id != client_id);
+            Ok(queue.len() != len)
+        })
+    }
+
+    fn pop_pair(&self) -> BoxFuture<'_, Result<Option<(WaitingUser, WaitingUser)>, String>> {
+        Box::pin(async move {
+            let mut queue = self.queue.write().await;
+            if queue.len() < 2 {
+                return Ok(None);
+            }
+
+            // 遍历队列，寻找不同性别的匹配，找不到时匹配最先加入队列的两个用户
+            let (first_idx, second_idx) = queue
+                .iter()
+                .enumerate()
+                .find_map(|(i, user1)| {
+                    queue
+                        .iter()
+                        .enumerate()
+                        .skip(i + 1)
+                        .find(|(_, user2)| user1.sex_index != user2.sex_index)
+                        .map(|(j, _)| (i, j))
+                })
+                .unwrap_or((0, 1));
+
+            let user2 = queue.remove(second_idx).unwrap();
+            let user1 = queue.remove(first_idx).unwrap();
+            Ok(Some((user1, user2)))
+        })
+    }
+}
+
+/// 排队顺序：client_id -> 序号
+const QUEUE_KEY: &str = "ws:match:queue";
+/// 排队用户信息：client_id -> WaitingUser JSON
+const USERS_KEY: &str = "ws:match:users";
+/// 排队序号
+const SEQ_KEY: &str = "ws:match:seq";
+
+/// 加入队列：已在队列中时返回 0
+const JOIN_SCRIPT: &str = r#"
+if redis.call('HEXISTS', KEYS[2], ARGV[1]) == 1 then
+    return 0
+end
+local seq = redis.call('INCR', KEYS[3])
+redis.call('ZADD', KEYS[1], seq, ARGV[1])
+redis.call('HSET', KEYS[2], ARGV[1], ARGV[2])
+return 1
+"#;
+
+/// 移出队列：返回移除的数量
+const REMOVE_SCRIPT: &str = r#"
+redis.call('ZREM', KEYS[1], ARGV[1])
+return redis.call('HDEL', KEYS[2], ARGV[1])
+"#;
+
+/// 取出一对用户，同时清理早已超时却仍未被移除的记录（所在节点已失效，定时器不会再触发）
+const POP_PAIR_SCRIPT: &str = r#"
+local ids = redis.call('ZRANGE', KEYS[1], 0, -1)
+local users = {}
+for _, id in ipairs(ids) do
+    local raw = redis.call('HGET', KEYS[2], id)
+    local user = raw and cjson.decode(raw)
+    if user and user.join_time + tonumber(ARGV[2]) >= tonumber(ARGV[1]) then
+        table.insert(users, { id = id, raw = raw, sex = user.sex_index })
+    else
+        redis.call('ZREM', KEYS[1], id)
+        redis.call('HDEL', KEYS[2], id)
+    end
+end
+if #users < 2 then
+    return {}
+end
+local first, second = 1, 2
+local found = false
+for i = 1, #users do
+    for j = i + 1, #users do
+        if users[i].sex ~= users[j].sex then
+            first, second = i, j
+            found = true
+            break
+        end
+    end
+    if found then
+        break
+    end
+end
+for _, idx in ipairs({ first, second }) do
+    redis.call('ZREM', KEYS[1], users[idx].id)
+    redis.call('HDEL', KEYS[2], users[idx].id)
+end
+return { users[first].raw, users[second].raw }
+"#;
+
+/// 基于 redis 的匹配队列，多个节点共享，配对通过 Lua 脚本原子完成
+pub struct RedisMatchQueue {
+    conn: MultiplexedConnection,
+}
+
+impl RedisMatchQueue {
+    pub fn new(conn: MultiplexedConnection) -> Self {
+        Self { conn }
+    }
+}
+
+impl MatchQueue for RedisMatchQueue {
+    fn join(&self, user: WaitingUser) -> BoxFuture<'_, Result<bool, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let raw = serde_json::to_string(&user).map_err(|e| format!("序列化失败: {}", e))?;
+            let joined: i32 = Script::new(JOIN_SCRIPT)
+                .key(QUEUE_KEY)
+                .key(USERS_KEY)
+                .key(SEQ_KEY)
+                .arg(&user.client_id)
+                .arg(raw)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| format!("加入匹配队列失败: {}", e))?;
+            Ok(joined == 1)
+        })
+    }
+
+    fn remove<'a>(&'a self, client_id: &'a str) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let removed: i32 = Script::new(REMOVE_SCRIPT)
+                .key(QUEUE_KEY)
+                .key(USERS_KEY)
+                .arg(client_id)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| format!("移出匹配队列失败: {}", e))?;
+            Ok(removed > 0)
+        })
+    }
+
+    fn pop_pair(&self) -> BoxFuture<'_, Result<Option<(WaitingUser, WaitingUser)>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let pair: Vec<String> = Script::new(POP_PAIR_SCRIPT)
+                .key(QUEUE_KEY)
+                .key(USERS_KEY)
+                .arg(now)
+                .arg(MATCH_TIMEOUT_SECONDS + NODE_TTL_SECONDS)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| format!("匹配失败: {}", e))?;
+
+            let [user1, user2] = pair.as_slice() else {
+                return Ok(None);
+            };
+            let user1 = serde_json::from_str(user1).map_err(|e| format!("解析失败: {}", e))?;
+            let user2 = serde_json::from_str(user2).map_err(|e| format!("解析失败: {}", e))?;
+            Ok(Some((user1, user2)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(client_id: &str, sex_index: u32) -> WaitingUser {
+        WaitingUser {
+            client_id: client_id.to_string(),
+            user_key: client_id.to_string(),
+            age_index: 0,
+            sex_index,
+            location: String::new(),
+            join_time: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            public_key: None,
+        }
+    }
+
+    async fn pair_ids(queue: &dyn MatchQueue) -> Option<(String, String)> {
+        queue
+            .pop_pair()
+            .await
+            .unwrap()
+            .map(|(a, b)| (a.client_id, b.client_id))
+    }
+
+    #[tokio::test]
+    async fn join_rejects_duplicates() {
+        let queue = MemoryMatchQueue::new();
+        assert!(queue.join(user("a", 0)).await.unwrap());
+        assert!(!queue.join(user("a", 1)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn remove_reports_membership() {
+        let queue = MemoryMatchQueue::new();
+        queue.join(user("a", 0)).await.unwrap();
+        assert!(queue.remove("a").await.unwrap());
+        assert!(!queue.remove("a").await.unwrap());
+        assert!(queue.join(user("a", 0)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn needs_two_users() {
+        let queue = MemoryMatchQueue::new();
+        assert_eq!(pair_ids(&queue).await, None);
+        queue.join(user("a", 0)).await.unwrap();
+        assert_eq!(pair_ids(&queue).await, None);
+        // 未配对的用户仍在队列中
+        assert!(queue.remove("a").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn prefers_different_sex() {
+        let queue = MemoryMatchQueue::new();
+        for (id, sex) in [("a", 0), ("b", 0), ("c", 1), ("d", 1)] {
+            queue.join(user(id, sex)).await.unwrap();
+        }
+        assert_eq!(
+            pair_ids(&queue).await,
+            Some(("a".to_string(), "c".to_string()))
+        );
+        assert_eq!(
+            pair_ids(&queue).await,
+            Some(("b".to_string(), "d".to_string()))
+        );
+        assert_eq!(pair_ids(&queue).await, None);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_first_two() {
+        let queue = MemoryMatchQueue::new();
+        for id in ["a", "b", "c"] {
+            queue.join(user(id, 0)).await.unwrap();
+        }
+        assert_eq!(
+            pair_ids(&queue).await,
+            Some(("a".to_string(), "b".to_string()))
+        );
+        // 配对后的用户已移出队列
+        assert!(!queue.remove("a").await.unwrap());
+        assert!(queue.remove("c").await.unwrap());
+    }
+
+    /// 需要本地 redis-server，见 cluster 模块的测试说明
+    #[tokio::test]
+    #[ignore = "需要本地 redis-server"]
+    async fn redis_queue_is_shared() {
+        let url = std::env::var("TEST_REDIS_URL")
+            .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let pool = kernel::redis::model::RedisPool::new(&url).unwrap();
+        let node_a = RedisMatchQueue::new(pool.get_connection().await.unwrap());
+        let node_b = RedisMatchQueue::new(pool.get_connection().await.unwrap());
+
+        let (a, b) = (
+            uuid::Uuid::new_v4().to_string(),
+            uuid::Uuid::new_v4().to_string(),
+        );
+        assert!(node_a.join(user(&a, 0)).await.unwrap());
+        assert!(!node_b.join(user(&a, 0)).await.unwrap());
+        assert!(node_b.join(user(&b, 1)).await.unwrap());
+
+        let (first, second) = node_a.pop_pair().await.unwrap().unwrap();
+        assert_eq!((first.client_id, second.client_id), (a.clone(), b.clone()));
+        assert!(!node_b.remove(&a).await.unwrap());
+        assert!(node_b.pop_pair().await.unwrap().is_none());
+    }
+}
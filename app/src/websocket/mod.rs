@@ -1,14 +1,50 @@
+use crate::storage::Attachments;
+use crate::websocket::call::CallManager;
+use crate::websocket::history::BroadcastHistory;
+use crate::websocket::persist::ChatWriter;
+use crate::websocket::room::RoomManager;
+use crate::websocket::session::SessionManager;
 use crate::websocket::types::ConnectionManager;
 use axum::Router;
 use axum::routing::get;
 use std::sync::Arc;
 
-pub mod types;
+pub mod call;
+pub mod cluster;
+pub mod codec;
+pub mod content;
 pub mod handler;
+pub mod history;
+pub mod match_queue;
+pub mod persist;
+pub mod protocol;
+pub mod rate_limit;
+pub mod room;
+pub mod session;
+pub mod types;
+
+/// websocket 共享状态
+#[derive(Clone)]
+pub struct WsState {
+    /// 连接管理器
+    pub connections: Arc<ConnectionManager>,
+    /// 房间管理器
+    pub rooms: Arc<RoomManager>,
+    /// 大厅广播历史
+    pub history: Arc<BroadcastHistory>,
+    /// 一对一会话管理器
+    pub sessions: Arc<SessionManager>,
+    /// 会话中的语音/视频通话
+    pub calls: Arc<CallManager>,
+    /// 私聊消息写入器，未配置数据库时为空
+    pub chat_writer: Option<ChatWriter>,
+    /// 附件服务，用于校验消息中引用的附件
+    pub attachments: Arc<Attachments>,
+}
 
 /// websocket app 路由
-pub fn set_websocket_api(connection_manager: Arc<ConnectionManager>) -> Router {
+pub fn set_websocket_api(state: WsState) -> Router {
     Router::new()
         .route("/", get(handler::websocket_handler))
-        .with_state(connection_manager)
+        .with_state(state)
 }
@@ -0,0 +1,116 @@
+use database::repository::chat_message_repository::{self, NewChatMessage};
+use kernel::config::server_config;
+use std::time::Duration;
+use tokio::{sync::mpsc, time};
+
+/// 写入任务的操作，按提交顺序执行
+enum ChatWrite {
+    Insert(NewChatMessage),
+    /// 删除已撤回的消息
+    Delete(String),
+}
+
+/// 私聊消息写入器：消息先进入通道，由后台任务按条数或时间间隔批量入库，不阻塞 socket 收发
+#[derive(Clone)]
+pub struct ChatWriter {
+    sender: mpsc::UnboundedSender<ChatWrite>,
+}
+
+impl ChatWriter {
+    /// 根据服务器配置启动后台写入任务
+    pub fn spawn() -> Self {
+        let config = server_config();
+        let batch_size = config.ws_chat_persist_batch.max(1);
+        let interval = Duration::from_millis(config.ws_chat_persist_interval.max(1));
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(run(receiver, batch_size, interval));
+
+        Self { sender }
+    }
+
+    /// 提交一条待入库的消息
+    pub fn write(&self, message: NewChatMessage) {
+        if self.sender.send(ChatWrite::Insert(message)).is_err() {
+            tracing::error!("私聊消息写入任务已停止，消息未能入库");
+        }
+    }
+
+    /// 删除一条消息，与之前提交的写入保持顺序
+    pub fn delete(&self, message_id: String) {
+        if self.sender.send(ChatWrite::Delete(message_id)).is_err() {
+            tracing::error!("私聊消息写入任务已停止，消息未能删除");
+        }
+    }
+}
+
+async fn run(
+    mut receiver: mpsc::UnboundedReceiver<ChatWrite>,
+    batch_size: usize,
+    interval: Duration,
+) {
+    let mut buffer = Vec::with_capacity(batch_size);
+    let mut ticker = time::interval(interval);
+
+    loop {
+        tokio::select! {
+            write = receiver.recv() => match write {
+                Some(ChatWrite::Insert(message)) => {
+                    buffer.push(message);
+                    if buffer.len() >= batch_size {
+                        flush(&mut buffer).await;
+                    }
+                }
+                Some(ChatWrite::Delete(message_id)) => delete(&mut buffer, &message_id).await,
+                None => {
+                    flush(&mut buffer).await;
+                    break;
+                }
+            },
+            _ = ticker.tick() => flush(&mut buffer).await,
+        }
+    }
+}
+
+async fn flush(buffer: &mut Vec<NewChatMessage>) {
+    if buffer.is_empty() {
+        return;
+    }
+
+    let messages = std::mem::take(buffer);
+    let count = messages.len();
+    if database::get_db().is_none() {
+        tracing::warn!("数据库未初始化，丢弃 {} 条私聊消息", count);
+        return;
+    }
+
+    let e = match chat_message_repository::insert_many(messages.clone()).await {
+        Ok(()) => {
+            tracing::debug!("{} 条私聊消息已入库", count);
+            return;
+        }
+        Err(e) => e,
+    };
+
+    // 批量写入失败时逐条重试，一条消息无法入库不影响同批的其他消息
+    tracing::warn!("{} 条私聊消息批量入库失败，逐条重试: {}", count, e);
+    for message in messages {
+        let message_id = message.message_id.clone();
+        if let Err(e) = chat_message_repository::insert_many(vec![message]).await {
+            tracing::error!("私聊消息 {} 入库失败: {}", message_id, e);
+        }
+    }
+}
+
+/// 消息还在缓冲区时直接丢弃，否则已经入库（或从未入库），从数据库中删除
+async fn delete(buffer: &mut Vec<NewChatMessage>, message_id: &str) {
+    let len = buffer.len();
+    buffer.retain(|message| message.message_id != message_id);
+    if buffer.len() != len || database::get_db().is_none() {
+        return;
+    }
+
+    if let Err(e) = chat_message_repository::delete_by_message_id(message_id).await {
+        tracing::error!("删除私聊消息 {} 失败: {}", message_id, e);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::websocket::WsState;
+use crate::websocket::types::{ConnectionManager, ServerMessage};
+use kernel::config::server_config;
+
+/// 当前协议版本
+pub const PROTOCOL_VERSION: u32 = 2;
+/// 仍兼容的最低协议版本，未发送 hello 的旧客户端视为版本 1
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// 客户端协议版本是否兼容
+pub fn is_compatible(version: u32) -> bool {
+    (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version)
+}
+
+/// 处理客户端的 hello：回复服务端的协议版本和功能，版本不兼容时回复后断开连接
+///
+/// 未发送 hello 的旧客户端不经过握手，按版本 1 处理
+pub async fn handshake(
+    connections: &ConnectionManager,
+    client_id: &str,
+    protocol_version: u32,
+    capabilities: Vec<String>,
+) -> Result<(), String> {
+    let accepted = is_compatible(protocol_version);
+    let hello_msg = serde_json::to_string(&ServerMessage::Hello {
+        accepted,
+        protocol_version: PROTOCOL_VERSION,
+        min_protocol_version: MIN_PROTOCOL_VERSION,
+        capabilities,
+    })
+    .map_err(|e| format!("序列化失败: {}", e))?;
+    connections.send_to(client_id, hello_msg).await?;
+
+    // 不兼容的客户端需要升级后重新连接
+    if !accepted {
+        let reason = format!(
+            "不支持协议版本 {}，服务端支持 {} 到 {}",
+            protocol_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        );
+        connections.disconnect(client_id, &reason).await;
+        return Err("连接关闭".to_string());
+    }
+    Ok(())
+}
+
+/// 服务端当前支持的功能，部分功能取决于配置
+pub fn capabilities(state: &WsState) -> Vec<String> {
+    let config = server_config();
+    let mut capabilities = vec![
+        "typed_content",
+        "attachments",
+        "image_thumbnails",
+        "calls",
+        "e2e",
+        "msgpack",
+        "rooms",
+    ];
+    if config.ws_recall_window > 0 {
+        capabilities.push("recall");
+    }
+    if config.ws_broadcast_history_page > 0 {
+        capabilities.push("broadcast_history");
+    }
+    if state.chat_writer.is_some() {
+        capabilities.push("transcripts");
+    }
+
+    capabilities.into_iter().map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::auth::token::{Claims, TokenKind};
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+    async fn connect(
+        connections: &ConnectionManager,
+        client_id: &str,
+    ) -> UnboundedReceiver<String> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let claims = Claims {
+            sub: client_id.to_string(),
+            kind: TokenKind::Guest,
+            jti: "jti".to_string(),
+            sid: None,
+            ip: None,
+            iss: "test".to_string(),
+            iat: 0,
+            exp: u64::MAX,
+        };
+        assert!(
+            connections
+                .register(client_id.to_string(), tx, &claims)
+                .await
+        );
+        rx
+    }
+
+    fn next(rx: &mut UnboundedReceiver<String>) -> serde_json::Value {
+        serde_json::from_str(&rx.try_recv().expect("没有收到消息")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn compatible_hello_is_accepted() {
+        let connections = ConnectionManager::new();
+        let mut rx = connect(&connections, "a").await;
+
+        for version in MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION {
+            handshake(&connections, "a", version, vec!["calls".to_string()])
+                .await
+                .unwrap();
+            let hello = next(&mut rx);
+            assert_eq!(hello["type"], "hello");
+            assert_eq!(hello["data"]["accepted"], true);
+            assert_eq!(hello["data"]["protocol_version"], PROTOCOL_VERSION);
+            assert_eq!(hello["data"]["min_protocol_version"], MIN_PROTOCOL_VERSION);
+            assert_eq!(hello["data"]["capabilities"][0], "calls");
+        }
+        assert!(connections.is_online("a").await);
+    }
+
+    #[tokio::test]
+    async fn incompatible_hello_disconnects() {
+        for version in [MIN_PROTOCOL_VERSION - 1, PROTOCOL_VERSION + 1] {
+            let connections = ConnectionManager::new();
+            let mut rx = connect(&connections, "a").await;
+
+            let result = handshake(&connections, "a", version, Vec::new()).await;
+            assert_eq!(result.unwrap_err(), "连接关闭");
+
+            // 先回复不兼容的 hello，再告知断开原因
+            let hello = next(&mut rx);
+            assert_eq!(hello["data"]["accepted"], false);
+            let error = next(&mut rx);
+            assert_eq!(error["type"], "error");
+            assert!(
+                error["data"]["message"]
+                    .as_str()
+                    .unwrap()
+                    .contains("不支持协议版本")
+            );
+            assert!(!connections.is_online("a").await);
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_hello_keeps_legacy_client_connected() {
+        // 未发送 hello 的旧客户端按版本 1 处理，仍在兼容范围内
+        assert!(is_compatible(1));
+
+        let connections = ConnectionManager::new();
+        let mut rx = connect(&connections, "a").await;
+        let pong = serde_json::to_string(&ServerMessage::Pong { online_count: 1 }).unwrap();
+        connections.send_to("a", pong).await.unwrap();
+        assert_eq!(next(&mut rx)["type"], "pong");
+        assert!(connections.is_online("a").await);
+    }
+}
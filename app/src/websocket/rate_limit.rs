@@ -0,0 +1,270 @@
+use crate::websocket::types::ClientMessage;
+use kernel::config::server_config;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// 限流的消息类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitKind {
+    Meet,
+    Private,
+    Broadcast,
+    Room,
+    /// 通话信令
+    Call,
+    Other,
+}
+
+impl From<&ClientMessage> for RateLimitKind {
+    fn from(msg: &ClientMessage) -> Self {
+        match msg {
+            ClientMessage::Meet { .. } => RateLimitKind::Meet,
+            ClientMessage::Private { .. } => RateLimitKind::Private,
+            ClientMessage::Broadcast { .. } => RateLimitKind::Broadcast,
+            ClientMessage::RoomMessage { .. } => RateLimitKind::Room,
+            ClientMessage::CallOffer { .. }
+            | ClientMessage::CallAnswer { .. }
+            | ClientMessage::IceCandidate { .. }
+            | ClientMessage::CallHangup { .. } => RateLimitKind::Call,
+            _ => RateLimitKind::Other,
+        }
+    }
+}
+
+/// 限流检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitVerdict {
+    /// 放行
+    Allowed,
+    /// 超限，丢弃本条消息
+    Limited,
+    /// 持续超限，需要断开连接
+    Disconnect,
+}
+
+/// 令牌桶
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    /// 每秒补充的令牌数
+    refill_rate: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, window: Duration) -> Self {
+        let capacity = capacity as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_rate: capacity / window.as_secs_f64().max(1.0),
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// 以指定时刻补充令牌并尝试取出一个，时刻早于上次补充时不补充
+    fn try_acquire_at(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_rate).min(self.capacity);
+        self.last_refill = self.last_refill.max(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// 单个连接的消息限流器，按消息类别分别维护令牌桶
+pub struct MessageRateLimiter {
+    buckets: HashMap<RateLimitKind, TokenBucket>,
+    window: Duration,
+    max_violations: u32,
+    violations: u32,
+    last_violation: Option<Instant>,
+}
+
+impl MessageRateLimiter {
+    /// 根据服务器配置创建限流器
+    pub fn from_config() -> Self {
+        let config = server_config();
+        let window = Duration::from_secs(config.ws_rate_window);
+
+        let mut buckets = HashMap::new();
+        for (kind, capacity) in [
+            (RateLimitKind::Meet, config.ws_rate_meet),
+            (RateLimitKind::Private, config.ws_rate_private),
+            (RateLimitKind::Broadcast, config.ws_rate_broadcast),
+            (RateLimitKind::Room, config.ws_rate_room),
+            (RateLimitKind::Call, config.ws_rate_call),
+            (RateLimitKind::Other, config.ws_rate_other),
+        ] {
+            // 0 表示该类别不限流
+            if capacity > 0 {
+                buckets.insert(kind, TokenBucket::new(capacity, window));
+            }
+        }
+
+        Self {
+            buckets,
+            window,
+            max_violations: config.ws_rate_max_violations,
+            violations: 0,
+            last_violation: None,
+        }
+    }
+
+    /// 检查一条消息是否允许通过
+    pub fn check(&mut self, kind: RateLimitKind) -> RateLimitVerdict {
+        self.check_at(kind, Instant::now())
+    }
+
+    /// 以指定时刻检查一条消息，便于测试注入时间
+    fn check_at(&mut self, kind: RateLimitKind, now: Instant) -> RateLimitVerdict {
+        let Some(bucket) = self.buckets.get_mut(&kind) else {
+            return RateLimitVerdict::Allowed;
+        };
+
+        if bucket.try_acquire_at(now) {
+            return RateLimitVerdict::Allowed;
+        }
+
+        // 距离上次超限已经超过一个周期，重新计数
+        if let Some(last) = self.last_violation
+            && now.saturating_duration_since(last) > self.window
+        {
+            self.violations = 0;
+        }
+        self.last_violation = Some(now);
+        self.violations += 1;
+
+        if self.max_violations > 0 && self.violations >= self.max_violations {
+            RateLimitVerdict::Disconnect
+        } else {
+            RateLimitVerdict::Limited
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(kind: RateLimitKind, capacity: u32, max_violations: u32) -> MessageRateLimiter {
+        let window = Duration::from_secs(10);
+        MessageRateLimiter {
+            buckets: HashMap::from([(kind, TokenBucket::new(capacity, window))]),
+            window,
+            max_violations,
+            violations: 0,
+            last_violation: None,
+        }
+    }
+
+    #[test]
+    fn bucket_allows_up_to_capacity() {
+        let mut bucket = TokenBucket::new(3, Duration::from_secs(10));
+        let now = bucket.last_refill;
+        assert!(bucket.try_acquire_at(now));
+        assert!(bucket.try_acquire_at(now));
+        assert!(bucket.try_acquire_at(now));
+        assert!(!bucket.try_acquire_at(now));
+    }
+
+    #[test]
+    fn bucket_refills_over_time() {
+        let mut bucket = TokenBucket::new(2, Duration::from_secs(10));
+        let start = bucket.last_refill;
+        assert!(bucket.try_acquire_at(start));
+        assert!(bucket.try_acquire_at(start));
+        assert!(!bucket.try_acquire_at(start));
+
+        // 每秒补充 0.2 个令牌，5 秒后补充 1 个
+        let later = start + Duration::from_secs(5);
+        assert!(bucket.try_acquire_at(later));
+        assert!(!bucket.try_acquire_at(later));
+
+        // 补充的令牌不超过容量
+        let much_later = later + Duration::from_secs(3600);
+        for _ in 0..2 {
+            assert!(bucket.try_acquire_at(much_later));
+        }
+        assert!(!bucket.try_acquire_at(much_later));
+    }
+
+    #[test]
+    fn unlimited_kind_is_always_allowed() {
+        let mut limiter = limiter(RateLimitKind::Private, 1, 1);
+        for _ in 0..100 {
+            assert_eq!(
+                limiter.check(RateLimitKind::Broadcast),
+                RateLimitVerdict::Allowed
+            );
+        }
+    }
+
+    #[test]
+    fn repeated_violations_disconnect() {
+        let mut limiter = limiter(RateLimitKind::Other, 1, 3);
+        assert_eq!(
+            limiter.check(RateLimitKind::Other),
+            RateLimitVerdict::Allowed
+        );
+        assert_eq!(
+            limiter.check(RateLimitKind::Other),
+            RateLimitVerdict::Limited
+        );
+        assert_eq!(
+            limiter.check(RateLimitKind::Other),
+            RateLimitVerdict::Limited
+        );
+        assert_eq!(
+            limiter.check(RateLimitKind::Other),
+            RateLimitVerdict::Disconnect
+        );
+    }
+
+    #[test]
+    fn violations_reset_after_quiet_window() {
+        // 容量 1、周期 10 秒，每 10 秒补充 1 个令牌
+        let mut limiter = limiter(RateLimitKind::Other, 1, 2);
+        let start = Instant::now();
+        assert_eq!(
+            limiter.check_at(RateLimitKind::Other, start),
+            RateLimitVerdict::Allowed
+        );
+        assert_eq!(
+            limiter.check_at(RateLimitKind::Other, start),
+            RateLimitVerdict::Limited
+        );
+
+        // 上次超限已超过一个周期，重新计数；补充的令牌先用掉
+        let later = start + limiter.window + Duration::from_secs(1);
+        assert_eq!(
+            limiter.check_at(RateLimitKind::Other, later),
+            RateLimitVerdict::Allowed
+        );
+        assert_eq!(
+            limiter.check_at(RateLimitKind::Other, later),
+            RateLimitVerdict::Limited
+        );
+    }
+
+    #[test]
+    fn zero_max_violations_never_disconnects() {
+        let mut limiter = limiter(RateLimitKind::Meet, 1, 0);
+        limiter.check(RateLimitKind::Meet);
+        for _ in 0..100 {
+            assert_eq!(
+                limiter.check(RateLimitKind::Meet),
+                RateLimitVerdict::Limited
+            );
+        }
+    }
+}
@@ -0,0 +1,648 @@
+use crate::websocket::types::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::RwLock;
+
+/// 房间名称的最大字符数
+pub const MAX_ROOM_NAME_LENGTH: usize = 32;
+/// 房间密码的最大字符数
+pub const MAX_ROOM_PASSWORD_LENGTH: usize = 64;
+/// 慢速模式的最大间隔（秒）
+const MAX_SLOW_MODE_SECONDS: u64 = 3600;
+/// 限时禁言的最长时间（秒），更长的禁言使用永久禁言
+const MAX_MUTE_SECONDS: u64 = 30 * 24 * 3600;
+
+/// 房间内角色，按权限从低到高排列
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RoomRole {
+    #[serde(rename = "member")]
+    Member,
+    #[serde(rename = "admin")]
+    Admin,
+    #[serde(rename = "owner")]
+    Owner,
+}
+
+/// 房间信息（对外展示，不包含密码）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoomInfo {
+    pub id: String,
+    pub name: String,
+    pub owner: String,
+    pub admins: Vec<String>,
+    pub capacity: usize,
+    pub has_password: bool,
+    /// 慢速模式间隔（秒），0 表示关闭
+    pub slow_mode: u64,
+    pub member_count: usize,
+    pub created_at: u64,
+}
+
+/// 聊天房间
+struct Room {
+    id: String,
+    name: String,
+    /// 房主
+    owner: String,
+    /// 管理员
+    admins: HashSet<String>,
+    /// 被禁言的成员及解除时间（秒级时间戳），None 表示永久禁言
+    muted: HashMap<String, Option<u64>>,
+    /// 慢速模式间隔（秒），0 表示关闭
+    slow_mode: u64,
+    /// 成员最近一次发言时间
+    last_spoke: HashMap<String, Instant>,
+    /// 最大人数
+    capacity: usize,
+    /// 进入密码
+    password: Option<String>,
+    /// 成员列表，按加入顺序排列
+    members: Vec<String>,
+    created_at: u64,
+}
+
+impl Room {
+    fn info(&self) -> RoomInfo {
+        RoomInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            owner: self.owner.clone(),
+            admins: self.admins.iter().cloned().collect(),
+            capacity: self.capacity,
+            has_password: self.password.is_some(),
+            slow_mode: self.slow_mode,
+            member_count: self.members.len(),
+            created_at: self.created_at,
+        }
+    }
+
+    fn is_member(&self, client_id: &str) -> bool {
+        self.members.iter().any(|m| m == client_id)
+    }
+
+    fn role_of(&self, client_id: &str) -> RoomRole {
+        if self.owner == client_id {
+            RoomRole::Owner
+        } else if self.admins.contains(client_id) {
+            RoomRole::Admin
+        } else {
+            RoomRole::Member
+        }
+    }
+
+    /// 校验操作者对目标成员的管理权限：操作者至少为管理员，且角色必须高于目标
+    fn check_manage(&self, operator: &str, target: &str) -> Result<(), String> {
+        if !self.is_member(target) {
+            return Err(format!("{} 不在该房间中", target));
+        }
+
+        let operator_role = self.role_of(operator);
+        if operator_role < RoomRole::Admin {
+            return Err("没有权限执行该操作".to_string());
+        }
+        if operator_role <= self.role_of(target) {
+            return Err("不能管理同级或更高权限的成员".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// 清理成员相关的状态
+    fn forget_member(&mut self, client_id: &str) {
+        self.members.retain(|m| m != client_id);
+        self.admins.remove(client_id);
+        self.muted.remove(client_id);
+        self.last_spoke.remove(client_id);
+    }
+}
+
+/// 成员离开房间后的结果
+#[derive(Debug, Clone)]
+pub struct RoomDeparture {
+    pub room_id: String,
+    /// 房间剩余成员，为空时房间已被销毁
+    pub remaining: Vec<String>,
+    /// 房主离开后新的房主
+    pub new_owner: Option<String>,
+}
+
+/// 房间管理器
+#[derive(Clone)]
+pub struct RoomManager {
+    /// 所有房间
+    rooms: Arc<RwLock<HashMap<String, Room>>>,
+    /// 每个客户端加入的房间
+    client_rooms: Arc<RwLock<HashMap<String, HashSet<String>>>>,
+}
+
+impl Default for RoomManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RoomManager {
+    pub fn new() -> Self {
+        Self {
+            rooms: Arc::new(RwLock::new(HashMap::new())),
+            client_rooms: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// 创建房间，创建者自动成为房主并加入房间
+    pub async fn create_room(
+        &self,
+        owner: &str,
+        name: String,
+        capacity: usize,
+        password: Option<String>,
+    ) -> RoomInfo {
+        let room = Room {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            owner: owner.to_string(),
+            admins: HashSet::new(),
+            muted: HashMap::new(),
+            slow_mode: 0,
+            last_spoke: HashMap::new(),
+            capacity,
+            password: password.filter(|p| !p.is_empty()),
+            members: vec![owner.to_string()],
+            created_at: now_secs(),
+        };
+        let info = room.info();
+
+        let mut rooms = self.rooms.write().await;
+        let mut client_rooms = self.client_rooms.write().await;
+        client_rooms
+            .entry(owner.to_string())
+            .or_default()
+            .insert(room.id.clone());
+        rooms.insert(room.id.clone(), room);
+        tracing::info!("客户端 {} 创建房间 {}", owner, info.id);
+
+        info
+    }
+
+    /// 加入房间，返回房间信息和加入前的成员列表
+    pub async fn join_room(
+        &self,
+        room_id: &str,
+        client_id: &str,
+        password: Option<&str>,
+    ) -> Result<(RoomInfo, Vec<String>), String> {
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| format!("房间 {} 不存在", room_id))?;
+
+        if room.is_member(client_id) {
+            return Err("已经在房间中".to_string());
+        }
+        if let Some(expected) = &room.password
+            && password != Some(expected.as_str())
+        {
+            return Err("房间密码错误".to_string());
+        }
+        if room.members.len() >= room.capacity {
+            return Err("房间人数已满".to_string());
+        }
+
+        let existing = room.members.clone();
+        room.members.push(client_id.to_string());
+        let info = room.info();
+        drop(rooms);
+
+        let mut client_rooms = self.client_rooms.write().await;
+        client_rooms
+            .entry(client_id.to_string())
+            .or_default()
+            .insert(room_id.to_string());
+        tracing::info!("客户端 {} 加入房间 {}", client_id, room_id);
+
+        Ok((info, existing))
+    }
+
+    /// 离开房间，房间为空时自动销毁
+    pub async fn leave_room(
+        &self,
+        room_id: &str,
+        client_id: &str,
+    ) -> Result<RoomDeparture, String> {
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| format!("房间 {} 不存在", room_id))?;
+
+        if !room.is_member(client_id) {
+            return Err("不在该房间中".to_string());
+        }
+        room.forget_member(client_id);
+
+        // 房主离开时，优先由管理员接任，没有管理员时由最早加入的成员接任
+        let mut new_owner = None;
+        if room.owner == client_id {
+            let next = room
+                .members
+                .iter()
+                .find(|m| room.admins.contains(*m))
+                .or_else(|| room.members.first())
+                .cloned();
+            if let Some(next) = next {
+                room.admins.remove(&next);
+                room.owner = next.clone();
+                new_owner = Some(next);
+            }
+        }
+
+        let remaining = room.members.clone();
+        if remaining.is_empty() {
+            rooms.remove(room_id);
+            tracing::info!("房间 {} 已无成员，自动销毁", room_id);
+        }
+        drop(rooms);
+
+        let mut client_rooms = self.client_rooms.write().await;
+        if let Some(joined) = client_rooms.get_mut(client_id) {
+            joined.remove(room_id);
+            if joined.is_empty() {
+                client_rooms.remove(client_id);
+            }
+        }
+        tracing::info!("客户端 {} 离开房间 {}", client_id, room_id);
+
+        Ok(RoomDeparture {
+            room_id: room_id.to_string(),
+            remaining,
+            new_owner,
+        })
+    }
+
+    /// 客户端断开时离开所有房间
+    pub async fn leave_all(&self, client_id: &str) -> Vec<RoomDeparture> {
+        let joined: Vec<String> = self
+            .client_rooms
+            .read()
+            .await
+            .get(client_id)
+            .map(|rooms| rooms.iter().cloned().collect())
+            .unwrap_or_default();
+
+        let mut departures = Vec::with_capacity(joined.len());
+        for room_id in joined {
+            if let Ok(departure) = self.leave_room(&room_id, client_id).await {
+                departures.push(departure);
+            }
+        }
+
+        departures
+    }
+
+    /// 获取房间成员（仅房间成员可查看）
+    pub async fn members(
+        &self,
+        room_id: &str,
+        client_id: &str,
+    ) -> Result<(RoomInfo, Vec<String>), String> {
+        let rooms = self.rooms.read().await;
+        let room = rooms
+            .get(room_id)
+            .ok_or_else(|| format!("房间 {} 不存在", room_id))?;
+
+        if !room.is_member(client_id) {
+            return Err("不在该房间中".to_string());
+        }
+
+        Ok((room.info(), room.members.clone()))
+    }
+
+    /// 发言前检查禁言和慢速模式，通过后返回房间成员
+    pub async fn check_speak(&self, room_id: &str, client_id: &str) -> Result<Vec<String>, String> {
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| format!("房间 {} 不存在", room_id))?;
+
+        if !room.is_member(client_id) {
+            return Err("不在该房间中".to_string());
+        }
+
+        if let Some(until) = room.muted.get(client_id).copied() {
+            match until {
+                Some(until) if until <= now_secs() => {
+                    // 禁言已到期
+                    room.muted.remove(client_id);
+                }
+                Some(until) => {
+                    return Err(format!(
+                        "你已被禁言，剩余 {} 秒",
+                        until.saturating_sub(now_secs())
+                    ));
+                }
+                None => return Err("你已被禁言".to_string()),
+            }
+        }
+
+        // 慢速模式只限制普通成员
+        if room.slow_mode > 0 && room.role_of(client_id) == RoomRole::Member {
+            let now = Instant::now();
+            if let Some(last) = room.last_spoke.get(client_id) {
+                let elapsed = now.duration_since(*last).as_secs();
+                if elapsed < room.slow_mode {
+                    return Err(format!(
+                        "慢速模式已开启，请 {} 秒后再发言",
+                        room.slow_mode - elapsed
+                    ));
+                }
+            }
+            room.last_spoke.insert(client_id.to_string(), now);
+        }
+
+        Ok(room.members.clone())
+    }
+
+    /// 房主设置管理员，返回房间成员
+    pub async fn promote_admin(
+        &self,
+        room_id: &str,
+        operator: &str,
+        target: &str,
+    ) -> Result<Vec<String>, String> {
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| format!("房间 {} 不存在", room_id))?;
+
+        if room.role_of(operator) != RoomRole::Owner {
+            return Err("只有房主可以设置管理员".to_string());
+        }
+        if !room.is_member(target) {
+            return Err(format!("{} 不在该房间中", target));
+        }
+        if room.role_of(target) != RoomRole::Member {
+            return Err(format!("{} 已经是管理员或房主", target));
+        }
+
+        room.admins.insert(target.to_string());
+        Ok(room.members.clone())
+    }
+
+    /// 房主取消管理员，返回房间成员
+    pub async fn demote_admin(
+        &self,
+        room_id: &str,
+        operator: &str,
+        target: &str,
+    ) -> Result<Vec<String>, String> {
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| format!("房间 {} 不存在", room_id))?;
+
+        if room.role_of(operator) != RoomRole::Owner {
+            return Err("只有房主可以取消管理员".to_string());
+        }
+        if !room.admins.remove(target) {
+            return Err(format!("{} 不是管理员", target));
+        }
+
+        Ok(room.members.clone())
+    }
+
+    /// 将成员踢出房间，返回踢出前的房间成员（包含被踢出的成员）
+    pub async fn kick(
+        &self,
+        room_id: &str,
+        operator: &str,
+        target: &str,
+    ) -> Result<Vec<String>, String> {
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| format!("房间 {} 不存在", room_id))?;
+
+        room.check_manage(operator, target)?;
+        let members = room.members.clone();
+        room.forget_member(target);
+        drop(rooms);
+
+        let mut client_rooms = self.client_rooms.write().await;
+        if let Some(joined) = client_rooms.get_mut(target) {
+            joined.remove(room_id);
+            if joined.is_empty() {
+                client_rooms.remove(target);
+            }
+        }
+        tracing::info!("客户端 {} 被 {} 踢出房间 {}", target, operator, room_id);
+
+        Ok(members)
+    }
+
+    /// 禁言成员，duration 为 None 时永久禁言，返回房间成员
+    pub async fn mute(
+        &self,
+        room_id: &str,
+        operator: &str,
+        target: &str,
+        duration: Option<u64>,
+    ) -> Result<Vec<String>, String> {
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| format!("房间 {} 不存在", room_id))?;
+
+        room.check_manage(operator, target)?;
+        if let Some(d) = duration
+            && d > MAX_MUTE_SECONDS
+        {
+            return Err(format!("禁言时长不能超过 {} 秒", MAX_MUTE_SECONDS));
+        }
+        room.muted.insert(
+            target.to_string(),
+            duration.map(|d| now_secs().saturating_add(d)),
+        );
+
+        Ok(room.members.clone())
+    }
+
+    /// 解除禁言，返回房间成员
+    pub async fn unmute(
+        &self,
+        room_id: &str,
+        operator: &str,
+        target: &str,
+    ) -> Result<Vec<String>, String> {
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| format!("房间 {} 不存在", room_id))?;
+
+        room.check_manage(operator, target)?;
+        if room.muted.remove(target).is_none() {
+            return Err(format!("{} 未被禁言", target));
+        }
+
+        Ok(room.members.clone())
+    }
+
+    /// 设置慢速模式（管理员及以上），seconds 为 0 时关闭，返回房间成员
+    pub async fn set_slow_mode(
+        &self,
+        room_id: &str,
+        operator: &str,
+        seconds: u64,
+    ) -> Result<Vec<String>, String> {
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| format!("房间 {} 不存在", room_id))?;
+
+        if room.role_of(operator) < RoomRole::Admin {
+            return Err("没有权限执行该操作".to_string());
+        }
+        if seconds > MAX_SLOW_MODE_SECONDS {
+            return Err(format!("慢速模式间隔不能超过 {} 秒", MAX_SLOW_MODE_SECONDS));
+        }
+
+        room.slow_mode = seconds;
+        room.last_spoke.clear();
+
+        Ok(room.members.clone())
+    }
+
+    /// 向房间内成员发送消息
+    pub async fn send_to_members(
+        &self,
+        connections: &ConnectionManager,
+        members: &[String],
+        message: &str,
+    ) {
+        for member in members {
+            let _ = connections.send_to(member, message.to_string()).await;
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 创建房间：owner 为房主，admin 为管理员，member 为普通成员
+    async fn room_with_roles() -> (RoomManager, String) {
+        let manager = RoomManager::new();
+        let room = manager
+            .create_room("owner", "测试房间".to_string(), 10, None)
+            .await;
+        for client in ["admin", "member", "other"] {
+            manager.join_room(&room.id, client, None).await.unwrap();
+        }
+        manager
+            .promote_admin(&room.id, "owner", "admin")
+            .await
+            .unwrap();
+        (manager, room.id)
+    }
+
+    #[tokio::test]
+    async fn member_cannot_moderate() {
+        let (manager, room_id) = room_with_roles().await;
+        assert!(manager.kick(&room_id, "member", "other").await.is_err());
+        assert!(
+            manager
+                .mute(&room_id, "member", "other", Some(60))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn admin_cannot_manage_owner_or_admin() {
+        let (manager, room_id) = room_with_roles().await;
+        assert!(manager.kick(&room_id, "admin", "owner").await.is_err());
+        manager.join_room(&room_id, "admin2", None).await.unwrap();
+        manager
+            .promote_admin(&room_id, "owner", "admin2")
+            .await
+            .unwrap();
+        assert!(manager.kick(&room_id, "admin", "admin2").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn kick_removes_member() {
+        let (manager, room_id) = room_with_roles().await;
+        let before = manager.kick(&room_id, "admin", "member").await.unwrap();
+        assert!(before.contains(&"member".to_string()));
+
+        let (_, members) = manager.members(&room_id, "owner").await.unwrap();
+        assert!(!members.contains(&"member".to_string()));
+        assert!(manager.check_speak(&room_id, "member").await.is_err());
+        // 被踢出后不再随断开连接离开该房间
+        assert!(manager.leave_all("member").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn muted_member_cannot_speak_until_unmuted() {
+        let (manager, room_id) = room_with_roles().await;
+        manager
+            .mute(&room_id, "admin", "member", Some(60))
+            .await
+            .unwrap();
+        assert!(manager.check_speak(&room_id, "member").await.is_err());
+
+        manager.unmute(&room_id, "admin", "member").await.unwrap();
+        assert!(manager.check_speak(&room_id, "member").await.is_ok());
+        assert!(manager.unmute(&room_id, "admin", "member").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn permanent_mute() {
+        let (manager, room_id) = room_with_roles().await;
+        manager
+            .mute(&room_id, "owner", "member", None)
+            .await
+            .unwrap();
+        assert_eq!(
+            manager.check_speak(&room_id, "member").await.unwrap_err(),
+            "你已被禁言"
+        );
+    }
+
+    #[tokio::test]
+    async fn mute_expires() {
+        let (manager, room_id) = room_with_roles().await;
+        manager
+            .mute(&room_id, "admin", "member", Some(0))
+            .await
+            .unwrap();
+        assert!(manager.check_speak(&room_id, "member").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn mute_duration_is_capped() {
+        let (manager, room_id) = room_with_roles().await;
+        assert!(
+            manager
+                .mute(&room_id, "admin", "member", Some(u64::MAX))
+                .await
+                .is_err()
+        );
+        assert!(manager.check_speak(&room_id, "member").await.is_ok());
+
+        manager
+            .mute(&room_id, "admin", "member", Some(MAX_MUTE_SECONDS))
+            .await
+            .unwrap();
+        assert!(manager.check_speak(&room_id, "member").await.is_err());
+    }
+}
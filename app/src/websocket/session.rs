@@ -0,0 +1,701 @@
+use futures_util::future::BoxFuture;
+use redis::{AsyncCommands, Script, aio::MultiplexedConnection};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::RwLock;
+
+/// 会话空闲多久后过期（秒），仅用于 redis 中的会话，防止节点失效后留下的会话永久存在
+pub const SESSION_IDLE_TTL_SECONDS: u64 = 24 * 3600;
+
+/// 一对一聊天会话
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatSession {
+    pub id: String,
+    /// 会话双方
+    pub participants: [String; 2],
+    pub started_at: u64,
+    /// 双方匹配时都提供了公钥，启用端到端加密，明文消息不再入库
+    pub e2e: bool,
+}
+
+impl ChatSession {
+    pub fn is_participant(&self, client_id: &str) -> bool {
+        self.participants.iter().any(|p| p == client_id)
+    }
+}
+
+/// 会话中已发送、仍可撤回的私聊消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentMessage {
+    pub id: String,
+    pub session_id: String,
+    pub sender: String,
+    pub receiver: String,
+    pub timestamp: u64,
+}
+
+/// 会话存储
+///
+/// 以双方ID（按字典序排列）为键，同一对用户同时只有一个会话；开始和结束会话都必须是原子操作
+pub trait SessionStore: Send + Sync {
+    /// 保存新会话。双方已有会话时，replace 为 true 则结束已有会话，否则保留并返回已有会话
+    fn insert(
+        &self,
+        session: ChatSession,
+        replace: bool,
+    ) -> BoxFuture<'_, Result<ChatSession, String>>;
+
+    /// 按ID获取会话
+    fn get<'a>(&'a self, session_id: &'a str)
+    -> BoxFuture<'a, Result<Option<ChatSession>, String>>;
+
+    /// 获取双方当前的会话
+    fn between<'a>(
+        &'a self,
+        a: &'a str,
+        b: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ChatSession>, String>>;
+
+    /// 移除会话，返回被移除的会话
+    fn remove<'a>(
+        &'a self,
+        session_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ChatSession>, String>>;
+
+    /// 某个用户参与的所有会话ID
+    fn sessions_of<'a>(&'a self, client_id: &'a str) -> BoxFuture<'a, Result<Vec<String>, String>>;
+
+    /// 记录一条可撤回的消息，window 秒后过期
+    fn record_message(
+        &self,
+        message: SentMessage,
+        window: u64,
+    ) -> BoxFuture<'_, Result<(), String>>;
+
+    /// 获取可撤回的消息
+    fn get_message<'a>(
+        &'a self,
+        message_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<SentMessage>, String>>;
+
+    /// 移除可撤回的消息，返回消息是否仍然存在（同一条消息只能撤回一次）
+    fn remove_message<'a>(&'a self, message_id: &'a str) -> BoxFuture<'a, Result<bool, String>>;
+}
+
+#[derive(Default)]
+struct SessionIndex {
+    /// session_id -> 会话
+    sessions: HashMap<String, ChatSession>,
+    /// 双方ID（按字典序排列）-> session_id
+    pairs: HashMap<(String, String), String>,
+    /// 撤回时间窗口内的消息：message_id -> (消息, 过期时间)
+    recent: HashMap<String, (SentMessage, u64)>,
+}
+
+impl SessionIndex {
+    fn remove(&mut self, session_id: &str) -> Option<ChatSession> {
+        let session = self.sessions.remove(session_id)?;
+        let [a, b] = &session.participants;
+        self.pairs.remove(&pair_key(a, b));
+        self.recent
+            .retain(|_, (message, _)| message.session_id != session_id);
+        Some(session)
+    }
+}
+
+fn pair_key(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+/// 进程内会话存储
+#[derive(Default)]
+pub struct MemorySessionStore {
+    index: RwLock<SessionIndex>,
+}
+
+impl MemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn insert(
+        &self,
+        session: ChatSession,
+        replace: bool,
+    ) -> BoxFuture<'_, Result<ChatSession, String>> {
+        Box::pin(async move {
+            let mut index = self.index.write().await;
+            let [a, b] = &session.participants;
+            let pair = pair_key(a, b);
+            if let Some(previous) = index.pairs.get(&pair).cloned() {
+                if !replace && let Some(current) = index.sessions.get(&previous) {
+                    return Ok(current.clone());
+                }
+                index.remove(&previous);
+            }
+            index.pairs.insert(pair, session.id.clone());
+            index.sessions.insert(session.id.clone(), session.clone());
+            Ok(session)
+        })
+    }
+
+    fn get<'a>(
+        &'a self,
+        session_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ChatSession>, String>> {
+        Box::pin(async move { Ok(self.index.read().await.sessions.get(session_id).cloned()) })
+    }
+
+    fn between<'a>(
+        &'a self,
+        a: &'a str,
+        b: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ChatSession>, String>> {
+        Box::pin(async move {
+            let index = self.index.read().await;
+            Ok(index
+                .pairs
+                .get(&pair_key(a, b))
+                .and_then(|id| index.sessions.get(id))
+                .cloned())
+        })
+    }
+
+    fn remove<'a>(
+        &'a self,
+        session_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ChatSession>, String>> {
+        Box::pin(async move { Ok(self.index.write().await.remove(session_id)) })
+    }
+
+    fn sessions_of<'a>(&'a self, client_id: &'a str) -> BoxFuture<'a, Result<Vec<String>, String>> {
+        Box::pin(async move {
+            Ok(self
+                .index
+                .read()
+                .await
+                .sessions
+                .values()
+                .filter(|session| session.is_participant(client_id))
+                .map(|session| session.id.clone())
+                .collect())
+        })
+    }
+
+    fn record_message(
+        &self,
+        message: SentMessage,
+        window: u64,
+    ) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            let now = now_secs();
+            let mut index = self.index.write().await;
+            if !index.sessions.contains_key(&message.session_id) {
+                return Ok(());
+            }
+            // 顺带清理已超过撤回时间的记录
+            index.recent.retain(|_, (_, expires_at)| *expires_at >= now);
+            index
+                .recent
+                .insert(message.id.clone(), (message, now.saturating_add(window)));
+            Ok(())
+        })
+    }
+
+    fn get_message<'a>(
+        &'a self,
+        message_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<SentMessage>, String>> {
+        Box::pin(async move {
+            let index = self.index.read().await;
+            Ok(index
+                .recent
+                .get(message_id)
+                .filter(|(_, expires_at)| *expires_at >= now_secs())
+                .map(|(message, _)| message.clone()))
+        })
+    }
+
+    fn remove_message<'a>(&'a self, message_id: &'a str) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move { Ok(self.index.write().await.recent.remove(message_id).is_some()) })
+    }
+}
+
+/// 会话：session_id -> ChatSession JSON
+const SESSION_KEY_PREFIX: &str = "ws:session:";
+/// 双方当前的会话：双方ID（按字典序排列）-> session_id
+const PAIR_KEY_PREFIX: &str = "ws:session:pair:";
+/// 用户参与的会话ID集合
+const CLIENT_KEY_PREFIX: &str = "ws:session:client:";
+/// 可撤回的消息：message_id -> SentMessage JSON
+const MESSAGE_KEY_PREFIX: &str = "ws:session:message:";
+
+/// 保存会话：双方已有会话时，ARGV[4] 为 1 则结束已有会话，否则返回已有会话
+const INSERT_SCRIPT: &str = r#"
+local previous = redis.call('GET', KEYS[1])
+if previous then
+    local raw = redis.call('GET', 'ws:session:' .. previous)
+    if raw and ARGV[4] ~= '1' then
+        redis.call('EXPIRE', KEYS[1], ARGV[3])
+        redis.call('EXPIRE', 'ws:session:' .. previous, ARGV[3])
+        return raw
+    end
+    redis.call('DEL', 'ws:session:' .. previous)
+    redis.call('SREM', KEYS[3], previous)
+    redis.call('SREM', KEYS[4], previous)
+end
+redis.call('SET', KEYS[1], ARGV[1], 'EX', ARGV[3])
+redis.call('SET', KEYS[2], ARGV[2], 'EX', ARGV[3])
+redis.call('SADD', KEYS[3], ARGV[1])
+redis.call('EXPIRE', KEYS[3], ARGV[3])
+redis.call('SADD', KEYS[4], ARGV[1])
+redis.call('EXPIRE', KEYS[4], ARGV[3])
+return ARGV[2]
+"#;
+
+/// 移除会话：同时清理双方索引，返回被移除的会话
+const REMOVE_SCRIPT: &str = r#"
+local raw = redis.call('GET', KEYS[1])
+if not raw then
+    return false
+end
+local session = cjson.decode(raw)
+local a, b = session.participants[1], session.participants[2]
+local first, second = a, b
+if first > second then
+    first, second = second, first
+end
+local pair = 'ws:session:pair:' .. first .. ':' .. second
+if redis.call('GET', pair) == session.id then
+    redis.call('DEL', pair)
+end
+redis.call('SREM', 'ws:session:client:' .. a, session.id)
+redis.call('SREM', 'ws:session:client:' .. b, session.id)
+redis.call('DEL', KEYS[1])
+return raw
+"#;
+
+/// 基于 redis 的会话存储，集群中的节点共享会话，开始和结束会话通过 Lua 脚本原子完成
+pub struct RedisSessionStore {
+    conn: MultiplexedConnection,
+}
+
+impl RedisSessionStore {
+    pub fn new(conn: MultiplexedConnection) -> Self {
+        Self { conn }
+    }
+}
+
+fn redis_pair_key(a: &str, b: &str) -> String {
+    let (first, second) = pair_key(a, b);
+    format!("{}{}:{}", PAIR_KEY_PREFIX, first, second)
+}
+
+fn decode<T: serde::de::DeserializeOwned>(raw: Option<String>) -> Result<Option<T>, String> {
+    raw.map(|raw| serde_json::from_str(&raw).map_err(|e| format!("解析失败: {}", e)))
+        .transpose()
+}
+
+impl SessionStore for RedisSessionStore {
+    fn insert(
+        &self,
+        session: ChatSession,
+        replace: bool,
+    ) -> BoxFuture<'_, Result<ChatSession, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let [a, b] = &session.participants;
+            let raw = serde_json::to_string(&session).map_err(|e| format!("序列化失败: {}", e))?;
+            let saved: String = Script::new(INSERT_SCRIPT)
+                .key(redis_pair_key(a, b))
+                .key(format!("{}{}", SESSION_KEY_PREFIX, session.id))
+                .key(format!("{}{}", CLIENT_KEY_PREFIX, a))
+                .key(format!("{}{}", CLIENT_KEY_PREFIX, b))
+                .arg(&session.id)
+                .arg(raw)
+                .arg(SESSION_IDLE_TTL_SECONDS)
+                .arg(if replace { 1 } else { 0 })
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| format!("保存会话失败: {}", e))?;
+            serde_json::from_str(&saved).map_err(|e| format!("解析失败: {}", e))
+        })
+    }
+
+    fn get<'a>(
+        &'a self,
+        session_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ChatSession>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let raw: Option<String> = conn
+                .get(format!("{}{}", SESSION_KEY_PREFIX, session_id))
+                .await
+                .map_err(|e| format!("读取会话失败: {}", e))?;
+            decode(raw)
+        })
+    }
+
+    fn between<'a>(
+        &'a self,
+        a: &'a str,
+        b: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ChatSession>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let session_id: Option<String> = conn
+                .get(redis_pair_key(a, b))
+                .await
+                .map_err(|e| format!("读取会话失败: {}", e))?;
+            match session_id {
+                Some(session_id) => self.get(&session_id).await,
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn remove<'a>(
+        &'a self,
+        session_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ChatSession>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let raw: Option<String> = Script::new(REMOVE_SCRIPT)
+                .key(format!("{}{}", SESSION_KEY_PREFIX, session_id))
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| format!("移除会话失败: {}", e))?;
+            decode(raw)
+        })
+    }
+
+    fn sessions_of<'a>(&'a self, client_id: &'a str) -> BoxFuture<'a, Result<Vec<String>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            conn.smembers(format!("{}{}", CLIENT_KEY_PREFIX, client_id))
+                .await
+                .map_err(|e| format!("读取会话失败: {}", e))
+        })
+    }
+
+    fn record_message(
+        &self,
+        message: SentMessage,
+        window: u64,
+    ) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            if window == 0 {
+                return Ok(());
+            }
+            let mut conn = self.conn.clone();
+            let raw = serde_json::to_string(&message).map_err(|e| format!("序列化失败: {}", e))?;
+            conn.set_ex::<_, _, ()>(format!("{}{}", MESSAGE_KEY_PREFIX, message.id), raw, window)
+                .await
+                .map_err(|e| format!("记录消息失败: {}", e))
+        })
+    }
+
+    fn get_message<'a>(
+        &'a self,
+        message_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<SentMessage>, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let raw: Option<String> = conn
+                .get(format!("{}{}", MESSAGE_KEY_PREFIX, message_id))
+                .await
+                .map_err(|e| format!("读取消息失败: {}", e))?;
+            decode(raw)
+        })
+    }
+
+    fn remove_message<'a>(&'a self, message_id: &'a str) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let removed: i32 = conn
+                .del(format!("{}{}", MESSAGE_KEY_PREFIX, message_id))
+                .await
+                .map_err(|e| format!("移除消息失败: {}", e))?;
+            Ok(removed > 0)
+        })
+    }
+}
+
+/// 一对一会话管理器，会话从匹配成功（或首次私聊）开始，到任一方离开聊天或断开连接结束
+///
+/// 集群模式下会话保存在 redis 中，双方连接在不同节点时看到的是同一个会话
+#[derive(Clone)]
+pub struct SessionManager {
+    store: Arc<dyn SessionStore>,
+}
+
+impl Default for SessionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionManager {
+    /// 会话保存在本进程内
+    pub fn new() -> Self {
+        Self {
+            store: Arc::new(MemorySessionStore::new()),
+        }
+    }
+
+    /// 会话保存在 redis 中，供集群中的节点共享
+    pub fn with_redis(conn: MultiplexedConnection) -> Self {
+        Self {
+            store: Arc::new(RedisSessionStore::new(conn)),
+        }
+    }
+
+    /// 开始新会话，双方之前的会话会被结束
+    pub async fn start(&self, a: &str, b: &str, e2e: bool) -> Result<ChatSession, String> {
+        let session = self.store.insert(new_session(a, b, e2e), true).await?;
+        tracing::info!("用户 {} 和 {} 开始会话 {}", a, b, session.id);
+        Ok(session)
+    }
+
+    /// 获取双方当前的会话，读取失败时视为没有会话
+    pub async fn between(&self, a: &str, b: &str) -> Option<ChatSession> {
+        self.store.between(a, b).await.unwrap_or_else(|e| {
+            tracing::error!("获取用户 {} 和 {} 的会话失败: {}", a, b, e);
+            None
+        })
+    }
+
+    /// 获取进行中的会话，读取失败时视为会话不存在
+    pub async fn get(&self, session_id: &str) -> Option<ChatSession> {
+        self.store.get(session_id).await.unwrap_or_else(|e| {
+            tracing::error!("获取会话 {} 失败: {}", session_id, e);
+            None
+        })
+    }
+
+    /// 记录一条可撤回的消息
+    pub async fn record_message(&self, message: SentMessage, window: u64) {
+        let message_id = message.id.clone();
+        if let Err(e) = self.store.record_message(message, window).await {
+            tracing::error!("记录可撤回消息 {} 失败: {}", message_id, e);
+        }
+    }
+
+    /// 撤回消息：只有发送者可以在撤回时间内撤回，成功后返回被撤回的消息
+    pub async fn recall(
+        &self,
+        message_id: &str,
+        client_id: &str,
+        window: u64,
+    ) -> Result<SentMessage, String> {
+        let Some(message) = self.store.get_message(message_id).await? else {
+            return Err("消息不存在或已超过撤回时间".to_string());
+        };
+        if message.sender != client_id {
+            return Err("只能撤回自己发送的消息".to_string());
+        }
+        if message.timestamp.saturating_add(window) < now_secs() {
+            self.store.remove_message(message_id).await?;
+            return Err("消息已超过撤回时间".to_string());
+        }
+        // 会话已结束的消息不能再撤回
+        if self.store.get(&message.session_id).await?.is_none() {
+            self.store.remove_message(message_id).await?;
+            return Err("消息不存在或已超过撤回时间".to_string());
+        }
+        // 同一条消息同时撤回多次时只有一次成功
+        if !self.store.remove_message(message_id).await? {
+            return Err("消息不存在或已超过撤回时间".to_string());
+        }
+
+        Ok(message)
+    }
+
+    /// 结束双方之间的会话
+    pub async fn end(&self, a: &str, b: &str) -> Option<ChatSession> {
+        let session = self.between(a, b).await?;
+        self.remove(&session.id).await
+    }
+
+    /// 结束某个用户参与的所有会话（断开连接时调用）
+    pub async fn end_all(&self, client_id: &str) -> Vec<ChatSession> {
+        let session_ids = self.store.sessions_of(client_id).await.unwrap_or_else(|e| {
+            tracing::error!("获取用户 {} 的会话失败: {}", client_id, e);
+            Vec::new()
+        });
+
+        let mut ended = Vec::with_capacity(session_ids.len());
+        for session_id in session_ids {
+            if let Some(session) = self.remove(&session_id).await {
+                ended.push(session);
+            }
+        }
+        ended
+    }
+
+    async fn remove(&self, session_id: &str) -> Option<ChatSession> {
+        self.store.remove(session_id).await.unwrap_or_else(|e| {
+            tracing::error!("结束会话 {} 失败: {}", session_id, e);
+            None
+        })
+    }
+}
+
+fn new_session(a: &str, b: &str, e2e: bool) -> ChatSession {
+    ChatSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        participants: [a.to_string(), b.to_string()],
+        started_at: now_secs(),
+        e2e,
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sent(id: &str, session: &ChatSession, sender: &str, timestamp: u64) -> SentMessage {
+        let receiver = session
+            .participants
+            .iter()
+            .find(|p| p.as_str() != sender)
+            .unwrap()
+            .clone();
+        SentMessage {
+            id: id.to_string(),
+            session_id: session.id.clone(),
+            sender: sender.to_string(),
+            receiver,
+            timestamp,
+        }
+    }
+
+    #[tokio::test]
+    async fn between_finds_session_regardless_of_order() {
+        let sessions = SessionManager::new();
+        assert!(sessions.between("a", "b").await.is_none());
+
+        let started = sessions.start("a", "b", false).await.unwrap();
+        assert_eq!(sessions.between("b", "a").await.unwrap().id, started.id);
+        assert_eq!(sessions.between("a", "b").await.unwrap().id, started.id);
+    }
+
+    #[tokio::test]
+    async fn start_replaces_previous_session() {
+        let sessions = SessionManager::new();
+        let old = sessions.start("a", "b", false).await.unwrap();
+        let new = sessions.start("b", "a", true).await.unwrap();
+        assert_ne!(old.id, new.id);
+        assert!(sessions.get(&old.id).await.is_none());
+
+        // e2e 标记随会话保存
+        let current = sessions.between("a", "b").await.unwrap();
+        assert_eq!(current.id, new.id);
+        assert!(current.e2e);
+    }
+
+    #[tokio::test]
+    async fn end_all_only_ends_own_sessions() {
+        let sessions = SessionManager::new();
+        let ab = sessions.start("a", "b", false).await.unwrap();
+        let ac = sessions.start("a", "c", false).await.unwrap();
+        let bc = sessions.start("b", "c", false).await.unwrap();
+
+        let mut ended: Vec<String> = sessions
+            .end_all("a")
+            .await
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        ended.sort();
+        let mut expected = vec![ab.id, ac.id];
+        expected.sort();
+        assert_eq!(ended, expected);
+        assert!(sessions.between("a", "b").await.is_none());
+        assert!(sessions.get(&bc.id).await.is_some());
+        assert!(sessions.end("a", "c").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn recall_only_by_sender_and_once() {
+        let sessions = SessionManager::new();
+        let session = sessions.start("a", "b", false).await.unwrap();
+        sessions
+            .record_message(sent("m1", &session, "a", now_secs()), 120)
+            .await;
+
+        assert!(sessions.recall("m1", "b", 120).await.is_err());
+        assert_eq!(sessions.recall("m1", "a", 120).await.unwrap().id, "m1");
+        assert!(sessions.recall("m1", "a", 120).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn recall_rejects_expired_or_ended() {
+        let sessions = SessionManager::new();
+        let session = sessions.start("a", "b", false).await.unwrap();
+        sessions
+            .record_message(sent("old", &session, "a", now_secs() - 300), 120)
+            .await;
+        assert!(sessions.recall("old", "a", 120).await.is_err());
+
+        sessions
+            .record_message(sent("m2", &session, "a", now_secs()), 120)
+            .await;
+        sessions.end("a", "b").await;
+        assert!(sessions.recall("m2", "a", 120).await.is_err());
+    }
+
+    /// 需要本地 redis-server：cargo test -p app session -- --ignored
+    #[tokio::test]
+    #[ignore = "需要本地 redis-server"]
+    async fn redis_sessions_are_shared() {
+        let url = std::env::var("TEST_REDIS_URL")
+            .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let client = redis::Client::open(url).unwrap();
+        let node_a =
+            SessionManager::with_redis(client.get_multiplexed_async_connection().await.unwrap());
+        let node_b =
+            SessionManager::with_redis(client.get_multiplexed_async_connection().await.unwrap());
+        let a = uuid::Uuid::new_v4().to_string();
+        let b = uuid::Uuid::new_v4().to_string();
+
+        // 一个节点上开始的会话（含 e2e 标记），另一个节点上私聊时复用
+        let started = node_a.start(&a, &b, true).await.unwrap();
+        let reused = node_b.between(&b, &a).await.unwrap();
+        assert_eq!(reused.id, started.id);
+        assert!(reused.e2e);
+        assert!(node_b.get(&started.id).await.is_some());
+
+        // 可撤回消息在任一节点上都只能撤回一次
+        let message_id = uuid::Uuid::new_v4().to_string();
+        node_a
+            .record_message(sent(&message_id, &started, &a, now_secs()), 120)
+            .await;
+        assert!(node_b.recall(&message_id, &b, 120).await.is_err());
+        assert!(node_b.recall(&message_id, &a, 120).await.is_ok());
+        assert!(node_a.recall(&message_id, &a, 120).await.is_err());
+
+        // 一个节点上结束的会话，另一个节点上也随之结束
+        let ended = node_b.end_all(&a).await;
+        assert_eq!(ended.len(), 1);
+        assert!(node_a.get(&started.id).await.is_none());
+        assert!(node_a.between(&a, &b).await.is_none());
+    }
+}
@@ -1,8 +1,19 @@
+use crate::websocket::call::{CallStatus, IceCandidateInit};
+use crate::websocket::cluster::{ClusterBus, ClusterEvent, NODE_TTL_SECONDS};
+use crate::websocket::content::MessageContent;
+use crate::websocket::history::BroadcastRecord;
+use crate::websocket::match_queue::{
+    MATCH_TIMEOUT_SECONDS, MatchQueue, MemoryMatchQueue, RedisMatchQueue,
+};
+use crate::websocket::room::RoomInfo;
+use futures_util::StreamExt;
+use kernel::auth::token::Claims;
+use kernel::redis::RedisResult;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::HashMap,
     sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::{
     sync::{RwLock, mpsc},
@@ -10,18 +21,66 @@ use tokio::{
     time::{self, Duration},
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+/// 私聊消息体
+///
+/// 新版客户端发送 `content`，旧版客户端发送数字 `type` 和 `text`；下发时两种字段都会带上
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "MessageWire", into = "MessageWire")]
 pub struct MessageStruct {
-    #[serde(rename = "burnAfterRead")]
     pub burn_after_read: bool,
-    pub r#type: u32,
-    pub text: String,
+    pub content: MessageContent,
+}
+
+/// 私聊消息体的传输格式
+#[derive(Serialize, Deserialize)]
+struct MessageWire {
+    #[serde(rename = "burnAfterRead")]
+    burn_after_read: bool,
+    content: Option<MessageContent>,
+    r#type: Option<u32>,
+    text: Option<String>,
+}
+
+impl TryFrom<MessageWire> for MessageStruct {
+    type Error = String;
+
+    fn try_from(wire: MessageWire) -> Result<Self, Self::Error> {
+        let content = match (wire.content, wire.r#type, wire.text) {
+            (Some(content), _, _) => content,
+            (None, Some(code), Some(text)) => MessageContent::from_legacy(code, text),
+            _ => return Err("消息缺少 content 或 type/text 字段".to_string()),
+        };
+        content.validate()?;
+
+        Ok(Self {
+            burn_after_read: wire.burn_after_read,
+            content,
+        })
+    }
+}
+
+impl From<MessageStruct> for MessageWire {
+    fn from(message: MessageStruct) -> Self {
+        Self {
+            burn_after_read: message.burn_after_read,
+            r#type: Some(message.content.code()),
+            text: Some(message.content.legacy_text()),
+            content: Some(message.content),
+        }
+    }
 }
 
 /// 客户端消息类型
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum ClientMessage {
+    /// 握手：声明客户端的协议版本和支持的功能
+    #[serde(rename = "hello")]
+    Hello {
+        protocol_version: u32,
+        #[serde(default)]
+        capabilities: Vec<String>,
+    },
     /// 匹配命令
     #[serde(rename = "meet")]
     Meet {
@@ -29,6 +88,9 @@ pub enum ClientMessage {
         age_index: u32,
         sex_index: u32,
         location: String,
+        /// 端到端加密公钥，匹配成功后交给对方
+        #[serde(default)]
+        public_key: Option<String>,
     },
     /// 离开某个1对1聊天
     #[serde(rename = "depart")]
@@ -36,6 +98,29 @@ pub enum ClientMessage {
     /// 私聊消息
     #[serde(rename = "private")]
     Private { to: String, message: MessageStruct },
+    /// 撤回自己发送的私聊消息
+    #[serde(rename = "recall")]
+    Recall { id: String },
+    /// 向会话中的对方发起语音/视频通话
+    #[serde(rename = "call_offer")]
+    CallOffer {
+        to: String,
+        sdp: String,
+        #[serde(default)]
+        video: bool,
+    },
+    /// 接听通话
+    #[serde(rename = "call_answer")]
+    CallAnswer { call_id: String, sdp: String },
+    /// 交换 ICE 候选
+    #[serde(rename = "ice_candidate")]
+    IceCandidate {
+        call_id: String,
+        candidate: IceCandidateInit,
+    },
+    /// 挂断或拒接
+    #[serde(rename = "call_hangup")]
+    CallHangup { call_id: String },
     /// 获取在线用户列表
     #[serde(rename = "list")]
     List,
@@ -45,6 +130,56 @@ pub enum ClientMessage {
     /// 广播消息
     #[serde(rename = "broadcast")]
     Broadcast { message: String },
+    /// 获取更早的广播历史，before 为已知最早一条消息的ID
+    #[serde(rename = "broadcast_history")]
+    BroadcastHistory {
+        before: Option<u64>,
+        limit: Option<usize>,
+    },
+    /// 创建房间
+    #[serde(rename = "create_room")]
+    CreateRoom {
+        name: String,
+        capacity: usize,
+        password: Option<String>,
+    },
+    /// 加入房间
+    #[serde(rename = "join_room")]
+    JoinRoom {
+        room_id: String,
+        password: Option<String>,
+    },
+    /// 离开房间
+    #[serde(rename = "leave_room")]
+    LeaveRoom { room_id: String },
+    /// 房间消息
+    #[serde(rename = "room_message")]
+    RoomMessage { room_id: String, message: String },
+    /// 获取房间成员列表
+    #[serde(rename = "room_members")]
+    RoomMembers { room_id: String },
+    /// 设置房间管理员（仅房主）
+    #[serde(rename = "promote_admin")]
+    PromoteAdmin { room_id: String, client_id: String },
+    /// 取消房间管理员（仅房主）
+    #[serde(rename = "demote_admin")]
+    DemoteAdmin { room_id: String, client_id: String },
+    /// 踢出房间成员
+    #[serde(rename = "kick_member")]
+    KickMember { room_id: String, client_id: String },
+    /// 禁言房间成员，duration 为空时永久禁言
+    #[serde(rename = "mute_member")]
+    MuteMember {
+        room_id: String,
+        client_id: String,
+        duration: Option<u64>,
+    },
+    /// 解除禁言
+    #[serde(rename = "unmute_member")]
+    UnmuteMember { room_id: String, client_id: String },
+    /// 设置房间慢速模式，seconds 为 0 时关闭
+    #[serde(rename = "slow_mode")]
+    SlowMode { room_id: String, seconds: u64 },
 }
 
 /// 客户端信息
@@ -55,7 +190,7 @@ pub struct ClientInfo {
 }
 
 /// 等待匹配的用户信息
-#[derive(Debug, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WaitingUser {
     pub client_id: String,
     pub user_key: String,
@@ -63,6 +198,9 @@ pub struct WaitingUser {
     pub sex_index: u32,
     pub location: String,
     pub join_time: u64,
+    /// 端到端加密公钥
+    #[serde(default)]
+    pub public_key: Option<String>,
 }
 
 /// 服务器消息类型
@@ -75,19 +213,39 @@ pub enum ServerMessage {
         client_id: String,
         online_count: usize,
     },
+    /// 握手响应，accepted 为 false 时连接随后会被断开
+    #[serde(rename = "hello")]
+    Hello {
+        accepted: bool,
+        protocol_version: u32,
+        min_protocol_version: u32,
+        capabilities: Vec<String>,
+    },
     #[serde(rename = "meet_loading")]
     MeetLoading { message: String },
     #[serde(rename = "meet")]
-    Meet { user_key: String },
+    Meet {
+        /// 对方匹配时提交的用户标识，仅用于展示
+        user_key: String,
+        /// 对方的客户端ID（令牌 sub），私聊、离开聊天等消息的 `to` 使用该ID
+        to: String,
+    },
     #[serde(rename = "meet_failed")]
     MeetFailed { message: String },
     #[serde(rename = "meet_success")]
     MeetSuccess {
+        /// 对方的客户端ID
         to: String,
+        /// 本次聊天的会话ID，可用于查询聊天记录
+        session_id: String,
         message: String,
         age: u32,
         sex: u32,
         location: String,
+        /// 对方的端到端加密公钥
+        public_key: Option<String>,
+        /// 双方都提供了公钥，会话启用端到端加密
+        e2e: bool,
     },
     /// 离开某个1对1聊天
     #[serde(rename = "depart")]
@@ -95,16 +253,72 @@ pub enum ServerMessage {
     /// 私聊消息
     #[serde(rename = "private")]
     Private {
+        /// 服务端分配的消息ID
+        id: String,
+        session_id: String,
         from: String,
         message: MessageStruct,
-        timestamp: u64, 
+        timestamp: u64,
+    },
+    /// 私聊消息已发出，回执给发送者
+    #[serde(rename = "private_sent")]
+    PrivateSent {
+        id: String,
+        session_id: String,
+        to: String,
+        timestamp: u64,
+    },
+    /// 私聊消息已被发送者撤回
+    #[serde(rename = "recalled")]
+    Recalled {
+        id: String,
+        session_id: String,
+        from: String,
+    },
+    /// 收到通话呼叫
+    #[serde(rename = "call_offer")]
+    CallOffer {
+        call_id: String,
+        session_id: String,
+        from: String,
+        sdp: String,
+        video: bool,
+    },
+    /// 对方已接听
+    #[serde(rename = "call_answer")]
+    CallAnswer {
+        call_id: String,
+        from: String,
+        sdp: String,
+    },
+    /// 对方的 ICE 候选
+    #[serde(rename = "ice_candidate")]
+    IceCandidate {
+        call_id: String,
+        from: String,
+        candidate: IceCandidateInit,
+    },
+    /// 对方挂断或拒接
+    #[serde(rename = "call_hangup")]
+    CallHangup { call_id: String, from: String },
+    /// 通话状态变化，发送给通话双方
+    #[serde(rename = "call_state")]
+    CallState {
+        call_id: String,
+        session_id: String,
+        state: CallStatus,
     },
     /// 在线用户列表
     #[serde(rename = "list")]
     List { clients: Vec<ClientInfo> },
     /// 系统消息
     #[serde(rename = "system")]
-    System { message: String },
+    System {
+        message: String,
+        /// 房间内的系统通知携带房间ID
+        #[serde(skip_serializing_if = "Option::is_none")]
+        room_id: Option<String>,
+    },
     /// 错误消息
     #[serde(rename = "error")]
     Error { message: String },
@@ -114,10 +328,56 @@ pub enum ServerMessage {
     /// 广播消息
     #[serde(rename = "broadcast")]
     Broadcast {
+        id: u64,
         from: String,
         message: String,
         timestamp: u64,
     },
+    /// 管理员公告，与用户广播区分
+    #[serde(rename = "announcement")]
+    Announcement { message: String, timestamp: u64 },
+    /// 广播历史（按时间正序）
+    #[serde(rename = "broadcast_history")]
+    BroadcastHistory {
+        messages: Vec<BroadcastRecord>,
+        has_more: bool,
+    },
+    /// 房间创建成功
+    #[serde(rename = "room_created")]
+    RoomCreated { room: RoomInfo },
+    /// 自己加入房间成功
+    #[serde(rename = "room_joined")]
+    RoomJoined {
+        room: RoomInfo,
+        members: Vec<String>,
+    },
+    /// 自己已离开房间
+    #[serde(rename = "room_left")]
+    RoomLeft { room_id: String },
+    /// 有成员加入房间
+    #[serde(rename = "room_member_joined")]
+    RoomMemberJoined { room_id: String, client_id: String },
+    /// 有成员离开房间
+    #[serde(rename = "room_member_left")]
+    RoomMemberLeft {
+        room_id: String,
+        client_id: String,
+        new_owner: Option<String>,
+    },
+    /// 房间消息
+    #[serde(rename = "room_message")]
+    RoomMessage {
+        room_id: String,
+        from: String,
+        message: String,
+        timestamp: u64,
+    },
+    /// 房间成员列表
+    #[serde(rename = "room_members")]
+    RoomMembers {
+        room: RoomInfo,
+        members: Vec<String>,
+    },
 }
 
 /// 客户端连接
@@ -126,6 +386,19 @@ struct ClientConnection {
     sender: mpsc::UnboundedSender<String>,
     /// 连接时间
     connected_at: u64,
+    /// 最近一次大厅广播时间
+    last_broadcast: Option<Instant>,
+    /// 建立连接时使用的令牌ID
+    token_id: String,
+    /// 令牌所属的登录会话ID，游客为空
+    login_session: Option<String>,
+}
+
+impl ClientConnection {
+    /// 连接是否使用该令牌或属于该登录会话
+    fn authenticated_by(&self, token: &str) -> bool {
+        self.token_id == token || self.login_session.as_deref() == Some(token)
+    }
 }
 
 /// 连接管理器
@@ -133,36 +406,163 @@ struct ClientConnection {
 pub struct ConnectionManager {
     /// 存储所有连接
     connections: Arc<RwLock<HashMap<String, ClientConnection>>>,
-    /// 等待匹配的用户队列
-    waiting_queue: Arc<RwLock<VecDeque<WaitingUser>>>,
+    /// 等待匹配的用户队列，集群模式下由所有节点共享
+    match_queue: Arc<dyn MatchQueue>,
     /// 存储用户的匹配超时定时器
     match_timers: Arc<RwLock<HashMap<String, JoinHandle<()>>>>,
+    /// 集群总线，未开启集群模式时为空
+    cluster: Option<ClusterBus>,
 }
 
 impl ConnectionManager {
     pub fn new() -> Self {
         Self {
             connections: Arc::new(RwLock::new(HashMap::new())),
-            waiting_queue: Arc::new(RwLock::new(VecDeque::new())),
+            match_queue: Arc::new(MemoryMatchQueue::new()),
             match_timers: Arc::new(RwLock::new(HashMap::new())),
+            cluster: None,
+        }
+    }
+
+    /// 创建集群模式的连接管理器：订阅节点频道并定时刷新节点存活状态
+    pub async fn with_cluster(bus: ClusterBus) -> RedisResult<Self> {
+        let manager = Self {
+            match_queue: Arc::new(RedisMatchQueue::new(bus.connection())),
+            cluster: Some(bus.clone()),
+            ..Self::new()
+        };
+
+        bus.heartbeat().await?;
+        let pubsub = bus.subscribe().await?;
+        tracing::info!("ws 集群节点 {} 已加入", bus.node_id());
+
+        // 处理其他节点投递过来的事件，订阅断开后自动重连
+        tokio::spawn({
+            let manager = manager.clone();
+            let bus = bus.clone();
+            async move {
+                let mut pubsub = Some(pubsub);
+                loop {
+                    let subscription = match pubsub.take() {
+                        Some(pubsub) => Ok(pubsub),
+                        None => bus.subscribe().await,
+                    };
+                    match subscription {
+                        Ok(pubsub) => {
+                            let mut messages = pubsub.into_on_message();
+                            while let Some(msg) = messages.next().await {
+                                match msg.get_payload::<String>() {
+                                    Ok(payload) => manager.handle_cluster_event(&payload).await,
+                                    Err(e) => tracing::warn!("ws 集群消息读取失败: {}", e),
+                                }
+                            }
+                            tracing::warn!("ws 集群订阅已断开，准备重连");
+                        }
+                        Err(e) => tracing::error!("ws 集群订阅失败: {}", e),
+                    }
+                    time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        });
+
+        // 定时刷新节点存活标记
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_secs(NODE_TTL_SECONDS / 3));
+            loop {
+                interval.tick().await;
+                if let Err(e) = bus.heartbeat().await {
+                    tracing::error!("ws 集群心跳失败: {}", e);
+                }
+            }
+        });
+
+        Ok(manager)
+    }
+
+    /// 处理其他节点发来的集群事件
+    async fn handle_cluster_event(&self, payload: &str) {
+        let event = match serde_json::from_str::<ClusterEvent>(payload) {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("ws 集群消息格式错误: {}", e);
+                return;
+            }
+        };
+
+        match event {
+            ClusterEvent::Direct { target, payload } => {
+                let _ = self.send_local(&target, payload).await;
+            }
+            ClusterEvent::Broadcast {
+                origin,
+                exclude,
+                payload,
+            } => {
+                // 本节点发出的广播已在本地投递过
+                if self
+                    .cluster
+                    .as_ref()
+                    .is_some_and(|bus| bus.node_id() == origin)
+                {
+                    return;
+                }
+                self.broadcast_local(exclude.as_deref(), &payload).await;
+            }
+            ClusterEvent::Disconnect {
+                target,
+                token,
+                reason,
+            } => {
+                self.disconnect_local(&target, token.as_deref(), &reason)
+                    .await;
+            }
         }
     }
 
     /// 注册新连接
-    pub async fn register(&self, client_id: String, sender: mpsc::UnboundedSender<String>) -> bool {
+    pub async fn register(
+        &self,
+        client_id: String,
+        sender: mpsc::UnboundedSender<String>,
+        claims: &Claims,
+    ) -> bool {
         let connection = ClientConnection {
             sender,
             connected_at: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            last_broadcast: None,
+            token_id: claims.jti.clone(),
+            login_session: claims.sid.clone(),
         };
+        let connected_at = connection.connected_at;
+
+        if self.connections.read().await.contains_key(&client_id) {
+            return false;
+        }
+
+        // 集群模式下同一客户端不能同时连接多个节点。登记在锁外进行，redis 较慢时不阻塞其他连接；
+        // 登记失败时拒绝连接，否则其他节点找不到该连接，同一客户端可能同时连接多个节点
+        if let Some(bus) = &self.cluster {
+            match bus.register(&client_id, connected_at).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    tracing::info!("客户端 {} 已在其他节点在线", client_id);
+                    return false;
+                }
+                Err(e) => {
+                    tracing::error!("客户端 {} 集群登记失败，拒绝连接: {}", client_id, e);
+                    return false;
+                }
+            }
+        }
 
+        // 登记期间同一客户端已在本节点建立连接时拒绝，集群中的归属记录仍指向本节点，无需回滚
         let mut connections = self.connections.write().await;
         if connections.contains_key(&client_id) {
             return false;
         }
-
         connections.insert(client_id.clone(), connection);
         tracing::info!("客户端 {} 已连接", client_id);
 
@@ -174,44 +574,206 @@ impl ConnectionManager {
         let mut connections = self.connections.write().await;
         if connections.remove(client_id).is_some() {
             tracing::info!("客户端 {} 已断开连接", client_id);
+            drop(connections);
+            self.cluster_unregister(client_id).await;
+        }
+    }
+
+    /// 服务端主动断开连接：先发送错误提示，再移除连接使发送通道关闭
+    pub async fn disconnect(&self, client_id: &str, reason: &str) {
+        self.disconnect_matching(client_id, None, reason).await;
+    }
+
+    /// 断开使用已吊销令牌建立的连接，token 为令牌ID或登录会话ID
+    ///
+    /// 同一客户端重新登录后建立的新连接不受影响
+    pub async fn disconnect_revoked(&self, client_id: &str, token: &str, reason: &str) {
+        self.disconnect_matching(client_id, Some(token), reason)
+            .await;
+    }
+
+    async fn disconnect_matching(&self, client_id: &str, token: Option<&str>, reason: &str) {
+        if self.disconnect_local(client_id, token, reason).await {
+            return;
+        }
+
+        // 客户端在其他节点上
+        if let Some(bus) = &self.cluster {
+            match bus.locate(client_id).await {
+                Ok(Some(node)) => {
+                    if let Err(e) = bus.disconnect(&node, client_id, token, reason).await {
+                        tracing::error!("通知节点 {} 断开客户端 {} 失败: {}", node, client_id, e);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => tracing::error!("查找客户端 {} 所在节点失败: {}", client_id, e),
+            }
+        }
+    }
+
+    /// 断开本节点上的连接，返回连接是否在本节点
+    ///
+    /// 指定 token 时只断开使用该令牌或登录会话建立的连接
+    async fn disconnect_local(&self, client_id: &str, token: Option<&str>, reason: &str) -> bool {
+        let mut connections = self.connections.write().await;
+        let Some(connection) = connections.get(client_id) else {
+            return false;
+        };
+        if let Some(token) = token
+            && !connection.authenticated_by(token)
+        {
+            return true;
+        }
+        let connection = connections.remove(client_id).unwrap();
+        drop(connections);
+
+        let error_msg = serde_json::to_string(&ServerMessage::Error {
+            message: reason.to_string(),
+        })
+        .unwrap_or_else(|_| "{\"type\":\"error\",\"message\":\"消息序列化失败\"}".to_string());
+
+        let _ = connection.sender.send(error_msg);
+        tracing::info!("客户端 {} 被服务端断开: {}", client_id, reason);
+        self.cluster_unregister(client_id).await;
+
+        true
+    }
+
+    async fn cluster_unregister(&self, client_id: &str) {
+        if let Some(bus) = &self.cluster
+            && let Err(e) = bus.unregister(client_id).await
+        {
+            tracing::error!("客户端 {} 集群注销失败: {}", client_id, e);
         }
     }
 
     /// 向指定客户端发送消息
     pub async fn send_to(&self, target_id: &str, message: String) -> Result<(), String> {
+        let message = match self.send_local(target_id, message).await {
+            Ok(()) => return Ok(()),
+            Err(message) => message,
+        };
+
+        // 本节点没有该连接时，通过集群转发给所在节点
+        let Some(bus) = &self.cluster else {
+            return Err(format!("用户 {} 不在线", target_id));
+        };
+        match bus.locate(target_id).await {
+            Ok(Some(node)) => bus
+                .send_direct(&node, target_id, &message)
+                .await
+                .map_err(|e| format!("发送失败: {}", e)),
+            Ok(None) => Err(format!("用户 {} 不在线", target_id)),
+            Err(e) => Err(format!("发送失败: {}", e)),
+        }
+    }
+
+    /// 向本节点上的客户端发送消息，连接不存在时原样返回消息
+    async fn send_local(&self, target_id: &str, message: String) -> Result<(), String> {
         let connections = self.connections.read().await;
 
         match connections.get(target_id) {
-            Some(connection) => connection
-                .sender
-                .send(message)
-                .map_err(|e| format!("发送失败: {}", e)),
-            None => Err(format!("用户 {} 不在线", target_id)),
+            Some(connection) => {
+                if let Err(e) = connection.sender.send(message) {
+                    tracing::warn!("发送给客户端 {} 失败: {}", target_id, e);
+                }
+                Ok(())
+            }
+            None => Err(message),
         }
     }
 
-    /// 广播消息给所有用户（除了发送者）
-    pub async fn broadcast(&self, from: &str, message: &str, timestamp: u64) {
+    /// 向本节点上的所有连接投递消息，返回送达的连接数
+    async fn broadcast_local(&self, exclude: Option<&str>, message: &str) -> usize {
         let connections = self.connections.read().await;
 
-        for (client_id, connection) in connections.iter() {
-            if client_id == from {
-                continue; // 不发送给自己
+        connections
+            .iter()
+            .filter(|(client_id, _)| Some(client_id.as_str()) != exclude)
+            .filter(|(_, connection)| connection.sender.send(message.to_string()).is_ok())
+            .count()
+    }
+
+    /// 广播消息给所有用户（除了发送者）
+    pub async fn broadcast(&self, id: u64, from: &str, message: &str, timestamp: u64) {
+        let broadcast_msg = serde_json::to_string(&ServerMessage::Broadcast {
+            id,
+            from: from.to_string(),
+            message: message.to_string(),
+            timestamp,
+        })
+        .unwrap_or_else(|_| "{\"type\":\"error\",\"message\":\"消息序列化失败\"}".to_string());
+
+        // 不发送给自己
+        self.broadcast_local(Some(from), &broadcast_msg).await;
+
+        if let Some(bus) = &self.cluster
+            && let Err(e) = bus.broadcast(Some(from), &broadcast_msg).await
+        {
+            tracing::error!("ws 集群广播失败: {}", e);
+        }
+    }
+
+    /// 检查大厅广播冷却时间，通过后记录本次广播时间；未冷却时返回剩余秒数
+    pub async fn check_broadcast_cooldown(
+        &self,
+        client_id: &str,
+        cooldown: u64,
+    ) -> Result<(), u64> {
+        if cooldown == 0 {
+            return Ok(());
+        }
+
+        let mut connections = self.connections.write().await;
+        let Some(connection) = connections.get_mut(client_id) else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        if let Some(last) = connection.last_broadcast {
+            let elapsed = now.duration_since(last).as_secs();
+            if elapsed < cooldown {
+                return Err(cooldown - elapsed);
             }
+        }
+        connection.last_broadcast = Some(now);
 
-            let broadcast_msg = serde_json::to_string(&ServerMessage::Broadcast {
-                from: from.to_string(),
-                message: message.to_string(),
-                timestamp,
-            })
-            .unwrap_or_else(|_| "{\"type\":\"error\",\"message\":\"消息序列化失败\"}".to_string());
+        Ok(())
+    }
 
-            let _ = connection.sender.send(broadcast_msg);
+    /// 向所有在线用户发送系统公告，返回送达的连接数
+    pub async fn announce(&self, message: &str, timestamp: u64) -> usize {
+        let announcement_msg = serde_json::to_string(&ServerMessage::Announcement {
+            message: message.to_string(),
+            timestamp,
+        })
+        .unwrap_or_else(|_| "{\"type\":\"error\",\"message\":\"消息序列化失败\"}".to_string());
+
+        let delivered = self.broadcast_local(None, &announcement_msg).await;
+
+        let Some(bus) = &self.cluster else {
+            return delivered;
+        };
+        if let Err(e) = bus.broadcast(None, &announcement_msg).await {
+            tracing::error!("ws 集群公告发送失败: {}", e);
+            return delivered;
         }
+
+        // 其他节点的送达数按其在线连接数估算
+        let local_count = self.connections.read().await.len();
+        let total = self.online_count().await;
+        delivered + total.saturating_sub(local_count)
     }
 
     /// 获取在线用户列表
     pub async fn list_clients(&self) -> Vec<ClientInfo> {
+        if let Some(bus) = &self.cluster {
+            match bus.list_clients().await {
+                Ok(clients) => return clients,
+                Err(e) => tracing::error!("获取集群在线用户失败: {}", e),
+            }
+        }
+
         let connections = self.connections.read().await;
 
         connections
@@ -225,12 +787,26 @@ impl ConnectionManager {
 
     /// 获取在线用户数量
     pub async fn online_count(&self) -> usize {
+        if let Some(bus) = &self.cluster {
+            match bus.online_count().await {
+                Ok(count) => return count,
+                Err(e) => tracing::error!("获取集群在线人数失败: {}", e),
+            }
+        }
+
         self.connections.read().await.len()
     }
 
     /// 检查用户是否在线
     pub async fn is_online(&self, client_id: &str) -> bool {
-        self.connections.read().await.contains_key(client_id)
+        if self.connections.read().await.contains_key(client_id) {
+            return true;
+        }
+
+        match &self.cluster {
+            Some(bus) => matches!(bus.locate(client_id).await, Ok(Some(_))),
+            None => false,
+        }
     }
 
     /// 添加用户到匹配队列
@@ -241,19 +817,8 @@ impl ConnectionManager {
         age_index: u32,
         sex_index: u32,
         location: String,
-    ) {
-        // 先检查用户是否已经在队列中
-        let waiting_queue_read = self.waiting_queue.read().await;
-        let already_in_queue = waiting_queue_read
-            .iter()
-            .any(|user| user.client_id == client_id);
-
-        if already_in_queue {
-            tracing::info!("用户 {} 已经在匹配队列中，无需重复添加", client_id);
-            return;
-        }
-        drop(waiting_queue_read);
-
+        public_key: Option<String>,
+    ) -> Result<(), String> {
         let waiting_user = WaitingUser {
             client_id: client_id.clone(),
             user_key,
@@ -264,43 +829,38 @@ impl ConnectionManager {
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            public_key,
         };
 
-        let mut waiting_queue = self.waiting_queue.write().await;
-        waiting_queue.push_back(waiting_user);
+        if !self.match_queue.join(waiting_user).await? {
+            tracing::info!("用户 {} 已经在匹配队列中，无需重复添加", client_id);
+            return Ok(());
+        }
         tracing::info!("用户 {} 加入匹配队列", client_id);
-        drop(waiting_queue);
 
-        // 设置10秒超时定时器
+        // 设置超时定时器，定时器只在用户所在节点上运行
         let manager_clone = self.clone();
         let client_id_clone = client_id.clone();
 
         let timer = tokio::spawn(async move {
-            time::sleep(Duration::from_secs(10)).await;
-
-            // 检查用户是否还在队列中
-            let waiting_queue_read = manager_clone.waiting_queue.read().await;
-            let still_waiting = waiting_queue_read
-                .iter()
-                .any(|user| user.client_id == client_id_clone);
-            drop(waiting_queue_read);
-
-            if still_waiting {
-                // 从队列中移除用户
-                manager_clone
-                    .remove_from_waiting_queue(&client_id_clone)
-                    .await;
+            time::sleep(Duration::from_secs(MATCH_TIMEOUT_SECONDS)).await;
 
-                // 发送超时消息给用户
-                let timeout_msg = serde_json::to_string(&ServerMessage::MeetFailed {
-                    message: "匹配超时，请重试".to_string(),
-                })
-                .unwrap_or_else(|_| {
-                    "{\"type\":\"error\",\"message\":\"消息序列化失败\"}".to_string()
-                });
+            // 用户仍在队列中才算超时，已被配对取走时移除会失败
+            match manager_clone.match_queue.remove(&client_id_clone).await {
+                Ok(true) => {
+                    // 发送超时消息给用户
+                    let timeout_msg = serde_json::to_string(&ServerMessage::MeetFailed {
+                        message: "匹配超时，请重试".to_string(),
+                    })
+                    .unwrap_or_else(|_| {
+                        "{\"type\":\"error\",\"message\":\"消息序列化失败\"}".to_string()
+                    });
 
-                let _ = manager_clone.send_to(&client_id_clone, timeout_msg).await;
-                tracing::info!("用户 {} 匹配超时，已退出匹配", client_id_clone);
+                    let _ = manager_clone.send_to(&client_id_clone, timeout_msg).await;
+                    tracing::info!("用户 {} 匹配超时，已退出匹配", client_id_clone);
+                }
+                Ok(false) => {}
+                Err(e) => tracing::error!("用户 {} 匹配超时处理失败: {}", client_id_clone, e),
             }
 
             // 移除定时器
@@ -308,69 +868,49 @@ impl ConnectionManager {
             timers.remove(&client_id_clone);
         });
 
-        // 存储定时器
+        // 存储定时器，替换掉上一次匹配遗留的定时器
         let mut timers = self.match_timers.write().await;
-        timers.insert(client_id.clone(), timer);
+        if let Some(previous) = timers.insert(client_id, timer) {
+            previous.abort();
+        }
+
+        Ok(())
     }
 
     /// 从匹配队列中移除用户
     pub async fn remove_from_waiting_queue(&self, client_id: &str) {
-        let mut waiting_queue = self.waiting_queue.write().await;
-        waiting_queue.retain(|user| user.client_id != client_id);
-        tracing::info!("用户 {} 从匹配队列中移除", client_id);
+        if let Some(timer) = self.match_timers.write().await.remove(client_id) {
+            timer.abort();
+        }
+
+        match self.match_queue.remove(client_id).await {
+            Ok(_) => tracing::info!("用户 {} 从匹配队列中移除", client_id),
+            Err(e) => tracing::error!("用户 {} 移出匹配队列失败: {}", client_id, e),
+        }
     }
 
     /// 执行匹配算法
     pub async fn match_users(&self) -> Option<(WaitingUser, WaitingUser)> {
-        let mut waiting_queue = self.waiting_queue.write().await;
-        if waiting_queue.len() < 2 {
-            return None;
-        }
-
-        // 优先匹配不同性别的用户
-        let mut matched_indices = None;
-
-        // 遍历队列，寻找不同性别的匹配
-        for (i, user1) in waiting_queue.iter().enumerate() {
-            for (j, user2) in waiting_queue.iter().enumerate() {
-                if i < j && user1.sex_index != user2.sex_index {
-                    matched_indices = Some((i, j));
-                    break;
-                }
-            }
-            if matched_indices.is_some() {
-                break;
+        let (user1, user2) = match self.match_queue.pop_pair().await {
+            Ok(pair) => pair?,
+            Err(e) => {
+                tracing::error!("{}", e);
+                return None;
             }
-        }
+        };
 
-        // 如果没有找到不同性别的匹配，就匹配最先加入队列的两个用户
-        if matched_indices.is_none() {
-            matched_indices = Some((0, 1));
+        // 取消两个用户的匹配定时器（在其他节点上的定时器触发时会发现用户已不在队列中）
+        let mut timers = self.match_timers.write().await;
+        if let Some(timer) = timers.remove(&user1.client_id) {
+            timer.abort();
         }
-
-        if let Some((i, j)) = matched_indices {
-            // 确保i < j
-            let (first_idx, second_idx) = if i < j { (i, j) } else { (j, i) };
-
-            // 获取匹配的用户
-            let user2 = waiting_queue.remove(second_idx).unwrap();
-            let user1 = waiting_queue.remove(first_idx).unwrap();
-
-            // 取消两个用户的匹配定时器
-            let mut timers = self.match_timers.write().await;
-            if let Some(timer) = timers.remove(&user1.client_id) {
-                timer.abort();
-            }
-            if let Some(timer) = timers.remove(&user2.client_id) {
-                timer.abort();
-            }
-            drop(timers);
-
-            tracing::info!("成功匹配用户 {} 和 {}", user1.client_id, user2.client_id);
-            return Some((user1, user2));
+        if let Some(timer) = timers.remove(&user2.client_id) {
+            timer.abort();
         }
+        drop(timers);
 
-        None
+        tracing::info!("成功匹配用户 {} 和 {}", user1.client_id, user2.client_id);
+        Some((user1, user2))
     }
 
     /// 发送匹配结果给双方用户
@@ -378,6 +918,7 @@ impl ConnectionManager {
         &self,
         user1: &WaitingUser,
         user2: &WaitingUser,
+        session_id: &str,
     ) -> Result<(), String> {
         // let timestamp = SystemTime::now()
         //     .duration_since(UNIX_EPOCH)
@@ -387,12 +928,14 @@ impl ConnectionManager {
         // 给用户1发送匹配成功消息
         let user1_msg = serde_json::to_string(&ServerMessage::Meet {
             user_key: user2.user_key.clone(),
+            to: user2.client_id.clone(),
         })
         .map_err(|e| format!("序列化失败: {}", e))?;
 
         // 给用户2发送匹配成功消息
         let user2_msg = serde_json::to_string(&ServerMessage::Meet {
             user_key: user1.user_key.clone(),
+            to: user1.client_id.clone(),
         })
         .map_err(|e| format!("序列化失败: {}", e))?;
 
@@ -400,22 +943,29 @@ impl ConnectionManager {
         let _ = self.send_to(&user1.client_id, user1_msg).await;
         let _ = self.send_to(&user2.client_id, user2_msg).await;
 
-        // 发送系统消息提示匹配成功
+        // 发送系统消息提示匹配成功，同时交换双方的公钥
+        let e2e = user1.public_key.is_some() && user2.public_key.is_some();
         let system_msg1 = serde_json::to_string(&ServerMessage::MeetSuccess {
-            to: user2.user_key.clone(),
+            to: user2.client_id.clone(),
+            session_id: session_id.to_string(),
             age: user2.age_index,
             sex: user2.sex_index,
             location: user2.location.clone(),
             message: "匹配成功，开始聊天吧！".to_string(),
+            public_key: user2.public_key.clone(),
+            e2e,
         })
         .map_err(|e| format!("序列化失败: {}", e))?;
 
         let system_msg2 = serde_json::to_string(&ServerMessage::MeetSuccess {
-            to: user1.user_key.clone(),
+            to: user1.client_id.clone(),
+            session_id: session_id.to_string(),
             age: user1.age_index,
             sex: user1.sex_index,
             location: user1.location.clone(),
             message: "匹配成功，开始聊天吧！".to_string(),
+            public_key: user1.public_key.clone(),
+            e2e,
         })
         .map_err(|e| format!("序列化失败: {}", e))?;
 
@@ -425,3 +975,74 @@ impl ConnectionManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::auth::token::TokenKind;
+
+    async fn connect(
+        connections: &ConnectionManager,
+        client_id: &str,
+        jti: &str,
+        sid: Option<&str>,
+    ) -> mpsc::UnboundedReceiver<String> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let claims = Claims {
+            sub: client_id.to_string(),
+            kind: TokenKind::Access,
+            jti: jti.to_string(),
+            sid: sid.map(str::to_string),
+            ip: None,
+            iss: "test".to_string(),
+            iat: 0,
+            exp: u64::MAX,
+        };
+        assert!(
+            connections
+                .register(client_id.to_string(), tx, &claims)
+                .await
+        );
+        rx
+    }
+
+    #[tokio::test]
+    async fn disconnect_revoked_matches_token_or_session() {
+        let connections = ConnectionManager::new();
+        let mut by_token = connect(&connections, "1", "jti-1", Some("sid-1")).await;
+        let mut by_session = connect(&connections, "2", "jti-2", Some("sid-2")).await;
+
+        // 其他令牌或会话被吊销时不影响现有连接（如重新登录后吊销旧会话）
+        connections
+            .disconnect_revoked("1", "jti-old", "已吊销")
+            .await;
+        connections
+            .disconnect_revoked("2", "sid-old", "已吊销")
+            .await;
+        connections.disconnect_revoked("1", "sid-2", "已吊销").await;
+        assert!(connections.is_online("1").await);
+        assert!(connections.is_online("2").await);
+        assert!(by_token.try_recv().is_err());
+        assert!(by_session.try_recv().is_err());
+
+        connections.disconnect_revoked("1", "jti-1", "已吊销").await;
+        connections.disconnect_revoked("2", "sid-2", "已吊销").await;
+        for rx in [&mut by_token, &mut by_session] {
+            let error: serde_json::Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+            assert_eq!(error["type"], "error");
+            assert_eq!(error["data"]["message"], "已吊销");
+        }
+        assert!(!connections.is_online("1").await);
+        assert!(!connections.is_online("2").await);
+    }
+
+    #[tokio::test]
+    async fn disconnect_ignores_the_token() {
+        let connections = ConnectionManager::new();
+        let mut rx = connect(&connections, "1", "jti-1", None).await;
+
+        connections.disconnect("1", "已封禁").await;
+        assert!(rx.try_recv().is_ok());
+        assert!(!connections.is_online("1").await);
+    }
+}
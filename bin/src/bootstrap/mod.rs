@@ -1,6 +1,7 @@
 use app::route;
 use axum::{Router, http::Method};
 use kernel::{
+    auth::token::TokenService,
     config::{AppConfig, database_config, redis_config, server_config},
     tasks::manager::SchedulerManager,
 };
@@ -20,6 +21,18 @@ pub async fn make() -> anyhow::Result<(Router, TcpListener, SchedulerManager)> {
         process::exit(1);
     };
 
+    // 初始化令牌服务，集群或正式环境未配置签名密钥时拒绝启动
+    if let Err(e) = TokenService::init() {
+        eprintln!("❌ Failed to initialize token service: {}", e);
+        process::exit(1);
+    };
+
+    // 初始化邮件发送器，选择了 SMTP 但配置无效时拒绝启动
+    if let Err(e) = kernel::mail::init() {
+        eprintln!("❌ Failed to initialize mailer: {}", e);
+        process::exit(1);
+    };
+
     // 构建应用
     let (make_service, listener) = build_application().await?;
 
@@ -62,7 +75,7 @@ pub async fn make() -> anyhow::Result<(Router, TcpListener, SchedulerManager)> {
 async fn build_application() -> anyhow::Result<(Router, TcpListener)> {
     let config = server_config();
 
-    let app = route::build_router();
+    let app = route::build_router().await;
     let app = match &config.content_gzip {
         true => {
             //  开启压缩后 SSE 数据无法返回  text/event-stream 单独处理不压缩
@@ -1,4 +1,5 @@
 use bootstrap::logger;
+use std::net::SocketAddr;
 
 mod bootstrap;
 
@@ -10,6 +11,9 @@ async fn main() -> anyhow::Result<()> {
     // 日志服务初始化(接收)
     let _logger = logger::Logger::init();
 
+    // 记录连接的对端地址，用于按IP限流
+    let make_service = make_service.into_make_service_with_connect_info::<SocketAddr>();
+
     tokio::select! {
         server_result =  axum::serve(listener, make_service) => {
             if let Err(e) = server_result {
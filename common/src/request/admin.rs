@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct AnnounceRequest {
+    #[validate(length(min = 1, max = 500, message = "公告内容长度必须在1到500个字符之间"))]
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct ReportsQuery {
+    /// 处理状态：pending / resolved / dismissed，为空时返回全部
+    pub status: Option<String>,
+    /// 只返回ID小于 before 的举报，用于向后翻页
+    pub before: Option<i32>,
+    #[validate(range(min = 1, max = 100, message = "limit 必须在1到100之间"))]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct HandleReportRequest {
+    /// 处理结果：resolved / dismissed
+    pub status: String,
+
+    #[validate(length(max = 500, message = "处理备注不能超过500个字符"))]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct BansQuery {
+    /// 只返回ID小于 before 的封禁，用于向后翻页
+    pub before: Option<i32>,
+    #[validate(range(min = 1, max = 100, message = "limit 必须在1到100之间"))]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct BanRequest {
+    /// 被封禁的客户端ID（登录用户为用户ID）
+    #[validate(length(min = 1, max = 128, message = "封禁对象无效"))]
+    pub target: String,
+
+    #[validate(length(min = 1, max = 200, message = "封禁原因长度必须在1到200个字符之间"))]
+    pub reason: String,
+
+    /// 封禁时长（秒），为空时永久封禁
+    #[validate(range(min = 60, max = 315360000, message = "封禁时长必须在60秒到10年之间"))]
+    pub duration: Option<u64>,
+}
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct UploadQuery {
+    #[validate(length(min = 1, message = "session_id 不能为空"))]
+    pub session_id: String,
+}
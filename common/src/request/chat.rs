@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct TranscriptQuery {
+    /// 只返回ID小于 before 的消息，用于向前翻页
+    pub before: Option<i64>,
+    #[validate(range(min = 1, max = 100, message = "limit 必须在1到100之间"))]
+    pub limit: Option<u64>,
+}
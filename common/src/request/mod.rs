@@ -1,2 +1,6 @@
+pub mod admin;
+pub mod attachment;
+pub mod chat;
+pub mod report;
 pub mod system;
 pub mod websocket;
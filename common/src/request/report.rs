@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct ReportRequest {
+    /// 被举报人的客户端ID
+    #[validate(length(min = 1, max = 128, message = "被举报人无效"))]
+    pub target: String,
+
+    /// 发生问题的私聊会话ID
+    #[validate(length(min = 1, max = 64, message = "会话ID无效"))]
+    pub session_id: Option<String>,
+
+    #[validate(length(min = 1, max = 32, message = "举报原因长度必须在1到32个字符之间"))]
+    pub reason: String,
+
+    #[validate(length(max = 500, message = "举报说明不能超过500个字符"))]
+    pub detail: Option<String>,
+}
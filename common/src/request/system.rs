@@ -6,9 +6,54 @@ pub struct LoginRequest {
     #[validate(email(message = "无效的邮箱地址"))]
     pub email: String,
 
-    #[validate(length(min = 6, message = "密码必须至少包含6个字符"))]
+    #[validate(length(min = 6, max = 128, message = "密码长度必须在6到128个字符之间"))]
     pub password: String,
 
     #[validate()]
     pub remember_me: Option<bool>,
-}
\ No newline at end of file
+
+    /// 设备名称，显示在登录会话列表中，为空时根据 User-Agent 推断
+    #[validate(length(min = 1, max = 64, message = "设备名称长度必须在1到64个字符之间"))]
+    pub device: Option<String>,
+}
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct RefreshTokenRequest {
+    #[validate(length(min = 1, message = "刷新令牌不能为空"))]
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct RegisterRequest {
+    #[validate(email(message = "无效的邮箱地址"))]
+    pub email: String,
+
+    #[validate(length(min = 8, max = 128, message = "密码长度必须在8到128个字符之间"))]
+    pub password: String,
+
+    #[validate(length(min = 1, max = 32, message = "昵称长度必须在1到32个字符之间"))]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct VerifyEmailRequest {
+    #[validate(email(message = "无效的邮箱地址"))]
+    pub email: String,
+
+    #[validate(length(equal = 6, message = "验证码为6位数字"))]
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email(message = "无效的邮箱地址"))]
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, max = 128, message = "重置令牌无效"))]
+    pub token: String,
+
+    #[validate(length(min = 8, max = 128, message = "密码长度必须在8到128个字符之间"))]
+    pub password: String,
+}
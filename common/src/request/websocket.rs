@@ -2,5 +2,6 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug, Serialize)]
 pub struct WsRequestParams {
-    pub key: String
-}
\ No newline at end of file
+    /// 访问令牌或游客令牌，客户端ID取自令牌
+    pub token: String,
+}
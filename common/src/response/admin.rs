@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ConnectionItem {
+    /// 客户端ID
+    pub id: String,
+    /// 连接时间（秒级时间戳）
+    pub connected_at: u64,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ConnectionsResponse {
+    pub online_count: usize,
+    pub clients: Vec<ConnectionItem>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct AnnounceResponse {
+    /// 送达的连接数
+    pub delivered: usize,
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ReportItem {
+    /// 举报ID，翻页时作为 before 参数
+    pub id: i32,
+    pub reporter: String,
+    pub target: String,
+    pub session_id: Option<String>,
+    pub reason: String,
+    pub detail: Option<String>,
+    /// pending / resolved / dismissed
+    pub status: String,
+    pub handled_by: Option<i32>,
+    pub handled_at: Option<i64>,
+    pub note: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ReportsResponse {
+    /// 最新的在前
+    pub reports: Vec<ReportItem>,
+    pub has_more: bool,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct BanItem {
+    /// 封禁ID，翻页时作为 before 参数
+    pub id: i32,
+    pub target: String,
+    /// 同时封禁的IP（封禁游客时为其领取身份时的IP）
+    pub ip: Option<String>,
+    pub reason: String,
+    pub created_by: i32,
+    /// 到期时间（秒级时间戳），为空时永久封禁
+    pub expires_at: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct BansResponse {
+    /// 最新的在前
+    pub bans: Vec<BanItem>,
+    pub has_more: bool,
+}
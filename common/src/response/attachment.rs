@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct UploadResponse {
+    /// 签名的附件ID，在消息中引用
+    pub file_id: String,
+    pub mime: String,
+    pub size: usize,
+    /// 附件ID过期时间（秒级时间戳）
+    pub expires_at: u64,
+    /// 图片的缩略图ID（JPEG），非图片时为空
+    pub thumbnail_id: Option<String>,
+    /// 图片摆正方向后的宽高
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
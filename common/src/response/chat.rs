@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ChatMessageItem {
+    /// 自增ID，翻页时作为 before 参数
+    pub seq: i64,
+    /// 服务端分配的消息ID
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub r#type: i32,
+    pub text: String,
+    pub timestamp: i64,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct TranscriptResponse {
+    pub session_id: String,
+    pub participants: Vec<String>,
+    /// 按时间正序
+    pub messages: Vec<ChatMessageItem>,
+    pub has_more: bool,
+}
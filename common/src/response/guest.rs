@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct GuestResponse {
+    /// 游客ID，即 websocket 连接的客户端ID
+    pub id: String,
+    /// 随机昵称
+    pub nickname: String,
+    /// 头像种子，客户端据此生成默认头像
+    pub avatar_seed: String,
+    /// 游客令牌，连接 websocket 时通过 token 参数携带
+    pub token: String,
+    pub token_type: String,
+    /// 令牌过期时间（秒级时间戳）
+    pub expires_at: u64,
+}
@@ -2,7 +2,14 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug, Serialize)]
 pub struct LoginResponse {
+    pub user_id: i32,
+    /// 访问令牌，请求接口和连接 websocket 时携带
     pub token: String,
     pub token_type: String,
-    pub message: String,
-}
\ No newline at end of file
+    /// 访问令牌过期时间（秒级时间戳）
+    pub expires_at: u64,
+    /// 刷新令牌，访问令牌过期后用于换取新令牌
+    pub refresh_token: String,
+    /// 刷新令牌过期时间（秒级时间戳）
+    pub refresh_expires_at: u64,
+}
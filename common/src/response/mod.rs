@@ -1 +1,6 @@
+pub mod admin;
+pub mod attachment;
+pub mod chat;
+pub mod guest;
 pub mod login;
+pub mod session;
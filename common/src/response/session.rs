@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct SessionItem {
+    /// 登录会话ID
+    pub id: String,
+    pub device: String,
+    pub user_agent: String,
+    /// 最近使用的IP
+    pub ip: String,
+    /// 登录时间（秒级时间戳）
+    pub created_at: i64,
+    /// 最近活跃时间（秒级时间戳）
+    pub last_seen_at: i64,
+    /// 过期时间（秒级时间戳）
+    pub expires_at: i64,
+    /// 是否为发起请求的会话
+    pub current: bool,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct SessionsResponse {
+    pub sessions: Vec<SessionItem>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct RevokeSessionsResponse {
+    /// 被吊销的会话数
+    pub revoked: usize,
+}
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS `chat_message` (
+    `id` BIGINT NOT NULL AUTO_INCREMENT,
+    `message_id` VARCHAR(64) NOT NULL COMMENT '服务端分配的消息ID',
+    `session_id` VARCHAR(64) NOT NULL COMMENT '会话ID',
+    `sender` VARCHAR(128) NOT NULL COMMENT '发送者',
+    `receiver` VARCHAR(128) NOT NULL COMMENT '接收者',
+    `message_type` INT NOT NULL DEFAULT 0 COMMENT '消息类型',
+    `text` MEDIUMTEXT NOT NULL COMMENT '消息内容',
+    `burn_after_read` TINYINT(1) NOT NULL DEFAULT 0 COMMENT '是否阅后即焚',
+    `timestamp` BIGINT NOT NULL COMMENT '发送时间（秒）',
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '入库时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_message_id` (`message_id`),
+    KEY `idx_session_id` (`session_id`, `id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '私聊消息';
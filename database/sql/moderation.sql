@@ -0,0 +1,31 @@
+CREATE TABLE IF NOT EXISTS `sys_report` (
+    `id` INT NOT NULL AUTO_INCREMENT,
+    `reporter` VARCHAR(128) NOT NULL COMMENT '举报人（客户端ID）',
+    `target` VARCHAR(128) NOT NULL COMMENT '被举报人（客户端ID）',
+    `session_id` VARCHAR(64) NULL DEFAULT NULL COMMENT '发生举报的一对一会话ID',
+    `reason` VARCHAR(32) NOT NULL COMMENT '举报原因',
+    `detail` VARCHAR(500) NULL DEFAULT NULL COMMENT '补充说明',
+    `status` INT NOT NULL DEFAULT 0 COMMENT '状态：0 待处理，1 已处理，2 已驳回',
+    `handled_by` INT NULL DEFAULT NULL COMMENT '处理人（用户ID）',
+    `handled_at` BIGINT NULL DEFAULT NULL COMMENT '处理时间（秒级时间戳）',
+    `note` VARCHAR(500) NULL DEFAULT NULL COMMENT '处理备注',
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '举报时间',
+    PRIMARY KEY (`id`),
+    KEY `idx_status` (`status`, `id`),
+    KEY `idx_target` (`target`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '用户举报';
+
+CREATE TABLE IF NOT EXISTS `sys_ban` (
+    `id` INT NOT NULL AUTO_INCREMENT,
+    `target` VARCHAR(128) NOT NULL COMMENT '被封禁的客户端ID（登录用户为用户ID）',
+    `ip` VARCHAR(45) NULL DEFAULT NULL COMMENT '同时封禁的IP（封禁游客时为其领取身份时的IP）',
+    `reason` VARCHAR(200) NOT NULL COMMENT '封禁原因',
+    `created_by` INT NOT NULL COMMENT '操作人（用户ID）',
+    `expires_at` BIGINT NULL DEFAULT NULL COMMENT '到期时间（秒级时间戳），为空时永久封禁',
+    `lifted_at` BIGINT NULL DEFAULT NULL COMMENT '解封时间（秒级时间戳）',
+    `lifted_by` INT NULL DEFAULT NULL COMMENT '解封人（用户ID）',
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '封禁时间',
+    PRIMARY KEY (`id`),
+    KEY `idx_target` (`target`),
+    KEY `idx_ip` (`ip`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '封禁记录';
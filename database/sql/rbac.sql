@@ -0,0 +1,46 @@
+CREATE TABLE IF NOT EXISTS `sys_role` (
+    `id` INT NOT NULL AUTO_INCREMENT,
+    `code` VARCHAR(64) NOT NULL COMMENT '角色编码',
+    `name` VARCHAR(64) NOT NULL COMMENT '角色名称',
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_code` (`code`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '角色';
+
+CREATE TABLE IF NOT EXISTS `sys_permission` (
+    `id` INT NOT NULL AUTO_INCREMENT,
+    `code` VARCHAR(64) NOT NULL COMMENT '权限编码，如 connections:read',
+    `name` VARCHAR(64) NOT NULL COMMENT '权限名称',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_code` (`code`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '权限';
+
+CREATE TABLE IF NOT EXISTS `sys_user_role` (
+    `user_id` INT NOT NULL COMMENT '用户ID',
+    `role_id` INT NOT NULL COMMENT '角色ID',
+    PRIMARY KEY (`user_id`, `role_id`),
+    KEY `idx_role_id` (`role_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '用户角色';
+
+CREATE TABLE IF NOT EXISTS `sys_role_permission` (
+    `role_id` INT NOT NULL COMMENT '角色ID',
+    `permission_id` INT NOT NULL COMMENT '权限ID',
+    PRIMARY KEY (`role_id`, `permission_id`),
+    KEY `idx_permission_id` (`permission_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '角色权限';
+
+-- 内置权限和管理员角色，给用户分配管理员角色：
+-- INSERT INTO `sys_user_role` (`user_id`, `role_id`) SELECT 用户ID, `id` FROM `sys_role` WHERE `code` = 'admin';
+INSERT IGNORE INTO `sys_permission` (`code`, `name`) VALUES
+    ('connections:read', '查看在线连接'),
+    ('connections:kick', '断开在线连接'),
+    ('announcements:send', '发送系统公告'),
+    ('reports:read', '查看举报'),
+    ('reports:handle', '处理举报'),
+    ('bans:read', '查看封禁'),
+    ('bans:manage', '封禁和解封用户');
+
+INSERT IGNORE INTO `sys_role` (`code`, `name`) VALUES ('admin', '管理员');
+
+INSERT IGNORE INTO `sys_role_permission` (`role_id`, `permission_id`)
+SELECT r.`id`, p.`id` FROM `sys_role` r, `sys_permission` p WHERE r.`code` = 'admin';
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS `sys_user` (
+    `id` INT NOT NULL AUTO_INCREMENT,
+    `name` VARCHAR(64) NULL DEFAULT NULL COMMENT '昵称',
+    `email` VARCHAR(255) NOT NULL COMMENT '登录邮箱（小写）',
+    `password_hash` VARCHAR(255) NOT NULL COMMENT 'argon2 密码哈希',
+    `status` INT NOT NULL DEFAULT 0 COMMENT '状态：0 待验证 1 正常 2 禁用',
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_email` (`email`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '用户';
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS `sys_user_session` (
+    `id` VARCHAR(36) NOT NULL COMMENT '登录会话ID（令牌中的 sid）',
+    `user_id` INT NOT NULL COMMENT '用户ID',
+    `device` VARCHAR(64) NOT NULL COMMENT '设备名称',
+    `user_agent` VARCHAR(512) NOT NULL DEFAULT '' COMMENT 'User-Agent',
+    `ip` VARCHAR(45) NOT NULL COMMENT '最近使用的IP',
+    `last_seen_at` BIGINT NOT NULL COMMENT '最近活跃时间（秒级时间戳）',
+    `expires_at` BIGINT NOT NULL COMMENT '过期时间（秒级时间戳）',
+    `revoked_at` BIGINT NULL DEFAULT NULL COMMENT '吊销时间（秒级时间戳）',
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '登录时间',
+    PRIMARY KEY (`id`),
+    KEY `idx_user_id` (`user_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '用户登录会话';
@@ -2,5 +2,13 @@
 
 pub mod prelude;
 
+pub mod chat_message;
+pub mod sys_ban;
 pub mod sys_order;
+pub mod sys_permission;
+pub mod sys_report;
+pub mod sys_role;
+pub mod sys_role_permission;
 pub mod sys_user;
+pub mod sys_user_role;
+pub mod sys_user_session;
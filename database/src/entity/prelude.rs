@@ -1,4 +1,12 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+pub use super::chat_message::Entity as ChatMessage;
+pub use super::sys_ban::Entity as SysBan;
 pub use super::sys_order::Entity as SysOrder;
+pub use super::sys_permission::Entity as SysPermission;
+pub use super::sys_report::Entity as SysReport;
+pub use super::sys_role::Entity as SysRole;
+pub use super::sys_role_permission::Entity as SysRolePermission;
 pub use super::sys_user::Entity as SysUser;
+pub use super::sys_user_role::Entity as SysUserRole;
+pub use super::sys_user_session::Entity as SysUserSession;
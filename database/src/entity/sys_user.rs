@@ -3,12 +3,35 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
+/// 账号状态
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum UserStatus {
+    /// 已注册，等待验证邮箱
+    #[sea_orm(num_value = 0)]
+    Pending,
+    /// 正常
+    #[sea_orm(num_value = 1)]
+    Active,
+    /// 已禁用
+    #[sea_orm(num_value = 2)]
+    Disabled,
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "sys_user")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub name: Option<String>,
+    #[sea_orm(unique)]
+    pub email: String,
+    /// argon2 密码哈希（PHC 格式）
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    pub status: UserStatus,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
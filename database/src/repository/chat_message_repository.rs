@@ -0,0 +1,79 @@
+use anyhow::Result;
+use sea_orm::{ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+
+use crate::entity::chat_message;
+use crate::get_db_unwrap;
+
+/// 待入库的私聊消息
+#[derive(Debug, Clone)]
+pub struct NewChatMessage {
+    pub message_id: String,
+    pub session_id: String,
+    pub sender: String,
+    pub receiver: String,
+    pub message_type: i32,
+    pub text: String,
+    pub burn_after_read: bool,
+    pub timestamp: i64,
+}
+
+/// 批量写入消息
+pub async fn insert_many(messages: Vec<NewChatMessage>) -> Result<()> {
+    if messages.is_empty() {
+        return Ok(());
+    }
+
+    let db = get_db_unwrap();
+    let models = messages
+        .into_iter()
+        .map(|message| chat_message::ActiveModel {
+            id: ActiveValue::NotSet,
+            message_id: ActiveValue::Set(message.message_id),
+            session_id: ActiveValue::Set(message.session_id),
+            sender: ActiveValue::Set(message.sender),
+            receiver: ActiveValue::Set(message.receiver),
+            message_type: ActiveValue::Set(message.message_type),
+            text: ActiveValue::Set(message.text),
+            burn_after_read: ActiveValue::Set(message.burn_after_read),
+            timestamp: ActiveValue::Set(message.timestamp),
+            created_at: ActiveValue::NotSet,
+        });
+
+    chat_message::Entity::insert_many(models).exec(db).await?;
+    Ok(())
+}
+
+/// 按消息ID删除消息
+pub async fn delete_by_message_id(message_id: &str) -> Result<u64> {
+    let db = get_db_unwrap();
+    let res = chat_message::Entity::delete_many()
+        .filter(chat_message::Column::MessageId.eq(message_id))
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected)
+}
+
+/// 分页获取会话消息：返回自增ID小于 before 的最近 limit 条（按时间正序）
+pub async fn find_by_session(
+    session_id: &str,
+    before: Option<i64>,
+    limit: u64,
+) -> Result<Vec<chat_message::Model>> {
+    let db = get_db_unwrap();
+
+    let mut query =
+        chat_message::Entity::find().filter(chat_message::Column::SessionId.eq(session_id));
+    if let Some(before) = before {
+        query = query.filter(chat_message::Column::Id.lt(before));
+    }
+
+    let mut messages = query
+        .order_by_desc(chat_message::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await?;
+    messages.reverse();
+
+    Ok(messages)
+}
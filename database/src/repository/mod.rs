@@ -1 +1,6 @@
+pub mod chat_message_repository;
+pub mod permission_repository;
+pub mod sys_ban_repository;
+pub mod sys_report_repository;
 pub mod sys_user_repository;
+pub mod sys_user_session_repository;
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect};
+
+use crate::entity::{sys_permission, sys_role_permission, sys_user_role};
+use crate::get_db_unwrap;
+
+/// 查询用户通过角色获得的所有权限编码
+pub async fn find_codes_by_user(user_id: i32) -> Result<Vec<String>> {
+    let db = get_db_unwrap();
+
+    let role_ids: Vec<i32> = sys_user_role::Entity::find()
+        .select_only()
+        .column(sys_user_role::Column::RoleId)
+        .filter(sys_user_role::Column::UserId.eq(user_id))
+        .into_tuple()
+        .all(db)
+        .await?;
+    if role_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let permission_ids: Vec<i32> = sys_role_permission::Entity::find()
+        .select_only()
+        .column(sys_role_permission::Column::PermissionId)
+        .filter(sys_role_permission::Column::RoleId.is_in(role_ids))
+        .distinct()
+        .into_tuple()
+        .all(db)
+        .await?;
+    if permission_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let codes: Vec<String> = sys_permission::Entity::find()
+        .select_only()
+        .column(sys_permission::Column::Code)
+        .filter(sys_permission::Column::Id.is_in(permission_ids))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    Ok(codes)
+}
+
+/// 用户是否拥有指定权限
+pub async fn user_has_permission(user_id: i32, code: &str) -> Result<bool> {
+    let codes = find_codes_by_user(user_id).await?;
+    Ok(codes.iter().any(|c| c == code))
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+
+use crate::entity::sys_ban::{self, ActiveModel};
+use crate::get_db_unwrap;
+
+/// 待记录的封禁
+#[derive(Debug, Clone)]
+pub struct NewBan {
+    pub target: String,
+    pub ip: Option<String>,
+    pub reason: String,
+    pub created_by: i32,
+    pub expires_at: Option<i64>,
+}
+
+/// 未解封且未到期的封禁
+fn active(now: i64) -> Condition {
+    Condition::all()
+        .add(sys_ban::Column::LiftedAt.is_null())
+        .add(
+            Condition::any()
+                .add(sys_ban::Column::ExpiresAt.is_null())
+                .add(sys_ban::Column::ExpiresAt.gt(now)),
+        )
+}
+
+/// 记录封禁
+pub async fn create(ban: NewBan) -> Result<sys_ban::Model> {
+    let db = get_db_unwrap();
+    let model = ActiveModel {
+        id: ActiveValue::NotSet,
+        target: ActiveValue::Set(ban.target),
+        ip: ActiveValue::Set(ban.ip),
+        reason: ActiveValue::Set(ban.reason),
+        created_by: ActiveValue::Set(ban.created_by),
+        expires_at: ActiveValue::Set(ban.expires_at),
+        lifted_at: ActiveValue::Set(None),
+        lifted_by: ActiveValue::Set(None),
+        created_at: ActiveValue::NotSet,
+    }
+    .insert(db)
+    .await?;
+
+    Ok(model)
+}
+
+/// 查找客户端当前生效的封禁，指定 ip 时同时查找封禁了该IP的记录；有多条时返回到期最晚的一条（永久封禁优先）
+pub async fn find_active(
+    target: &str,
+    ip: Option<&str>,
+    now: i64,
+) -> Result<Option<sys_ban::Model>> {
+    let db = get_db_unwrap();
+    let mut matches = Condition::any().add(sys_ban::Column::Target.eq(target));
+    if let Some(ip) = ip {
+        matches = matches.add(sys_ban::Column::Ip.eq(ip));
+    }
+    let bans = sys_ban::Entity::find()
+        .filter(matches)
+        .filter(active(now))
+        .all(db)
+        .await?;
+
+    Ok(bans
+        .into_iter()
+        .max_by_key(|ban| ban.expires_at.unwrap_or(i64::MAX)))
+}
+
+/// 分页获取生效中的封禁：返回ID小于 before 的最近 limit 条（最新的在前）
+pub async fn find_active_page(
+    now: i64,
+    before: Option<i32>,
+    limit: u64,
+) -> Result<Vec<sys_ban::Model>> {
+    let db = get_db_unwrap();
+
+    let mut query = sys_ban::Entity::find().filter(active(now));
+    if let Some(before) = before {
+        query = query.filter(sys_ban::Column::Id.lt(before));
+    }
+
+    let bans = query
+        .order_by_desc(sys_ban::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await?;
+
+    Ok(bans)
+}
+
+/// 解除生效中的封禁，返回是否有封禁被解除
+pub async fn lift(id: i32, lifted_by: i32, now: i64) -> Result<bool> {
+    let db = get_db_unwrap();
+    let res = sys_ban::Entity::update_many()
+        .set(ActiveModel {
+            lifted_at: ActiveValue::Set(Some(now)),
+            lifted_by: ActiveValue::Set(Some(lifted_by)),
+            ..Default::default()
+        })
+        .filter(sys_ban::Column::Id.eq(id))
+        .filter(active(now))
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected > 0)
+}
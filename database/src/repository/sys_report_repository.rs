@@ -0,0 +1,89 @@
+use anyhow::Result;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+
+use crate::entity::sys_report::{self, ActiveModel, ReportStatus};
+use crate::get_db_unwrap;
+
+/// 待记录的举报
+#[derive(Debug, Clone)]
+pub struct NewReport {
+    pub reporter: String,
+    pub target: String,
+    pub session_id: Option<String>,
+    pub reason: String,
+    pub detail: Option<String>,
+}
+
+/// 记录举报，返回举报ID
+pub async fn create(report: NewReport) -> Result<i32> {
+    let db = get_db_unwrap();
+    let model = ActiveModel {
+        id: ActiveValue::NotSet,
+        reporter: ActiveValue::Set(report.reporter),
+        target: ActiveValue::Set(report.target),
+        session_id: ActiveValue::Set(report.session_id),
+        reason: ActiveValue::Set(report.reason),
+        detail: ActiveValue::Set(report.detail),
+        status: ActiveValue::Set(ReportStatus::Pending),
+        handled_by: ActiveValue::Set(None),
+        handled_at: ActiveValue::Set(None),
+        note: ActiveValue::Set(None),
+        created_at: ActiveValue::NotSet,
+    }
+    .insert(db)
+    .await?;
+
+    Ok(model.id)
+}
+
+/// 分页获取举报：返回ID小于 before 的最近 limit 条（最新的在前），status 为空时不按状态过滤
+pub async fn find_page(
+    status: Option<ReportStatus>,
+    before: Option<i32>,
+    limit: u64,
+) -> Result<Vec<sys_report::Model>> {
+    let db = get_db_unwrap();
+
+    let mut query = sys_report::Entity::find();
+    if let Some(status) = status {
+        query = query.filter(sys_report::Column::Status.eq(status));
+    }
+    if let Some(before) = before {
+        query = query.filter(sys_report::Column::Id.lt(before));
+    }
+
+    let reports = query
+        .order_by_desc(sys_report::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await?;
+
+    Ok(reports)
+}
+
+/// 处理待处理的举报，返回是否有举报被更新（已处理过的举报不再更新）
+pub async fn handle(
+    id: i32,
+    status: ReportStatus,
+    handled_by: i32,
+    note: Option<String>,
+    handled_at: i64,
+) -> Result<bool> {
+    let db = get_db_unwrap();
+    let res = sys_report::Entity::update_many()
+        .set(ActiveModel {
+            status: ActiveValue::Set(status),
+            handled_by: ActiveValue::Set(Some(handled_by)),
+            handled_at: ActiveValue::Set(Some(handled_at)),
+            note: ActiveValue::Set(note),
+            ..Default::default()
+        })
+        .filter(sys_report::Column::Id.eq(id))
+        .filter(sys_report::Column::Status.eq(ReportStatus::Pending))
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected > 0)
+}
@@ -1,9 +1,9 @@
 use anyhow::{Result, anyhow};
-use sea_orm::{IntoActiveModel, QueryFilter};
 use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, TransactionTrait};
+use sea_orm::{IntoActiveModel, QueryFilter};
 
 use crate::entity::sys_user;
-use crate::entity::sys_user::ActiveModel;
+use crate::entity::sys_user::{ActiveModel, UserStatus};
 use crate::get_db_unwrap;
 
 pub async fn get_by_id(user_id: &str) -> Result<sys_user::Model> {
@@ -19,6 +19,89 @@ pub async fn get_by_id(user_id: &str) -> Result<sys_user::Model> {
     }
 }
 
+/// 按ID查找用户
+pub async fn find_by_id(user_id: i32) -> Result<Option<sys_user::Model>> {
+    let db = get_db_unwrap();
+    let user = sys_user::Entity::find_by_id(user_id).one(db).await?;
+
+    Ok(user)
+}
+
+/// 按登录邮箱查找用户，邮箱统一以小写存储
+pub async fn find_by_email(email: &str) -> Result<Option<sys_user::Model>> {
+    let db = get_db_unwrap();
+    let user = sys_user::Entity::find()
+        .filter(sys_user::Column::Email.eq(email.to_lowercase()))
+        .one(db)
+        .await?;
+
+    Ok(user)
+}
+
+/// 创建等待验证邮箱的用户
+///
+/// 验证邮箱前账号没有密码（空哈希无法通过校验），密码和昵称在验证通过时由 [`activate`] 写入
+pub async fn create_pending(email: &str) -> Result<sys_user::Model> {
+    let db = get_db_unwrap();
+    let user = ActiveModel {
+        id: ActiveValue::NotSet,
+        name: ActiveValue::Set(None),
+        email: ActiveValue::Set(email.to_lowercase()),
+        password_hash: ActiveValue::Set(String::new()),
+        status: ActiveValue::Set(UserStatus::Pending),
+        created_at: ActiveValue::NotSet,
+        updated_at: ActiveValue::NotSet,
+    }
+    .insert(db)
+    .await?;
+
+    Ok(user)
+}
+
+/// 更新密码哈希
+pub async fn update_password(user_id: i32, password_hash: String) -> Result<()> {
+    let db = get_db_unwrap();
+    ActiveModel {
+        id: ActiveValue::Unchanged(user_id),
+        password_hash: ActiveValue::Set(password_hash),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+
+    Ok(())
+}
+
+/// 邮箱验证通过：写入验证的那次注册设置的密码和昵称并激活账号
+pub async fn activate(user_id: i32, password_hash: String, name: Option<String>) -> Result<()> {
+    let db = get_db_unwrap();
+    ActiveModel {
+        id: ActiveValue::Unchanged(user_id),
+        name: ActiveValue::Set(name),
+        password_hash: ActiveValue::Set(password_hash),
+        status: ActiveValue::Set(UserStatus::Active),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+
+    Ok(())
+}
+
+/// 更新账号状态
+pub async fn update_status(user_id: i32, status: UserStatus) -> Result<()> {
+    let db = get_db_unwrap();
+    ActiveModel {
+        id: ActiveValue::Unchanged(user_id),
+        status: ActiveValue::Set(status),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn insert() -> ActiveModel {
     let db = get_db_unwrap();
     let txn = db.begin().await.unwrap();
@@ -26,6 +109,11 @@ pub async fn insert() -> ActiveModel {
     let user = ActiveModel {
         id: ActiveValue::NotSet,
         name: ActiveValue::Set(Some("李寻欢".to_owned())),
+        email: ActiveValue::Set("lixunhuan@example.com".to_owned()),
+        password_hash: ActiveValue::Set(String::new()),
+        status: ActiveValue::Set(UserStatus::Disabled),
+        created_at: ActiveValue::NotSet,
+        updated_at: ActiveValue::NotSet,
     }
     .save(&txn)
     .await
@@ -0,0 +1,126 @@
+use anyhow::Result;
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::entity::sys_user_session;
+use crate::entity::sys_user_session::ActiveModel;
+use crate::get_db_unwrap;
+
+/// 待记录的登录会话
+#[derive(Debug, Clone)]
+pub struct NewUserSession {
+    pub id: String,
+    pub user_id: i32,
+    pub device: String,
+    pub user_agent: String,
+    pub ip: String,
+    pub last_seen_at: i64,
+    pub expires_at: i64,
+}
+
+/// 记录登录会话
+pub async fn create(session: NewUserSession) -> Result<()> {
+    let db = get_db_unwrap();
+    ActiveModel {
+        id: ActiveValue::Set(session.id),
+        user_id: ActiveValue::Set(session.user_id),
+        device: ActiveValue::Set(session.device),
+        user_agent: ActiveValue::Set(session.user_agent),
+        ip: ActiveValue::Set(session.ip),
+        last_seen_at: ActiveValue::Set(session.last_seen_at),
+        expires_at: ActiveValue::Set(session.expires_at),
+        revoked_at: ActiveValue::Set(None),
+        created_at: ActiveValue::NotSet,
+    }
+    .insert(db)
+    .await?;
+
+    Ok(())
+}
+
+/// 更新会话最近活跃的时间、IP 和 User-Agent，已吊销的会话不更新
+pub async fn touch(
+    session_id: &str,
+    ip: String,
+    user_agent: String,
+    last_seen_at: i64,
+) -> Result<()> {
+    let db = get_db_unwrap();
+    sys_user_session::Entity::update_many()
+        .set(ActiveModel {
+            ip: ActiveValue::Set(ip),
+            user_agent: ActiveValue::Set(user_agent),
+            last_seen_at: ActiveValue::Set(last_seen_at),
+            ..Default::default()
+        })
+        .filter(sys_user_session::Column::Id.eq(session_id))
+        .filter(sys_user_session::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+/// 按ID查找登录会话
+pub async fn find_by_id(session_id: &str) -> Result<Option<sys_user_session::Model>> {
+    let db = get_db_unwrap();
+    let session = sys_user_session::Entity::find_by_id(session_id)
+        .one(db)
+        .await?;
+
+    Ok(session)
+}
+
+/// 查询用户未吊销且未过期的登录会话，最近活跃的在前
+pub async fn find_active_by_user(user_id: i32, now: i64) -> Result<Vec<sys_user_session::Model>> {
+    let db = get_db_unwrap();
+    let sessions = sys_user_session::Entity::find()
+        .filter(sys_user_session::Column::UserId.eq(user_id))
+        .filter(sys_user_session::Column::RevokedAt.is_null())
+        .filter(sys_user_session::Column::ExpiresAt.gt(now))
+        .order_by_desc(sys_user_session::Column::LastSeenAt)
+        .all(db)
+        .await?;
+
+    Ok(sessions)
+}
+
+/// 标记登录会话已吊销，返回是否有会话被吊销
+pub async fn revoke(session_id: &str, revoked_at: i64) -> Result<bool> {
+    let db = get_db_unwrap();
+    let res = sys_user_session::Entity::update_many()
+        .set(ActiveModel {
+            revoked_at: ActiveValue::Set(Some(revoked_at)),
+            ..Default::default()
+        })
+        .filter(sys_user_session::Column::Id.eq(session_id))
+        .filter(sys_user_session::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected > 0)
+}
+
+/// 吊销用户所有有效的登录会话，返回被吊销的会话ID
+pub async fn revoke_all_by_user(user_id: i32, revoked_at: i64) -> Result<Vec<String>> {
+    let db = get_db_unwrap();
+    let ids: Vec<String> = find_active_by_user(user_id, revoked_at)
+        .await?
+        .into_iter()
+        .map(|session| session.id)
+        .collect();
+    if ids.is_empty() {
+        return Ok(ids);
+    }
+
+    sys_user_session::Entity::update_many()
+        .set(ActiveModel {
+            revoked_at: ActiveValue::Set(Some(revoked_at)),
+            ..Default::default()
+        })
+        .filter(sys_user_session::Column::Id.is_in(ids.clone()))
+        .filter(sys_user_session::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(ids)
+}
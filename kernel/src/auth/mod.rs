@@ -0,0 +1,3 @@
+pub mod password;
+pub mod revocation;
+pub mod token;
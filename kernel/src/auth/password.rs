@@ -0,0 +1,74 @@
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+};
+use std::sync::OnceLock;
+
+/// 账号不存在时参与校验的哈希，保证无论账号是否存在校验耗时都相同
+static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+/// 使用 argon2id（默认参数）计算密码哈希，返回 PHC 格式字符串
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())
+        .map_err(|e| format!("生成盐值失败: {}", e))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("密码哈希失败: {}", e))
+}
+
+/// 校验密码，hash 为空（账号不存在）时与固定哈希比较并返回 false
+///
+/// argon2 计算耗时较长，异步环境中应放到 spawn_blocking 中调用
+pub fn verify_password(password: &str, hash: Option<&str>) -> bool {
+    let dummy = DUMMY_HASH.get_or_init(|| {
+        hash_password(&uuid::Uuid::new_v4().to_string()).expect("生成固定哈希失败")
+    });
+    let (hash, exists) = match hash {
+        Some(hash) => (hash, true),
+        None => (dummy.as_str(), false),
+    };
+
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        // 哈希格式损坏时仍做一次校验，避免通过耗时区分
+        let _ = verify_password(password, None);
+        return false;
+    };
+    let matched = Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok();
+
+    matched && exists
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_hashed_password() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify_password("correct horse", Some(&hash)));
+        assert!(!verify_password("wrong horse", Some(&hash)));
+
+        // 每次哈希使用不同的盐
+        assert_ne!(hash, hash_password("correct horse").unwrap());
+    }
+
+    #[test]
+    fn unknown_user_checks_against_dummy_hash() {
+        assert!(!verify_password("correct horse", None));
+        assert!(!verify_password("", None));
+
+        // 账号不存在时确实与一个有效的 argon2 哈希比较，耗时与账号存在时相同
+        let dummy = DUMMY_HASH.get().expect("固定哈希未生成");
+        assert!(PasswordHash::new(dummy).is_ok());
+    }
+
+    #[test]
+    fn malformed_hash_never_matches() {
+        assert!(!verify_password("correct horse", Some("not a hash")));
+        assert!(!verify_password("correct horse", Some("")));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::auth::token::{Claims, TokenError};
+use crate::config::auth_config;
+use crate::redis::get_redis_pool;
+use crate::redis::service::RedisService;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 本地记录超过该数量时清理已过期的记录
+const LOCAL_PRUNE_THRESHOLD: usize = 10_000;
+
+/// 本进程内的吊销记录
+struct LocalRecord {
+    value: u64,
+    expires_at: u64,
+}
+
+/// 本进程内的吊销记录：key -> 记录，只在未配置 redis 时使用
+static LOCAL_REVOKED: OnceLock<Mutex<HashMap<String, LocalRecord>>> = OnceLock::new();
+
+/// 被吊销的令牌
+fn token_key(jti: &str) -> String {
+    format!("auth:revoked:token:{}", jti)
+}
+
+/// 被吊销的登录会话
+fn session_key(sid: &str) -> String {
+    format!("auth:revoked:session:{}", sid)
+}
+
+/// 用户的令牌吊销时间，此前签发的令牌全部失效
+fn user_key(sub: &str) -> String {
+    format!("auth:revoked:user:{}", sub)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// 吊销单个令牌，记录保留到令牌自然过期
+pub async fn revoke_token(claims: &Claims) -> Result<(), TokenError> {
+    let ttl = claims.exp.saturating_sub(now());
+    if ttl > 0 {
+        mark(&token_key(&claims.jti), 1, ttl).await?;
+    }
+    Ok(())
+}
+
+/// 吊销登录会话，会话内签发的所有访问令牌和刷新令牌都失效
+pub async fn revoke_session(sid: &str) -> Result<(), TokenError> {
+    mark(&session_key(sid), 1, session_lifetime()).await?;
+    tracing::info!("登录会话 {} 已吊销", sid);
+    Ok(())
+}
+
+/// 吊销用户此前签发的所有令牌（如重置密码后），之后重新登录签发的令牌不受影响
+pub async fn revoke_user(sub: &str) -> Result<(), TokenError> {
+    mark(&user_key(sub), now(), session_lifetime()).await?;
+    tracing::info!("用户 {} 的所有令牌已吊销", sub);
+    Ok(())
+}
+
+/// 检查令牌本身、所属登录会话或用户是否已被吊销
+pub async fn check(claims: &Claims) -> Result<(), TokenError> {
+    if read(&token_key(&claims.jti)).await?.is_some() {
+        return Err(TokenError::Revoked);
+    }
+    check_session(claims).await
+}
+
+/// 只检查令牌所属的登录会话和用户是否已被吊销
+pub async fn check_session(claims: &Claims) -> Result<(), TokenError> {
+    if let Some(sid) = &claims.sid
+        && read(&session_key(sid)).await?.is_some()
+    {
+        return Err(TokenError::Revoked);
+    }
+    // 签发时间精确到秒，与吊销同一秒签发的令牌也视为已吊销
+    if read(&user_key(&claims.sub))
+        .await?
+        .is_some_and(|revoked_at| claims.iat <= revoked_at)
+    {
+        return Err(TokenError::Revoked);
+    }
+    Ok(())
+}
+
+/// 使用刷新令牌：原子地吊销该令牌，已被使用过时返回 Reused
+pub async fn consume(claims: &Claims) -> Result<(), TokenError> {
+    let ttl = claims.exp.saturating_sub(now()).max(1);
+    match mark_once(&token_key(&claims.jti), ttl).await? {
+        true => Ok(()),
+        false => Err(TokenError::Reused),
+    }
+}
+
+/// 登录会话内令牌的最长有效期（轮换后的刷新令牌不会超过首次签发时的过期时间）
+fn session_lifetime() -> u64 {
+    let config = auth_config();
+    config.refresh_token_ttl.max(config.remember_token_ttl) + config.access_token_ttl
+}
+
+/// 配置了 redis 时吊销记录只保存在 redis 中，redis 不可用时返回 Unavailable 而不是退回本地记录：
+/// 本地记录对其他节点不可见，退回会让已吊销的令牌在其他节点上继续有效
+async fn mark(key: &str, value: u64, ttl: u64) -> Result<(), TokenError> {
+    if get_redis_pool().is_err() {
+        mark_local(key, value, ttl, false);
+        return Ok(());
+    }
+    RedisService::set(key, &value.to_string(), Some(ttl))
+        .await
+        .map_err(|e| unavailable("写入", e))
+}
+
+async fn mark_once(key: &str, ttl: u64) -> Result<bool, TokenError> {
+    if get_redis_pool().is_err() {
+        return Ok(mark_local(key, 1, ttl, true));
+    }
+    RedisService::set_nx(key, "1", ttl)
+        .await
+        .map_err(|e| unavailable("写入", e))
+}
+
+/// 读取吊销记录的值，不存在时返回 None
+async fn read(key: &str) -> Result<Option<u64>, TokenError> {
+    if get_redis_pool().is_ok() {
+        let value = RedisService::get(key)
+            .await
+            .map_err(|e| unavailable("读取", e))?;
+        return Ok(value.and_then(|value| value.parse().ok()));
+    }
+
+    let now = now();
+    let revoked = LOCAL_REVOKED.get_or_init(Default::default).lock().unwrap();
+    Ok(revoked
+        .get(key)
+        .filter(|record| record.expires_at > now)
+        .map(|record| record.value))
+}
+
+fn unavailable(action: &str, e: impl std::fmt::Display) -> TokenError {
+    tracing::error!("{}吊销记录失败: {}", action, e);
+    TokenError::Unavailable
+}
+
+/// 写入本地记录，only_new 为 true 时记录已存在则不覆盖，返回是否写入
+fn mark_local(key: &str, value: u64, ttl: u64, only_new: bool) -> bool {
+    let now = now();
+    let mut revoked = LOCAL_REVOKED.get_or_init(Default::default).lock().unwrap();
+
+    if revoked.len() > LOCAL_PRUNE_THRESHOLD {
+        revoked.retain(|_, record| record.expires_at > now);
+    }
+
+    if only_new
+        && revoked
+            .get(key)
+            .is_some_and(|record| record.expires_at > now)
+    {
+        return false;
+    }
+    revoked.insert(
+        key.to_string(),
+        LocalRecord {
+            value,
+            expires_at: now + ttl,
+        },
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::token::TokenKind;
+
+    fn claims(sid: Option<&str>, iat: u64) -> Claims {
+        Claims {
+            sub: uuid::Uuid::new_v4().to_string(),
+            kind: TokenKind::Refresh,
+            jti: uuid::Uuid::new_v4().to_string(),
+            sid: sid.map(str::to_string),
+            ip: None,
+            iss: "test".to_string(),
+            iat,
+            exp: now() + 3600,
+        }
+    }
+
+    #[tokio::test]
+    async fn consume_detects_reuse() {
+        let claims = claims(None, now());
+        assert!(consume(&claims).await.is_ok());
+        assert!(matches!(consume(&claims).await, Err(TokenError::Reused)));
+        // 已使用的刷新令牌同时视为已吊销
+        assert!(matches!(check(&claims).await, Err(TokenError::Revoked)));
+
+        // 其他令牌不受影响
+        assert!(consume(&self::claims(None, now())).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn revoked_token_is_rejected() {
+        let claims = claims(None, now());
+        assert!(check(&claims).await.is_ok());
+        revoke_token(&claims).await.unwrap();
+        assert!(matches!(check(&claims).await, Err(TokenError::Revoked)));
+    }
+
+    #[tokio::test]
+    async fn revoked_session_rejects_its_tokens() {
+        let sid = uuid::Uuid::new_v4().to_string();
+        let claims = claims(Some(&sid), now());
+        mark_local(&session_key(&sid), 1, 60, false);
+        assert!(matches!(
+            check_session(&claims).await,
+            Err(TokenError::Revoked)
+        ));
+        assert!(
+            check_session(&self::claims(Some("other"), now()))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn revoked_user_rejects_tokens_issued_up_to_that_second() {
+        let revoked_at = now();
+        for (iat, revoked) in [
+            (revoked_at - 10, true),
+            (revoked_at, true),
+            (revoked_at + 1, false),
+        ] {
+            let claims = claims(None, iat);
+            mark_local(&user_key(&claims.sub), revoked_at, 60, false);
+            assert_eq!(
+                check_session(&claims).await.is_err(),
+                revoked,
+                "iat {}",
+                iat
+            );
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use crate::config::{auth_config, server_config};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode, errors::ErrorKind,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// 令牌类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    /// 登录用户的访问令牌
+    Access,
+    /// 登录用户的刷新令牌，只能用于换取新的访问令牌
+    Refresh,
+    /// 匿名游客令牌
+    Guest,
+}
+
+/// JWT 载荷
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// 用户ID，游客为随机ID
+    pub sub: String,
+    pub kind: TokenKind,
+    /// 令牌ID
+    pub jti: String,
+    /// 登录会话ID，同一次登录签发（含轮换）的访问令牌和刷新令牌共用，游客令牌为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    /// 领取游客身份时的客户端IP，只有游客令牌携带，用于按IP封禁游客
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    pub iss: String,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+#[derive(Error, Debug)]
+pub enum TokenError {
+    #[error("令牌已过期")]
+    Expired,
+
+    #[error("令牌无效")]
+    Invalid,
+
+    #[error("令牌类型不匹配")]
+    WrongKind,
+
+    #[error("令牌已被吊销")]
+    Revoked,
+
+    #[error("刷新令牌已被使用，请重新登录")]
+    Reused,
+
+    #[error("令牌签发失败: {0}")]
+    Encode(String),
+
+    #[error("认证服务暂不可用，请稍后再试")]
+    Unavailable,
+}
+
+impl TokenError {
+    /// 对应的响应码：吊销记录暂时无法读写时为 503，其余为 401
+    pub fn code(&self) -> i32 {
+        match self {
+            TokenError::Unavailable => 503,
+            _ => 401,
+        }
+    }
+}
+
+/// 全局令牌服务
+static TOKEN_SERVICE: OnceLock<TokenService> = OnceLock::new();
+
+/// JWT 签发和校验（HS256）
+pub struct TokenService {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    validation: Validation,
+    issuer: String,
+}
+
+impl TokenService {
+    pub fn new(secret: &[u8], issuer: &str) -> Self {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[issuer]);
+        validation.set_required_spec_claims(&["exp", "sub", "iss"]);
+        validation.leeway = 5;
+
+        Self {
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+            validation,
+            issuer: issuer.to_string(),
+        }
+    }
+
+    /// 根据密钥创建令牌服务
+    ///
+    /// 未配置密钥时只有单节点的调试环境使用随机密钥（重启后令牌失效）；集群或正式环境中各节点、
+    /// 每次重启的密钥不同会导致令牌互不认可，直接拒绝启动
+    pub fn from_secret(
+        secret: &str,
+        issuer: &str,
+        debug: bool,
+        cluster: bool,
+    ) -> Result<Self, String> {
+        if !secret.is_empty() {
+            return Ok(Self::new(secret.as_bytes(), issuer));
+        }
+        if cluster || !debug {
+            return Err("未配置 JWT_SECRET，集群或正式环境必须配置签名密钥".to_string());
+        }
+
+        tracing::warn!("未配置 JWT_SECRET，使用随机密钥，重启后令牌失效");
+        let secret = [uuid::Uuid::new_v4(), uuid::Uuid::new_v4()]
+            .iter()
+            .flat_map(|id| id.into_bytes())
+            .collect::<Vec<u8>>();
+        Ok(Self::new(&secret, issuer))
+    }
+
+    /// 根据认证配置初始化全局令牌服务（应用启动时调用），密钥配置无效时返回错误
+    pub fn init() -> Result<(), String> {
+        let service = Self::from_config()?;
+        let _ = TOKEN_SERVICE.set(service);
+        Ok(())
+    }
+
+    /// 获取全局令牌服务
+    pub fn global() -> &'static Self {
+        TOKEN_SERVICE.get_or_init(|| Self::from_config().unwrap_or_else(|e| panic!("{}", e)))
+    }
+
+    fn from_config() -> Result<Self, String> {
+        let config = auth_config();
+        let server = server_config();
+        Self::from_secret(
+            &config.jwt_secret,
+            &config.jwt_issuer,
+            server.debug,
+            server.ws_cluster,
+        )
+    }
+
+    /// 签发令牌，返回令牌和载荷
+    pub fn issue(
+        &self,
+        sub: &str,
+        kind: TokenKind,
+        ttl: u64,
+    ) -> Result<(String, Claims), TokenError> {
+        self.issue_in_session(sub, kind, ttl, None)
+    }
+
+    /// 签发游客令牌，记录领取身份时的客户端IP
+    pub fn issue_guest(
+        &self,
+        sub: &str,
+        ttl: u64,
+        ip: &str,
+    ) -> Result<(String, Claims), TokenError> {
+        self.sign(sub, TokenKind::Guest, ttl, None, Some(ip))
+    }
+
+    /// 签发属于某个登录会话的令牌
+    pub fn issue_in_session(
+        &self,
+        sub: &str,
+        kind: TokenKind,
+        ttl: u64,
+        sid: Option<&str>,
+    ) -> Result<(String, Claims), TokenError> {
+        self.sign(sub, kind, ttl, sid, None)
+    }
+
+    fn sign(
+        &self,
+        sub: &str,
+        kind: TokenKind,
+        ttl: u64,
+        sid: Option<&str>,
+        ip: Option<&str>,
+    ) -> Result<(String, Claims), TokenError> {
+        let iat = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let claims = Claims {
+            sub: sub.to_string(),
+            kind,
+            jti: uuid::Uuid::new_v4().to_string(),
+            sid: sid.map(|sid| sid.to_string()),
+            ip: ip.map(|ip| ip.to_string()),
+            iss: self.issuer.clone(),
+            iat,
+            exp: iat + ttl,
+        };
+
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &self.encoding)
+            .map_err(|e| TokenError::Encode(e.to_string()))?;
+        Ok((token, claims))
+    }
+
+    /// 校验令牌签名、签发者、有效期和类型
+    pub fn verify(&self, token: &str, kinds: &[TokenKind]) -> Result<Claims, TokenError> {
+        let claims = decode::<Claims>(token, &self.decoding, &self.validation)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => TokenError::Expired,
+                _ => TokenError::Invalid,
+            })?
+            .claims;
+
+        if !kinds.contains(&claims.kind) {
+            return Err(TokenError::WrongKind);
+        }
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test-secret";
+
+    fn service() -> TokenService {
+        TokenService::new(SECRET.as_bytes(), "stranger-api")
+    }
+
+    #[test]
+    fn issued_token_round_trips() {
+        let service = service();
+        let (token, issued) = service
+            .issue_in_session("user-1", TokenKind::Access, 60, Some("sid-1"))
+            .unwrap();
+
+        let claims = service
+            .verify(&token, &[TokenKind::Access, TokenKind::Guest])
+            .unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.kind, TokenKind::Access);
+        assert_eq!(claims.jti, issued.jti);
+        assert_eq!(claims.sid.as_deref(), Some("sid-1"));
+        assert_eq!(claims.exp, claims.iat + 60);
+        assert_eq!(claims.ip, None);
+
+        let (guest, _) = service.issue_guest("guest-1", 60, "203.0.113.7").unwrap();
+        let claims = service.verify(&guest, &[TokenKind::Guest]).unwrap();
+        assert_eq!(claims.kind, TokenKind::Guest);
+        assert_eq!(claims.ip.as_deref(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    fn tampered_signature_is_rejected() {
+        let service = service();
+        let (token, _) = service.issue("user-1", TokenKind::Access, 60).unwrap();
+
+        // 修改签名的第一个字符（最后一个字符的低位是 base64 填充，修改后可能仍解码为同一签名）
+        let at = token.rfind('.').unwrap() + 1;
+        let replacement = if token[at..].starts_with('A') {
+            "B"
+        } else {
+            "A"
+        };
+        let mut tampered = token.clone();
+        tampered.replace_range(at..at + 1, replacement);
+        assert!(matches!(
+            service.verify(&tampered, &[TokenKind::Access]),
+            Err(TokenError::Invalid)
+        ));
+
+        // 其他密钥签发的令牌
+        let other = TokenService::new(b"other-secret", "stranger-api");
+        let (forged, _) = other.issue("user-1", TokenKind::Access, 60).unwrap();
+        assert!(matches!(
+            service.verify(&forged, &[TokenKind::Access]),
+            Err(TokenError::Invalid)
+        ));
+    }
+
+    #[test]
+    fn expired_token_is_rejected() {
+        let service = service();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let claims = Claims {
+            sub: "user-1".to_string(),
+            kind: TokenKind::Access,
+            jti: uuid::Uuid::new_v4().to_string(),
+            sid: None,
+            ip: None,
+            iss: "stranger-api".to_string(),
+            iat: now - 120,
+            // 超过校验时允许的 5 秒误差
+            exp: now - 60,
+        };
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &service.encoding).unwrap();
+
+        assert!(matches!(
+            service.verify(&token, &[TokenKind::Access]),
+            Err(TokenError::Expired)
+        ));
+    }
+
+    #[test]
+    fn wrong_kind_is_rejected() {
+        let service = service();
+        let (refresh, _) = service.issue("user-1", TokenKind::Refresh, 60).unwrap();
+
+        assert!(matches!(
+            service.verify(&refresh, &[TokenKind::Access, TokenKind::Guest]),
+            Err(TokenError::WrongKind)
+        ));
+        assert!(service.verify(&refresh, &[TokenKind::Refresh]).is_ok());
+    }
+
+    #[test]
+    fn wrong_issuer_is_rejected() {
+        let other = TokenService::new(SECRET.as_bytes(), "other-api");
+        let (token, _) = other.issue("user-1", TokenKind::Access, 60).unwrap();
+
+        assert!(matches!(
+            service().verify(&token, &[TokenKind::Access]),
+            Err(TokenError::Invalid)
+        ));
+    }
+
+    #[test]
+    fn empty_secret_only_allowed_for_single_debug_node() {
+        assert!(TokenService::from_secret("", "stranger-api", true, false).is_ok());
+        assert!(TokenService::from_secret("", "stranger-api", true, true).is_err());
+        assert!(TokenService::from_secret("", "stranger-api", false, false).is_err());
+        assert!(TokenService::from_secret(SECRET, "stranger-api", false, true).is_ok());
+
+        // 配置的密钥签发的令牌在各节点上都能校验
+        let node_a = TokenService::from_secret(SECRET, "stranger-api", false, true).unwrap();
+        let (token, _) = node_a.issue("user-1", TokenKind::Access, 60).unwrap();
+        assert!(service().verify(&token, &[TokenKind::Access]).is_ok());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::config::error::ConfigError;
+use std::env;
+
+/// 认证配置
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    /// JWT 签名密钥，只有单节点的调试环境可以留空（启动时随机生成，重启后令牌失效），集群或正式环境留空时拒绝启动
+    pub jwt_secret: String,
+    /// JWT 签发者
+    pub jwt_issuer: String,
+    /// 访问令牌有效期（秒）
+    pub access_token_ttl: u64,
+    /// 刷新令牌有效期（秒）
+    pub refresh_token_ttl: u64,
+    /// 登录时勾选「记住我」的刷新令牌有效期（秒）
+    pub remember_token_ttl: u64,
+    /// 游客令牌有效期（秒）
+    pub guest_token_ttl: u64,
+    /// 单个IP在周期内可领取的游客身份数，0 表示不限制
+    pub guest_rate_limit: u32,
+    /// 游客身份领取限制的周期（秒）
+    pub guest_rate_window: u64,
+    /// 邮箱验证码有效期（秒）
+    pub email_code_ttl: u64,
+    /// 重置密码令牌有效期（秒）
+    pub password_reset_ttl: u64,
+    /// 重置密码页面地址，配置后邮件中附带 `地址?token=令牌` 链接
+    pub password_reset_url: String,
+}
+
+impl AuthConfig {
+    /// 从环境变量创建认证配置
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let jwt_secret = env::var("JWT_SECRET")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("JWT_SECRET".to_string()))?;
+
+        let jwt_issuer = env::var("JWT_ISSUER")
+            .unwrap_or_else(|_| "stranger-api".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("JWT_ISSUER".to_string()))?;
+
+        let access_token_ttl = env::var("JWT_ACCESS_TTL")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<u64>()
+            .map_err(|e| ConfigError::InvalidValue("JWT_ACCESS_TTL".to_string(), e.to_string()))?;
+
+        let refresh_token_ttl = env::var("JWT_REFRESH_TTL")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse::<u64>()
+            .map_err(|e| ConfigError::InvalidValue("JWT_REFRESH_TTL".to_string(), e.to_string()))?;
+
+        let remember_token_ttl = env::var("JWT_REMEMBER_TTL")
+            .unwrap_or_else(|_| "2592000".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("JWT_REMEMBER_TTL".to_string(), e.to_string())
+            })?;
+
+        let guest_token_ttl = env::var("JWT_GUEST_TTL")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse::<u64>()
+            .map_err(|e| ConfigError::InvalidValue("JWT_GUEST_TTL".to_string(), e.to_string()))?;
+
+        let guest_rate_limit = env::var("GUEST_RATE_LIMIT")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u32>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("GUEST_RATE_LIMIT".to_string(), e.to_string())
+            })?;
+
+        let guest_rate_window = env::var("GUEST_RATE_WINDOW")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("GUEST_RATE_WINDOW".to_string(), e.to_string())
+            })?;
+
+        let email_code_ttl = env::var("EMAIL_CODE_TTL")
+            .unwrap_or_else(|_| "600".to_string())
+            .parse::<u64>()
+            .map_err(|e| ConfigError::InvalidValue("EMAIL_CODE_TTL".to_string(), e.to_string()))?;
+
+        let password_reset_ttl = env::var("PASSWORD_RESET_TTL")
+            .unwrap_or_else(|_| "1800".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("PASSWORD_RESET_TTL".to_string(), e.to_string())
+            })?;
+
+        let password_reset_url = env::var("PASSWORD_RESET_URL")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("PASSWORD_RESET_URL".to_string()))?;
+
+        Ok(Self {
+            jwt_secret,
+            jwt_issuer,
+            access_token_ttl,
+            refresh_token_ttl,
+            remember_token_ttl,
+            guest_token_ttl,
+            guest_rate_limit,
+            guest_rate_window,
+            email_code_ttl,
+            password_reset_ttl,
+            password_reset_url,
+        })
+    }
+}
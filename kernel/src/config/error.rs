@@ -13,4 +13,4 @@ pub enum ConfigError {
 
     #[error("Failed to load .env file: {0}")]
     EnvLoadFailed(String),
-}
\ No newline at end of file
+}
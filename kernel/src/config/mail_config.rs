@@ -0,0 +1,90 @@
+use crate::config::error::ConfigError;
+use std::env;
+
+/// 邮件配置
+#[derive(Debug, Clone)]
+pub struct MailConfig {
+    /// 发信方式：log（写入本地发件箱目录并打印日志，用于本地测试）或 smtp
+    pub backend: String,
+    /// 发件人，如 `Stranger <no-reply@example.com>`
+    pub from: String,
+    /// log 方式的发件箱目录
+    pub outbox_dir: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    pub smtp_password: String,
+    /// SMTP 加密方式：starttls、tls 或 none
+    pub smtp_tls: String,
+}
+
+impl MailConfig {
+    /// 从环境变量创建邮件配置
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let backend = env::var("MAIL_BACKEND")
+            .unwrap_or_else(|_| "log".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("MAIL_BACKEND".to_string()))?;
+        if backend != "log" && backend != "smtp" {
+            return Err(ConfigError::InvalidValue(
+                "MAIL_BACKEND".to_string(),
+                format!("unsupported backend `{}`, expected log or smtp", backend),
+            ));
+        }
+
+        let from = env::var("MAIL_FROM")
+            .unwrap_or_else(|_| "Stranger <no-reply@localhost>".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("MAIL_FROM".to_string()))?;
+
+        let outbox_dir = env::var("MAIL_OUTBOX_DIR")
+            .unwrap_or_else(|_| "mail".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("MAIL_OUTBOX_DIR".to_string()))?;
+
+        let smtp_host = env::var("MAIL_SMTP_HOST")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("MAIL_SMTP_HOST".to_string()))?;
+
+        let smtp_port = env::var("MAIL_SMTP_PORT")
+            .unwrap_or_else(|_| "587".to_string())
+            .parse::<u16>()
+            .map_err(|e| ConfigError::InvalidValue("MAIL_SMTP_PORT".to_string(), e.to_string()))?;
+
+        let smtp_username = env::var("MAIL_SMTP_USERNAME")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("MAIL_SMTP_USERNAME".to_string()))?;
+
+        let smtp_password = env::var("MAIL_SMTP_PASSWORD")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("MAIL_SMTP_PASSWORD".to_string()))?;
+
+        let smtp_tls = env::var("MAIL_SMTP_TLS")
+            .unwrap_or_else(|_| "starttls".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("MAIL_SMTP_TLS".to_string()))?;
+        if !["starttls", "tls", "none"].contains(&smtp_tls.as_str()) {
+            return Err(ConfigError::InvalidValue(
+                "MAIL_SMTP_TLS".to_string(),
+                format!(
+                    "unsupported value `{}`, expected starttls, tls or none",
+                    smtp_tls
+                ),
+            ));
+        }
+
+        Ok(Self {
+            backend,
+            from,
+            outbox_dir,
+            smtp_host,
+            smtp_port,
+            smtp_username,
+            smtp_password,
+            smtp_tls,
+        })
+    }
+}
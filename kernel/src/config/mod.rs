@@ -1,11 +1,17 @@
+mod auth_config;
 mod database_config;
 pub mod error;
+mod mail_config;
 mod redis_config;
 mod server_config;
+mod storage_config;
 
 use crate::config::{
-    database_config::DatabaseConfig, redis_config::RedisConfig, server_config::ServerConfig,
+    auth_config::AuthConfig, database_config::DatabaseConfig, redis_config::RedisConfig,
+    server_config::ServerConfig, storage_config::StorageConfig,
 };
+pub use mail_config::MailConfig;
+
 use dotenvy::dotenv;
 use error::ConfigError;
 use std::sync::OnceLock;
@@ -19,6 +25,9 @@ pub struct AppConfig {
     pub database: DatabaseConfig,
     pub server: ServerConfig,
     pub redis: RedisConfig,
+    pub storage: StorageConfig,
+    pub auth: AuthConfig,
+    pub mail: MailConfig,
 }
 
 impl AppConfig {
@@ -41,12 +50,26 @@ impl AppConfig {
             .map_err(|_| ConfigError::AlreadyInitialized)
     }
 
+    /// 只从环境变量初始化配置，不加载 .env 文件（如测试），已经初始化时直接返回
+    pub fn init_from_env() -> Result<(), ConfigError> {
+        if CONFIG.get().is_some() {
+            return Ok(());
+        }
+
+        let config = Self::from_env()?;
+        let _ = CONFIG.set(config);
+        Ok(())
+    }
+
     /// 从环境变量创建配置
     fn from_env() -> Result<Self, ConfigError> {
         Ok(Self {
             server: ServerConfig::from_env()?,
             database: DatabaseConfig::from_env()?,
             redis: RedisConfig::from_env()?,
+            storage: StorageConfig::from_env()?,
+            auth: AuthConfig::from_env()?,
+            mail: MailConfig::from_env()?,
         })
     }
 
@@ -77,3 +100,18 @@ pub fn database_config() -> &'static DatabaseConfig {
 pub fn redis_config() -> &'static RedisConfig {
     &AppConfig::global().redis
 }
+
+/// 便捷函数：获取附件存储配置
+pub fn storage_config() -> &'static StorageConfig {
+    &AppConfig::global().storage
+}
+
+/// 便捷函数：获取认证配置
+pub fn auth_config() -> &'static AuthConfig {
+    &AppConfig::global().auth
+}
+
+/// 便捷函数：获取邮件配置
+pub fn mail_config() -> &'static MailConfig {
+    &AppConfig::global().mail
+}
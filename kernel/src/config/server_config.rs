@@ -11,9 +11,51 @@ pub struct ServerConfig {
     pub content_gzip: bool,
     /// 是否开启定时任务
     pub cron: bool,
+    /// 部署在反向代理之后时，客户端IP取自 X-Forwarded-For
+    pub trust_proxy: bool,
+    /// 可信反向代理的层数，客户端IP取 X-Forwarded-For 中从右往左数第该层数个地址
+    pub trusted_proxies: usize,
     /// 是否开启ws
     pub ws_open: bool,
     pub ws_path: String,
+    /// ws 单连接限流令牌桶的补充周期（秒）
+    pub ws_rate_window: u64,
+    /// 每个周期允许的匹配消息数，0 表示不限制
+    pub ws_rate_meet: u32,
+    /// 每个周期允许的私聊消息数，0 表示不限制
+    pub ws_rate_private: u32,
+    /// 每个周期允许的广播消息数，0 表示不限制
+    pub ws_rate_broadcast: u32,
+    /// 每个周期允许的房间消息数，0 表示不限制
+    pub ws_rate_room: u32,
+    /// 每个周期允许的其他消息数，0 表示不限制
+    pub ws_rate_other: u32,
+    /// 周期内允许的通话信令数（ICE 候选较多）
+    pub ws_rate_call: u32,
+    /// 连续超限达到该次数后断开连接，0 表示从不断开
+    pub ws_rate_max_violations: u32,
+    /// 房间人数上限
+    pub ws_room_max_capacity: usize,
+    /// 保留的大厅广播历史条数，0 表示不保留
+    pub ws_broadcast_history: usize,
+    /// 每页返回的广播历史条数（连接成功后也会推送一页）
+    pub ws_broadcast_history_page: usize,
+    /// 同一用户两次大厅广播的最小间隔（秒），0 表示不限制
+    pub ws_broadcast_cooldown: u64,
+    /// 大厅广播的最大字符数
+    pub ws_broadcast_max_length: usize,
+    /// 是否开启 ws 集群模式（依赖 redis 发布订阅）
+    pub ws_cluster: bool,
+    /// 集群节点ID，为空时启动时随机生成
+    pub ws_node_id: String,
+    /// 私聊消息批量入库的条数上限
+    pub ws_chat_persist_batch: usize,
+    /// 私聊消息批量入库的最长间隔（毫秒）
+    pub ws_chat_persist_interval: u64,
+    /// 私聊消息可撤回的时间（秒），0 表示不允许撤回
+    pub ws_recall_window: u64,
+    /// 通话呼叫未接听的超时时间（秒）
+    pub ws_call_ring_timeout: u64,
     /// `log_level` 日志输出等级 TRACE DEBUG INFO  WARN ERROR
     pub log_level: String,
     /// `dir` 日志输出文件夹
@@ -50,13 +92,29 @@ impl ServerConfig {
         let content_gzip = env::var("SERVER_CONTENT_GZIP")
             .unwrap_or_else(|_| "true".to_string())
             .parse::<bool>()
-            .map_err(|e| ConfigError::InvalidValue("SERVER_CONTENT_GZIP".to_string(), e.to_string()))?;
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_CONTENT_GZIP".to_string(), e.to_string())
+            })?;
 
         let cron = env::var("SERVER_CRON")
             .unwrap_or_else(|_| "false".to_string())
             .parse::<bool>()
             .map_err(|e| ConfigError::InvalidValue("SERVER_CRON".to_string(), e.to_string()))?;
 
+        let trust_proxy = env::var("SERVER_TRUST_PROXY")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_TRUST_PROXY".to_string(), e.to_string())
+            })?;
+
+        let trusted_proxies = env::var("SERVER_TRUSTED_PROXIES")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse::<usize>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_TRUSTED_PROXIES".to_string(), e.to_string())
+            })?;
+
         let ws_open = env::var("SERVER_WS_OPEN")
             .unwrap_or_else(|_| "false".to_string())
             .parse::<bool>()
@@ -67,6 +125,149 @@ impl ServerConfig {
             .parse::<String>()
             .map_err(|_| ConfigError::MissingEnvVar("SERVER_WS_PATH".to_string()))?;
 
+        let ws_rate_window = env::var("SERVER_WS_RATE_WINDOW")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_RATE_WINDOW".to_string(), e.to_string())
+            })?;
+
+        let ws_rate_meet = env::var("SERVER_WS_RATE_MEET")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u32>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_RATE_MEET".to_string(), e.to_string())
+            })?;
+
+        let ws_rate_private = env::var("SERVER_WS_RATE_PRIVATE")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u32>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_RATE_PRIVATE".to_string(), e.to_string())
+            })?;
+
+        let ws_rate_broadcast = env::var("SERVER_WS_RATE_BROADCAST")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u32>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_RATE_BROADCAST".to_string(), e.to_string())
+            })?;
+
+        let ws_rate_room = env::var("SERVER_WS_RATE_ROOM")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse::<u32>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_RATE_ROOM".to_string(), e.to_string())
+            })?;
+
+        let ws_rate_other = env::var("SERVER_WS_RATE_OTHER")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse::<u32>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_RATE_OTHER".to_string(), e.to_string())
+            })?;
+
+        let ws_rate_call = env::var("SERVER_WS_RATE_CALL")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u32>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_RATE_CALL".to_string(), e.to_string())
+            })?;
+
+        let ws_rate_max_violations = env::var("SERVER_WS_RATE_MAX_VIOLATIONS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u32>()
+            .map_err(|e| {
+                ConfigError::InvalidValue(
+                    "SERVER_WS_RATE_MAX_VIOLATIONS".to_string(),
+                    e.to_string(),
+                )
+            })?;
+
+        let ws_room_max_capacity = env::var("SERVER_WS_ROOM_MAX_CAPACITY")
+            .unwrap_or_else(|_| "50".to_string())
+            .parse::<usize>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_ROOM_MAX_CAPACITY".to_string(), e.to_string())
+            })?;
+
+        let ws_broadcast_history = env::var("SERVER_WS_BROADCAST_HISTORY")
+            .unwrap_or_else(|_| "200".to_string())
+            .parse::<usize>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_BROADCAST_HISTORY".to_string(), e.to_string())
+            })?;
+
+        let ws_broadcast_history_page = env::var("SERVER_WS_BROADCAST_HISTORY_PAGE")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse::<usize>()
+            .map_err(|e| {
+                ConfigError::InvalidValue(
+                    "SERVER_WS_BROADCAST_HISTORY_PAGE".to_string(),
+                    e.to_string(),
+                )
+            })?;
+
+        let ws_broadcast_cooldown = env::var("SERVER_WS_BROADCAST_COOLDOWN")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_BROADCAST_COOLDOWN".to_string(), e.to_string())
+            })?;
+
+        let ws_broadcast_max_length = env::var("SERVER_WS_BROADCAST_MAX_LENGTH")
+            .unwrap_or_else(|_| "200".to_string())
+            .parse::<usize>()
+            .map_err(|e| {
+                ConfigError::InvalidValue(
+                    "SERVER_WS_BROADCAST_MAX_LENGTH".to_string(),
+                    e.to_string(),
+                )
+            })?;
+
+        let ws_cluster = env::var("SERVER_WS_CLUSTER")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_CLUSTER".to_string(), e.to_string())
+            })?;
+
+        let ws_node_id = env::var("SERVER_WS_NODE_ID")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("SERVER_WS_NODE_ID".to_string()))?;
+
+        let ws_chat_persist_batch = env::var("SERVER_WS_CHAT_PERSIST_BATCH")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<usize>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_CHAT_PERSIST_BATCH".to_string(), e.to_string())
+            })?;
+
+        let ws_chat_persist_interval = env::var("SERVER_WS_CHAT_PERSIST_INTERVAL")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ConfigError::InvalidValue(
+                    "SERVER_WS_CHAT_PERSIST_INTERVAL".to_string(),
+                    e.to_string(),
+                )
+            })?;
+
+        let ws_recall_window = env::var("SERVER_WS_RECALL_WINDOW")
+            .unwrap_or_else(|_| "120".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_RECALL_WINDOW".to_string(), e.to_string())
+            })?;
+
+        let ws_call_ring_timeout = env::var("SERVER_WS_CALL_RING_TIMEOUT")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("SERVER_WS_CALL_RING_TIMEOUT".to_string(), e.to_string())
+            })?;
+
         let log_dir = env::var("LOG_DIR")
             .unwrap_or_else(|_| "logs".to_string())
             .parse::<String>()
@@ -88,8 +289,29 @@ impl ServerConfig {
             port,
             content_gzip,
             cron,
+            trust_proxy,
+            trusted_proxies,
             ws_open,
             ws_path,
+            ws_rate_window,
+            ws_rate_meet,
+            ws_rate_private,
+            ws_rate_broadcast,
+            ws_rate_room,
+            ws_rate_other,
+            ws_rate_call,
+            ws_rate_max_violations,
+            ws_room_max_capacity,
+            ws_broadcast_history,
+            ws_broadcast_history_page,
+            ws_broadcast_cooldown,
+            ws_broadcast_max_length,
+            ws_cluster,
+            ws_node_id,
+            ws_chat_persist_batch,
+            ws_chat_persist_interval,
+            ws_recall_window,
+            ws_call_ring_timeout,
             log_level,
             log_dir,
             log_file,
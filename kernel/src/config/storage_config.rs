@@ -0,0 +1,134 @@
+use crate::config::error::ConfigError;
+use std::env;
+
+/// 附件存储配置
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    /// 存储后端：local 或 s3
+    pub backend: String,
+    /// 本地存储目录
+    pub local_dir: String,
+    /// S3 兼容服务地址（如本地 MinIO：http://127.0.0.1:9000），为空时使用 AWS
+    pub s3_endpoint: String,
+    pub s3_bucket: String,
+    pub s3_region: String,
+    pub s3_access_key: String,
+    pub s3_secret_key: String,
+    /// 单个附件的最大字节数
+    pub max_size: usize,
+    /// 允许上传的 MIME 类型（按文件内容识别）
+    pub allowed_types: Vec<String>,
+    /// 图片的最大像素数（宽 x 高），超过时视为解压炸弹拒绝
+    pub image_max_pixels: u64,
+    /// 缩略图的最大边长（像素）
+    pub thumbnail_size: u32,
+    /// 附件ID的签名密钥，为空时启动时随机生成（多节点部署必须配置）
+    pub file_id_secret: String,
+    /// 附件ID的有效期（秒）
+    pub file_id_ttl: u64,
+}
+
+impl StorageConfig {
+    /// 从环境变量创建附件存储配置
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let backend = env::var("STORAGE_BACKEND")
+            .unwrap_or_else(|_| "local".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("STORAGE_BACKEND".to_string()))?;
+        if backend != "local" && backend != "s3" {
+            return Err(ConfigError::InvalidValue(
+                "STORAGE_BACKEND".to_string(),
+                format!("unsupported backend `{}`, expected local or s3", backend),
+            ));
+        }
+
+        let local_dir = env::var("STORAGE_LOCAL_DIR")
+            .unwrap_or_else(|_| "uploads".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("STORAGE_LOCAL_DIR".to_string()))?;
+
+        let s3_endpoint = env::var("STORAGE_S3_ENDPOINT")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("STORAGE_S3_ENDPOINT".to_string()))?;
+
+        let s3_bucket = env::var("STORAGE_S3_BUCKET")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("STORAGE_S3_BUCKET".to_string()))?;
+
+        let s3_region = env::var("STORAGE_S3_REGION")
+            .unwrap_or_else(|_| "us-east-1".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("STORAGE_S3_REGION".to_string()))?;
+
+        let s3_access_key = env::var("STORAGE_S3_ACCESS_KEY")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("STORAGE_S3_ACCESS_KEY".to_string()))?;
+
+        let s3_secret_key = env::var("STORAGE_S3_SECRET_KEY")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("STORAGE_S3_SECRET_KEY".to_string()))?;
+
+        let max_size = env::var("STORAGE_MAX_SIZE")
+            .unwrap_or_else(|_| "10485760".to_string())
+            .parse::<usize>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("STORAGE_MAX_SIZE".to_string(), e.to_string())
+            })?;
+
+        let allowed_types = env::var("STORAGE_ALLOWED_TYPES")
+            .unwrap_or_else(|_| {
+                "image/jpeg,image/png,image/gif,image/webp,audio/mpeg,audio/ogg,audio/aac,audio/m4a,audio/x-wav"
+                    .to_string()
+            })
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let image_max_pixels = env::var("STORAGE_IMAGE_MAX_PIXELS")
+            .unwrap_or_else(|_| "40000000".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("STORAGE_IMAGE_MAX_PIXELS".to_string(), e.to_string())
+            })?;
+
+        let thumbnail_size = env::var("STORAGE_THUMBNAIL_SIZE")
+            .unwrap_or_else(|_| "320".to_string())
+            .parse::<u32>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("STORAGE_THUMBNAIL_SIZE".to_string(), e.to_string())
+            })?;
+
+        let file_id_secret = env::var("STORAGE_FILE_ID_SECRET")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<String>()
+            .map_err(|_| ConfigError::MissingEnvVar("STORAGE_FILE_ID_SECRET".to_string()))?;
+
+        let file_id_ttl = env::var("STORAGE_FILE_ID_TTL")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ConfigError::InvalidValue("STORAGE_FILE_ID_TTL".to_string(), e.to_string())
+            })?;
+
+        Ok(Self {
+            backend,
+            local_dir,
+            s3_endpoint,
+            s3_bucket,
+            s3_region,
+            s3_access_key,
+            s3_secret_key,
+            max_size,
+            allowed_types,
+            image_max_pixels,
+            thumbnail_size,
+            file_id_secret,
+            file_id_ttl,
+        })
+    }
+}
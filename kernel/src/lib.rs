@@ -1,4 +1,7 @@
+pub mod auth;
 pub mod config;
+pub mod mail;
 pub mod system;
+pub mod throttle;
 pub mod tasks;
 pub mod redis;
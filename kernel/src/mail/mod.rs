@@ -0,0 +1,105 @@
+use crate::config::{MailConfig, mail_config};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use thiserror::Error;
+
+pub mod outbox;
+pub mod smtp;
+
+/// 待发送的纯文本邮件
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Error, Debug)]
+pub enum MailError {
+    #[error("邮件地址无效: {0}")]
+    Address(String),
+
+    #[error("邮件构建失败: {0}")]
+    Build(String),
+
+    #[error("邮件发送失败: {0}")]
+    Send(String),
+}
+
+/// 发送邮件的异步结果
+pub type MailFuture<'a> = Pin<Box<dyn Future<Output = Result<(), MailError>> + Send + 'a>>;
+
+/// 邮件发送方式
+pub trait Mailer: Send + Sync {
+    fn send<'a>(&'a self, email: &'a Email) -> MailFuture<'a>;
+}
+
+/// 全局邮件发送器
+static MAILER: OnceLock<Arc<dyn Mailer>> = OnceLock::new();
+
+/// 根据邮件配置初始化全局邮件发送器（应用启动时调用），`MAIL_BACKEND=smtp` 但 SMTP 配置无效时返回错误
+pub fn init() -> Result<(), MailError> {
+    let mailer = from_config(mail_config())?;
+    let _ = MAILER.set(mailer);
+    Ok(())
+}
+
+/// 获取全局邮件发送器
+pub fn global() -> Arc<dyn Mailer> {
+    MAILER
+        .get_or_init(|| from_config(mail_config()).unwrap_or_else(|e| panic!("{}", e)))
+        .clone()
+}
+
+/// 根据邮件配置创建邮件发送器，选择了 SMTP 时不会退回本地发件箱，避免邮件静默丢失
+fn from_config(config: &MailConfig) -> Result<Arc<dyn Mailer>, MailError> {
+    match config.backend.as_str() {
+        "smtp" => Ok(Arc::new(smtp::SmtpMailer::new(config)?)),
+        _ => Ok(Arc::new(outbox::OutboxMailer::new(&config.outbox_dir))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(backend: &str) -> MailConfig {
+        MailConfig {
+            backend: backend.to_string(),
+            from: "Stranger <no-reply@example.com>".to_string(),
+            outbox_dir: "mail".to_string(),
+            smtp_host: "smtp.example.com".to_string(),
+            smtp_port: 587,
+            smtp_username: String::new(),
+            smtp_password: String::new(),
+            smtp_tls: "none".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_smtp_config_is_an_error() {
+        assert!(from_config(&config("smtp")).is_ok());
+
+        let missing_host = MailConfig {
+            smtp_host: String::new(),
+            ..config("smtp")
+        };
+        assert!(from_config(&missing_host).is_err());
+
+        let invalid_from = MailConfig {
+            from: "not an address".to_string(),
+            ..config("smtp")
+        };
+        assert!(from_config(&invalid_from).is_err());
+    }
+
+    #[test]
+    fn log_backend_ignores_smtp_config() {
+        let config = MailConfig {
+            smtp_host: String::new(),
+            ..config("log")
+        };
+        assert!(from_config(&config).is_ok());
+    }
+}
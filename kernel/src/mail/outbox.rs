@@ -0,0 +1,48 @@
+use crate::mail::{Email, MailError, MailFuture, Mailer};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 本地发件箱：邮件写入目录下的文本文件并打印日志，不真正发送，用于本地测试
+pub struct OutboxMailer {
+    dir: PathBuf,
+}
+
+impl OutboxMailer {
+    pub fn new(dir: &str) -> Self {
+        Self {
+            dir: PathBuf::from(dir),
+        }
+    }
+}
+
+impl Mailer for OutboxMailer {
+    fn send<'a>(&'a self, email: &'a Email) -> MailFuture<'a> {
+        Box::pin(async move {
+            let millis = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis();
+            let path = self
+                .dir
+                .join(format!("{}-{}.eml", millis, uuid::Uuid::new_v4().simple()));
+            let content = format!(
+                "To: {}\nSubject: {}\n\n{}\n",
+                email.to, email.subject, email.body
+            );
+
+            tokio::fs::create_dir_all(&self.dir)
+                .await
+                .map_err(|e| MailError::Send(e.to_string()))?;
+            tokio::fs::write(&path, content)
+                .await
+                .map_err(|e| MailError::Send(e.to_string()))?;
+            tracing::info!(
+                "邮件「{}」已写入发件箱 {}，收件人 {}",
+                email.subject,
+                path.display(),
+                email.to
+            );
+            Ok(())
+        })
+    }
+}
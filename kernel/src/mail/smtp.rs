@@ -0,0 +1,69 @@
+use crate::config::MailConfig;
+use crate::mail::{Email, MailError, MailFuture, Mailer};
+use lettre::message::{Mailbox, header::ContentType};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+/// 通过 SMTP 服务器发送邮件
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// 根据邮件配置创建，只校验配置，不连接服务器
+    pub fn new(config: &MailConfig) -> Result<Self, MailError> {
+        if config.smtp_host.is_empty() {
+            return Err(MailError::Build("未配置 MAIL_SMTP_HOST".to_string()));
+        }
+        let from = config
+            .from
+            .parse::<Mailbox>()
+            .map_err(|e| MailError::Address(format!("{}: {}", config.from, e)))?;
+
+        let mut builder = match config.smtp_tls.as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host),
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host),
+            _ => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                &config.smtp_host,
+            )),
+        }
+        .map_err(|e| MailError::Build(e.to_string()))?
+        .port(config.smtp_port);
+        if !config.smtp_username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                config.smtp_username.clone(),
+                config.smtp_password.clone(),
+            ));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send<'a>(&'a self, email: &'a Email) -> MailFuture<'a> {
+        Box::pin(async move {
+            let to = email
+                .to
+                .parse::<Mailbox>()
+                .map_err(|e| MailError::Address(format!("{}: {}", email.to, e)))?;
+            let message = Message::builder()
+                .from(self.from.clone())
+                .to(to)
+                .subject(email.subject.as_str())
+                .header(ContentType::TEXT_PLAIN)
+                .body(email.body.clone())
+                .map_err(|e| MailError::Build(e.to_string()))?;
+
+            self.transport
+                .send(message)
+                .await
+                .map_err(|e| MailError::Send(e.to_string()))?;
+            Ok(())
+        })
+    }
+}
@@ -1,7 +1,7 @@
+use crate::redis::RedisResult;
+use redis::aio::{MultiplexedConnection, PubSub};
 use redis::{Client, RedisError};
-use redis::aio::MultiplexedConnection;
 use thiserror::Error;
-use crate::redis::RedisResult;
 
 // Redis 操作专用错误类型（不实现 IntoResponse）
 #[derive(Error, Debug)]
@@ -47,6 +47,14 @@ impl RedisPool {
             .map_err(|e| RedisServiceError::ConnectionError(e.to_string()))
     }
 
+    // 获取发布订阅连接（独占连接，不能与普通命令复用）
+    pub async fn get_pubsub(&self) -> RedisResult<PubSub> {
+        self.client
+            .get_async_pubsub()
+            .await
+            .map_err(|e| RedisServiceError::ConnectionError(e.to_string()))
+    }
+
     // 测试连接
     pub async fn test_connection(&self) -> RedisResult<()> {
         let mut conn = self.get_connection().await?;
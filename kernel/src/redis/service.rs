@@ -2,6 +2,15 @@ use crate::redis::model::RedisServiceError;
 use crate::redis::{RedisResult, get_redis_pool};
 use redis::{AsyncCommands, RedisError, aio::MultiplexedConnection};
 
+// 递增计数并在首次递增时设置过期时间，保证计数键一定会过期
+const INCR_WITH_TTL_SCRIPT: &str = r#"
+local count = redis.call('INCR', KEYS[1])
+if count == 1 then
+    redis.call('EXPIRE', KEYS[1], ARGV[1])
+end
+return count
+"#;
+
 // Redis 服务
 pub struct RedisService;
 
@@ -23,6 +32,21 @@ impl RedisService {
         .map_err(RedisServiceError::OperationError)
     }
 
+    // 键不存在时设置值并指定过期时间，返回是否设置成功
+    pub async fn set_nx(key: &str, value: &str, ttl_seconds: u64) -> RedisResult<bool> {
+        let mut conn = Self::get_conn().await?;
+        let result: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_seconds)
+            .query_async(&mut conn)
+            .await
+            .map_err(RedisServiceError::OperationError)?;
+        Ok(result.is_some())
+    }
+
     // 获取值
     pub async fn get(key: &str) -> RedisResult<Option<String>> {
         let mut conn = Self::get_conn().await?;
@@ -31,6 +55,16 @@ impl RedisService {
             .map_err(RedisServiceError::OperationError)
     }
 
+    // 获取值并删除键（原子操作，需要 Redis 6.2+）
+    pub async fn get_del(key: &str) -> RedisResult<Option<String>> {
+        let mut conn = Self::get_conn().await?;
+        redis::cmd("GETDEL")
+            .arg(key)
+            .query_async(&mut conn)
+            .await
+            .map_err(RedisServiceError::OperationError)
+    }
+
     // 删除键
     pub async fn delete(key: &str) -> RedisResult<()> {
         let mut conn = Self::get_conn().await?;
@@ -47,6 +81,17 @@ impl RedisService {
             .map_err(RedisServiceError::OperationError)
     }
 
+    // 递增计数器，首次递增时设置过期时间（固定窗口计数）
+    pub async fn incr_with_ttl(key: &str, ttl_seconds: u64) -> RedisResult<i64> {
+        let mut conn = Self::get_conn().await?;
+        redis::Script::new(INCR_WITH_TTL_SCRIPT)
+            .key(key)
+            .arg(ttl_seconds)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(RedisServiceError::OperationError)
+    }
+
     // 检查键是否存在
     pub async fn exists(key: &str) -> RedisResult<bool> {
         let mut conn = Self::get_conn().await?;
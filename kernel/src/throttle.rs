@@ -0,0 +1,118 @@
+use crate::redis::get_redis_pool;
+use crate::redis::service::RedisService;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 本地计数超过该数量时清理已过期的窗口
+const LOCAL_PRUNE_THRESHOLD: usize = 10_000;
+
+/// 本进程内的计数窗口
+struct LocalWindow {
+    start: u64,
+    window: u64,
+    count: u32,
+}
+
+/// 本进程内的计数：key -> 计数窗口
+static LOCAL_COUNTERS: OnceLock<Mutex<HashMap<String, LocalWindow>>> = OnceLock::new();
+
+/// 固定窗口限流，返回本次是否允许
+///
+/// 配置了 redis 时多个节点共享计数，redis 未配置或不可用时退回本进程内计数；limit 为 0 表示不限制
+pub async fn allow(key: &str, limit: u32, window: u64) -> bool {
+    if limit == 0 {
+        return true;
+    }
+
+    if get_redis_pool().is_ok() {
+        match RedisService::incr_with_ttl(&format!("throttle:{}", key), window).await {
+            Ok(count) => return count <= limit as i64,
+            Err(e) => tracing::warn!("限流计数失败，使用本地计数: {}", e),
+        }
+    }
+
+    allow_local(key, limit, window)
+}
+
+fn allow_local(key: &str, limit: u32, window: u64) -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut counters = LOCAL_COUNTERS.get_or_init(Default::default).lock().unwrap();
+
+    if counters.len() > LOCAL_PRUNE_THRESHOLD {
+        counters.retain(|_, counter| counter.start + counter.window > now);
+    }
+
+    let counter = counters.entry(key.to_string()).or_insert(LocalWindow {
+        start: now,
+        window,
+        count: 0,
+    });
+    if counter.start + counter.window <= now {
+        counter.start = now;
+        counter.window = window;
+        counter.count = 0;
+    }
+    counter.count += 1;
+    counter.count <= limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(prefix: &str) -> String {
+        format!("{}:{}", prefix, uuid::Uuid::new_v4())
+    }
+
+    #[tokio::test]
+    async fn rejects_after_limit_within_window() {
+        let key = key("reset:email");
+        for _ in 0..3 {
+            assert!(allow(&key, 3, 3600).await);
+        }
+        assert!(!allow(&key, 3, 3600).await);
+        assert!(!allow(&key, 3, 3600).await);
+    }
+
+    #[tokio::test]
+    async fn zero_limit_is_unlimited() {
+        let key = key("reset:ip");
+        for _ in 0..100 {
+            assert!(allow(&key, 0, 3600).await);
+        }
+    }
+
+    #[tokio::test]
+    async fn keys_are_counted_independently() {
+        let ip = key("reset:ip");
+        let email = key("reset:email");
+        assert!(allow(&email, 1, 3600).await);
+        assert!(!allow(&email, 1, 3600).await);
+
+        // 同一IP申请其他邮箱不受该邮箱计数影响
+        assert!(allow(&ip, 10, 3600).await);
+        assert!(allow(&key("reset:email"), 1, 3600).await);
+    }
+
+    #[tokio::test]
+    async fn window_expiry_resets_count() {
+        let key = key("reset:email");
+        assert!(allow(&key, 1, 3600).await);
+        assert!(!allow(&key, 1, 3600).await);
+
+        // 模拟窗口到期
+        LOCAL_COUNTERS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap()
+            .get_mut(&key)
+            .unwrap()
+            .start -= 3600;
+        assert!(allow(&key, 1, 3600).await);
+        assert!(!allow(&key, 1, 3600).await);
+    }
+}
@@ -0,0 +1,225 @@
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{HeaderMap, header::AUTHORIZATION, request::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use common::utils::response::ApiResponse;
+use database::entity::sys_user::{self, UserStatus};
+use database::repository::{sys_ban_repository, sys_user_repository};
+use kernel::auth::revocation;
+use kernel::auth::token::{Claims, TokenKind, TokenService};
+use std::convert::Infallible;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 当前请求的用户，由 [`authenticate`] 中间件解析令牌后放入请求扩展
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    /// 用户ID（令牌 sub），游客为随机ID，与 websocket 客户端ID一致
+    pub id: String,
+    pub claims: Claims,
+    /// 登录用户的账号信息，游客或未配置数据库时为空
+    pub account: Option<sys_user::Model>,
+}
+
+impl CurrentUser {
+    pub fn is_guest(&self) -> bool {
+        self.claims.kind == TokenKind::Guest
+    }
+
+    /// 登录用户的账号ID，游客为空
+    pub fn user_id(&self) -> Option<i32> {
+        match self.claims.kind {
+            TokenKind::Guest => None,
+            _ => self.id.parse().ok(),
+        }
+    }
+}
+
+/// 认证失败，统一以 ApiResponse 返回错误码和提示
+#[derive(Debug)]
+pub struct AuthRejection {
+    pub code: i32,
+    pub message: String,
+}
+
+impl AuthRejection {
+    fn new(code: i32, message: &str) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for AuthRejection {
+    fn into_response(self) -> Response {
+        ApiResponse::<()>::error(self.code, &self.message).into_response()
+    }
+}
+
+/// 可选的当前用户，请求未携带令牌时为空
+#[derive(Debug, Clone)]
+pub struct OptionalUser(pub Option<CurrentUser>);
+
+/// 认证中间件，按 Router 挂载：`.layer(middleware::from_fn(authenticate))`
+///
+/// 携带 `Authorization: Bearer` 时校验令牌并加载用户，令牌无效、已吊销或账号不可用时直接拒绝；
+/// 未携带时放行，由 [`CurrentUser`] / [`OptionalUser`] 提取器决定是否必须登录
+pub async fn authenticate(mut request: Request, next: Next) -> Response {
+    let token = match bearer_token(request.headers()) {
+        Ok(Some(token)) => token.to_string(),
+        Ok(None) => return next.run(request).await,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    match load_user(&token).await {
+        Ok(user) => {
+            request.extensions_mut().insert(user);
+            next.run(request).await
+        }
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
+/// 解析 `Authorization: Bearer <token>`，未携带时返回 None
+fn bearer_token(headers: &HeaderMap) -> Result<Option<&str>, AuthRejection> {
+    let Some(value) = headers.get(AUTHORIZATION) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|token| Some(token.trim()))
+        .ok_or_else(|| AuthRejection::new(401, "认证信息格式错误"))
+}
+
+/// 校验令牌并加载登录用户的账号
+async fn load_user(token: &str) -> Result<CurrentUser, AuthRejection> {
+    let claims = TokenService::global()
+        .verify(token, &[TokenKind::Access, TokenKind::Guest])
+        .map_err(|e| AuthRejection::new(401, &e.to_string()))?;
+    revocation::check(&claims)
+        .await
+        .map_err(|e| AuthRejection::new(e.code(), &e.to_string()))?;
+    check_ban(&claims.sub, claims.ip.as_deref()).await?;
+
+    // 游客没有账号；未配置数据库时只校验令牌
+    let mut account = None;
+    if claims.kind == TokenKind::Access && database::get_db().is_some() {
+        let Ok(user_id) = claims.sub.parse::<i32>() else {
+            return Err(AuthRejection::new(401, "令牌无效"));
+        };
+        let user = match sys_user_repository::find_by_id(user_id).await {
+            Ok(Some(user)) => user,
+            Ok(None) => return Err(AuthRejection::new(401, "登录已失效，请重新登录")),
+            Err(e) => {
+                tracing::error!("加载用户 {} 失败: {}", user_id, e);
+                return Err(AuthRejection::new(500, "加载用户失败"));
+            }
+        };
+        if user.status != UserStatus::Active {
+            return Err(AuthRejection::new(403, "账号不可用"));
+        }
+        account = Some(user);
+    }
+
+    Ok(CurrentUser {
+        id: claims.sub.clone(),
+        claims,
+        account,
+    })
+}
+
+/// 检查客户端（令牌 sub）是否处于封禁中，未配置数据库时不检查
+///
+/// 游客令牌同时传入领取身份时的IP（[`Claims::ip`]），封禁游客时一并封禁的IP上领取的其他游客身份同样被拒绝
+pub async fn check_ban(sub: &str, ip: Option<&str>) -> Result<(), AuthRejection> {
+    if database::get_db().is_none() {
+        return Ok(());
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    match sys_ban_repository::find_active(sub, ip, now).await {
+        Ok(None) => Ok(()),
+        Ok(Some(ban)) => Err(AuthRejection::new(403, ban_message(ban.expires_at))),
+        Err(e) => {
+            tracing::error!("查询客户端 {} 的封禁记录失败: {}", sub, e);
+            Err(AuthRejection::new(503, "认证服务暂不可用，请稍后再试"))
+        }
+    }
+}
+
+/// 封禁提示，区分限时封禁和永久封禁
+fn ban_message(expires_at: Option<i64>) -> &'static str {
+    match expires_at {
+        Some(_) => "你已被封禁，请在封禁到期后再试",
+        None => "你已被永久封禁",
+    }
+}
+
+impl<S> FromRequestParts<S> for CurrentUser
+where
+    S: Send + Sync,
+{
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CurrentUser>()
+            .cloned()
+            .ok_or_else(|| AuthRejection::new(401, "请先登录"))
+    }
+}
+
+impl<S> FromRequestParts<S> for OptionalUser
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(OptionalUser(parts.extensions.get::<CurrentUser>().cloned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderValue, Request};
+
+    fn parts() -> Parts {
+        Request::new(()).into_parts().0
+    }
+
+    #[tokio::test]
+    async fn current_user_requires_identity() {
+        // 未通过认证中间件（未携带或携带无效令牌）的请求没有当前用户
+        let rejection = CurrentUser::from_request_parts(&mut parts(), &())
+            .await
+            .unwrap_err();
+        assert_eq!(rejection.code, 401);
+
+        let OptionalUser(user) = OptionalUser::from_request_parts(&mut parts(), &())
+            .await
+            .unwrap();
+        assert!(user.is_none());
+    }
+
+    #[test]
+    fn parses_bearer_token() {
+        let mut headers = HeaderMap::new();
+        assert!(matches!(bearer_token(&headers), Ok(None)));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer  abc "));
+        assert_eq!(bearer_token(&headers).unwrap(), Some("abc"));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Basic abc"));
+        assert_eq!(bearer_token(&headers).unwrap_err().code, 401);
+    }
+}
@@ -0,0 +1,79 @@
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use common::utils::response::ApiResponse;
+use kernel::config::server_config;
+use std::net::{IpAddr, SocketAddr};
+
+/// 客户端IP：默认取连接的对端地址，开启 `SERVER_TRUST_PROXY` 时取 `X-Forwarded-For` 中由可信代理追加的地址
+///
+/// 每层代理把它看到的对端地址追加到末尾，最左边的地址可以由客户端任意伪造，
+/// 因此从右往左数第 `SERVER_TRUSTED_PROXIES` 个地址才是最外层代理看到的客户端地址
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let config = server_config();
+        if config.trust_proxy
+            && let Some(ip) = parts
+                .headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| forwarded_ip(v, config.trusted_proxies))
+        {
+            return Ok(ClientIp(ip));
+        }
+
+        match parts.extensions.get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(addr)) => Ok(ClientIp(addr.ip())),
+            None => Err(ApiResponse::<()>::error(500, "无法获取客户端地址").into_response()),
+        }
+    }
+}
+
+/// 取 X-Forwarded-For 中从右往左数第 hops 个地址，地址数不足时取最左边的地址
+fn forwarded_ip(header: &str, hops: usize) -> Option<IpAddr> {
+    if hops == 0 {
+        return None;
+    }
+    let entries: Vec<&str> = header.split(',').map(str::trim).collect();
+    entries[entries.len().saturating_sub(hops)].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn takes_address_appended_by_trusted_proxy() {
+        assert_eq!(forwarded_ip("203.0.113.7", 1), ip("203.0.113.7"));
+        // 两层代理：外层代理追加客户端地址，内层代理追加外层代理地址
+        assert_eq!(forwarded_ip("203.0.113.7, 10.0.0.2", 2), ip("203.0.113.7"));
+        assert_eq!(forwarded_ip("203.0.113.7", 3), ip("203.0.113.7"));
+        assert_eq!(forwarded_ip("203.0.113.7", 0), None);
+    }
+
+    #[test]
+    fn ignores_spoofed_entries() {
+        // 客户端自带的 X-Forwarded-For 位于左侧，代理把真实地址追加到末尾
+        assert_eq!(forwarded_ip("1.2.3.4, 203.0.113.7", 1), ip("203.0.113.7"));
+        assert_eq!(
+            forwarded_ip("1.2.3.4, 5.6.7.8, 203.0.113.7, 10.0.0.2", 2),
+            ip("203.0.113.7")
+        );
+        // 代理追加的地址无法解析时不采用伪造的地址
+        assert_eq!(forwarded_ip("1.2.3.4, unknown", 1), None);
+    }
+}
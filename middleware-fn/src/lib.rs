@@ -1 +1,4 @@
-pub mod request;
\ No newline at end of file
+pub mod auth;
+pub mod client_ip;
+pub mod permission;
+pub mod request;
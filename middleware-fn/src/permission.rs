@@ -0,0 +1,155 @@
+use crate::auth::CurrentUser;
+use axum::{
+    extract::{Request, State},
+    middleware::{self, FromFnLayer, Next},
+    response::{IntoResponse, Response},
+};
+use common::utils::response::ApiResponse;
+use database::repository::permission_repository;
+use std::{fmt::Display, future::Future, pin::Pin};
+
+/// 权限校验函数，使用函数指针以便 [`require_permission`] 返回具名类型
+type PermissionCheck =
+    fn(State<&'static str>, Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send>>;
+
+/// 要求当前用户拥有指定权限，按路由挂载：`.route_layer(require_permission("connections:read"))`
+///
+/// 依赖 [`crate::auth::authenticate`] 解析出的当前用户，权限来自用户角色（`sys_user_role` / `sys_role_permission`）
+pub fn require_permission(
+    permission: &'static str,
+) -> FromFnLayer<PermissionCheck, &'static str, (State<&'static str>, Request)> {
+    middleware::from_fn_with_state(permission, check_permission as PermissionCheck)
+}
+
+fn check_permission(
+    State(permission): State<&'static str>,
+    request: Request,
+    next: Next,
+) -> Pin<Box<dyn Future<Output = Response> + Send>> {
+    Box::pin(async move {
+        let user = request.extensions().get::<CurrentUser>();
+        let authorized = authorize(user, permission, |user_id| async move {
+            // 未配置数据库时无法加载权限
+            if database::get_db().is_none() {
+                return Ok(false);
+            }
+            permission_repository::user_has_permission(user_id, permission).await
+        })
+        .await;
+
+        match authorized {
+            Ok(()) => next.run(request).await,
+            Err(response) => response,
+        }
+    })
+}
+
+/// 校验当前用户是否拥有权限，lookup 按账号ID查询权限
+async fn authorize<F, Fut, E>(
+    user: Option<&CurrentUser>,
+    permission: &str,
+    lookup: F,
+) -> Result<(), Response>
+where
+    F: FnOnce(i32) -> Fut,
+    Fut: Future<Output = Result<bool, E>>,
+    E: Display,
+{
+    let Some(user) = user else {
+        return Err(ApiResponse::<()>::error(401, "请先登录").into_response());
+    };
+    // 游客没有角色
+    let Some(user_id) = user.user_id() else {
+        return Err(ApiResponse::<()>::error(403, "没有操作权限").into_response());
+    };
+
+    match lookup(user_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            tracing::warn!("用户 {} 缺少权限 {}", user_id, permission);
+            Err(ApiResponse::<()>::error(403, "没有操作权限").into_response())
+        }
+        Err(e) => {
+            tracing::error!("加载用户 {} 的权限失败: {}", user_id, e);
+            Err(ApiResponse::<()>::error(500, "加载权限失败").into_response())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::auth::token::{Claims, TokenKind};
+
+    fn user(id: &str, kind: TokenKind) -> CurrentUser {
+        CurrentUser {
+            id: id.to_string(),
+            claims: Claims {
+                sub: id.to_string(),
+                kind,
+                jti: "jti".to_string(),
+                sid: None,
+                ip: None,
+                iss: "test".to_string(),
+                iat: 0,
+                exp: u64::MAX,
+            },
+            account: None,
+        }
+    }
+
+    /// 按授予的权限查询，记录查询的账号ID
+    async fn check(user: Option<&CurrentUser>, granted: &[&str]) -> Result<(), i32> {
+        let granted: Vec<String> = granted.iter().map(|p| p.to_string()).collect();
+        let result = authorize(user, "bans:manage", |user_id| async move {
+            assert_eq!(user_id, 7);
+            Ok::<_, String>(granted.iter().any(|p| p == "bans:manage"))
+        })
+        .await;
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(response) => Err(code(response).await),
+        }
+    }
+
+    async fn code(response: Response) -> i32 {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        body["code"].as_i64().unwrap() as i32
+    }
+
+    #[tokio::test]
+    async fn requires_login() {
+        assert_eq!(check(None, &["bans:manage"]).await, Err(401));
+    }
+
+    #[tokio::test]
+    async fn guests_have_no_permissions() {
+        // 游客ID不会被当作账号ID查询权限
+        let guest = user("7", TokenKind::Guest);
+        assert_eq!(check(Some(&guest), &["bans:manage"]).await, Err(403));
+    }
+
+    #[tokio::test]
+    async fn checks_granted_permissions() {
+        let admin = user("7", TokenKind::Access);
+        assert_eq!(check(Some(&admin), &["bans:read"]).await, Err(403));
+        assert_eq!(
+            check(Some(&admin), &["bans:read", "bans:manage"]).await,
+            Ok(())
+        );
+    }
+
+    #[tokio::test]
+    async fn lookup_failure_is_an_error() {
+        let admin = user("7", TokenKind::Access);
+        let result = authorize(Some(&admin), "bans:manage", |_| async {
+            Err::<bool, _>("数据库不可用")
+        })
+        .await;
+        assert_eq!(code(result.unwrap_err()).await, 500);
+    }
+}
@@ -1,7 +1,10 @@
 use axum::{
     Json,
     extract::{OriginalUri, Request},
-    http::StatusCode,
+    http::{
+        HeaderMap, HeaderName, HeaderValue, StatusCode, Uri,
+        header::{AUTHORIZATION, COOKIE, Entry, PROXY_AUTHORIZATION, SET_COOKIE},
+    },
     middleware::Next,
     response::Response,
 };
@@ -13,6 +16,11 @@ use tracing::info;
 
 use serde_json::json;
 
+/// 日志中隐藏取值的查询参数（websocket 握手通过查询参数携带令牌）
+const SENSITIVE_QUERY_PARAMS: [&str; 2] = ["token", "access_token"];
+/// 日志中隐藏取值的请求头
+const SENSITIVE_HEADERS: [HeaderName; 4] = [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE, SET_COOKIE];
+
 pub async fn logging_middleware(
     OriginalUri(original_uri): OriginalUri, // 原始地址
     request: Request,
@@ -27,14 +35,12 @@ pub async fn logging_middleware(
 
     let method = request.method().clone();
     // let uri = request.uri().clone();
-    let headers = request.headers().clone();
+    let uri = redact_uri(&original_uri);
+    let headers = redact_headers(request.headers());
     // 记录请求开始时间
     let start = Instant::now();
     // 打印请求信息
-    info!(
-        "[Request] {} {} - Headers: {:?}",
-        method, original_uri, headers
-    );
+    info!("[Request] {} {} - Headers: {:?}", method, uri, headers);
 
     // 处理请求
     let response = next.run(request).await;
@@ -44,7 +50,7 @@ pub async fn logging_middleware(
     info!(
         "[Response] {} {} - Status: {} - Duration: {:?}",
         method,
-        original_uri,
+        uri,
         response.status(),
         duration
     );
@@ -52,6 +58,33 @@ pub async fn logging_middleware(
     Ok(response)
 }
 
+/// 隐藏查询参数中令牌的取值，用于日志输出
+fn redact_uri(uri: &Uri) -> String {
+    let Some(query) = uri.query() else {
+        return uri.to_string();
+    };
+    let query: Vec<String> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if SENSITIVE_QUERY_PARAMS.contains(&name) => format!("{}=***", name),
+            _ => pair.to_string(),
+        })
+        .collect();
+
+    format!("{}?{}", uri.path(), query.join("&"))
+}
+
+/// 隐藏认证信息和 Cookie 的取值，用于日志输出
+fn redact_headers(headers: &HeaderMap) -> HeaderMap {
+    let mut headers = headers.clone();
+    for name in SENSITIVE_HEADERS {
+        if let Entry::Occupied(mut entry) = headers.entry(name) {
+            entry.insert(HeaderValue::from_static("***"));
+        }
+    }
+    headers
+}
+
 /// 限流，每秒超过100个就延迟
 pub async fn rate_limiter(request: Request, next: Next) -> Result<Response, StatusCode> {
     // 简单的计数器限流
@@ -72,3 +105,41 @@ pub async fn rate_limiter(request: Request, next: Next) -> Result<Response, Stat
     // 每秒清零（实际应用中需要更复杂的逻辑）
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_token_query() {
+        let uri: Uri = "/ws?token=eyJhbGciOi.abc.def&protocol=2".parse().unwrap();
+        assert_eq!(redact_uri(&uri), "/ws?token=***&protocol=2");
+
+        let uri: Uri = "/api/x?a=1&access_token=secret".parse().unwrap();
+        assert_eq!(redact_uri(&uri), "/api/x?a=1&access_token=***");
+    }
+
+    #[test]
+    fn redacts_sensitive_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        headers.append(COOKIE, HeaderValue::from_static("a=1"));
+        headers.append(COOKIE, HeaderValue::from_static("b=2"));
+        headers.insert("user-agent", HeaderValue::from_static("curl/8"));
+
+        let redacted = redact_headers(&headers);
+        assert_eq!(redacted[AUTHORIZATION], "***");
+        assert_eq!(redacted.get_all(COOKIE).iter().count(), 1);
+        assert_eq!(redacted[COOKIE], "***");
+        assert_eq!(redacted["user-agent"], "curl/8");
+        assert!(!format!("{:?}", redacted).contains("secret"));
+    }
+
+    #[test]
+    fn keeps_other_queries() {
+        let uri: Uri = "/api/chat?before=10&limit=20&tokens=1".parse().unwrap();
+        assert_eq!(redact_uri(&uri), "/api/chat?before=10&limit=20&tokens=1");
+        let uri: Uri = "/api/chat".parse().unwrap();
+        assert_eq!(redact_uri(&uri), "/api/chat");
+    }
+}